@@ -1,21 +1,40 @@
-use std::collections::VecDeque;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::Instant;
 
+use crossbeam_channel::Sender;
 use failure::*;
 use shiplift::rep::{Info, Version};
 use termion::event::Key;
 use tui::{
+    buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     widgets::{Paragraph, Text, Widget},
     Frame, Terminal,
 };
 
-use crate::docker::DockerExecutor;
+use crate::bulk::{self, Confirm, ContainerOperation, Target};
+use crate::cli::Options;
+use crate::config::Config;
+use crate::connection::Connection;
+use crate::context::Context;
+use crate::docker::{
+    ConnectionConfig, DeleteOptions, DockerBackend, DockerExecutor, ImageDeleteOptions, ListFilter,
+    ReadOnlyDocker, Subscription,
+};
+use crate::events::{DockerEvent, MAX_EVENTS};
+use crate::images::{ImageOperation, ImageTarget};
+use crate::keys::{Action, Keymap};
+use crate::shell;
+use crate::theme;
 use crate::views::{
-    AppLogsView, ContainerInfo, ContainerListView, DockerInfo, HelpView, ImagesListView, View,
-    ViewType,
+    Answer, AppLogsView, ContainerColumn, ContainerGraphsView, ContainerInfo, ContainerListView,
+    ContainerLogsView, ContainerStatsView, ContextsView, Dialog, DockerInfo, Draw, EventsView,
+    HelpView, ImageColumn, ImagesListView, View, ViewType,
 };
+use crate::worker::{Pending, Worker};
 use crate::Backend;
 
 // Stolen from clap
@@ -31,63 +50,541 @@ macro_rules! crate_version {
     };
 }
 
+/// Command run by default when opening a shell in a container
+const DEFAULT_EXEC_COMMAND: &str = "/bin/sh";
+
 /// The event type used in the main event loop of the application.
 pub enum AppEvent {
     /// Represents a key press
     Input(Key),
+    /// Sent periodically to automatically refresh the current view
+    Tick,
+    /// Sent by the background worker when it has completed a job, whose result can be picked up by
+    /// calling `App::update()`
+    Refreshed,
+    /// An event reported by the Docker daemon
+    DockerEvent(DockerEvent),
+    /// Sent when the connection to the Docker daemon has been lost, with the reason
+    Disconnected(String),
+    /// Sent when rkr should suspend itself (SIGTSTP)
+    Suspend,
+    /// Sent when rkr has been continued after being stopped (SIGCONT)
+    Continued,
+    /// Sent when rkr should exit (SIGTERM, SIGINT or SIGHUP)
+    Terminate,
+}
+
+/// A context being switched to, waiting for its daemon to respond.
+struct PendingSwitch {
+    context: String,
+    docker: Arc<dyn DockerBackend>,
+    worker: Worker,
+    result: Pending<Result<(Version, Info), Error>>,
+}
+
+/// An operation, with what it applies to.
+enum Operation {
+    Containers(ContainerOperation, Vec<Target>),
+    Image(ImageOperation, ImageTarget),
+}
+
+impl Operation {
+    /// Returns the name of the operation, as used in the `[confirm]` table of the configuration.
+    fn name(&self) -> &'static str {
+        match *self {
+            Operation::Containers(operation, _) => operation.name(),
+            Operation::Image(ref operation, _) => operation.name(),
+        }
+    }
+
+    /// Returns `true` if the operation applies to running containers.
+    fn is_running(&self) -> bool {
+        match *self {
+            Operation::Containers(_, ref targets) => targets.iter().any(|t| t.running),
+            Operation::Image(..) => false,
+        }
+    }
+}
+
+/// An operation waiting for the user to confirm it.
+struct Confirmation {
+    dialog: Dialog,
+    operation: Operation,
+}
+
+impl Confirmation {
+    /// Creates the dialog asking to confirm the operation.
+    fn new(operation: Operation, keymap: &Keymap) -> Confirmation {
+        let dialog = match operation {
+            Operation::Containers(operation, ref targets) => {
+                let title = match targets.as_slice() {
+                    [target] => format!("Really {} container {}?", operation.name(), target.name),
+                    _ => format!("Really {} {} containers?", operation.name(), targets.len()),
+                };
+                let items = targets
+                    .iter()
+                    .map(|t| {
+                        if t.running {
+                            format!("{} (running)", t.name)
+                        } else {
+                            t.name.clone()
+                        }
+                    })
+                    .collect();
+                let dialog = Dialog::new(title, items, keymap);
+                match operation {
+                    ContainerOperation::Delete(opts) => dialog
+                        .with_option('f', "Force, kill the running containers first", opts.force)
+                        .with_option('v', "Remove the anonymous volumes", opts.volumes),
+                    _ => dialog,
+                }
+            }
+            Operation::Image(ref operation, ref image) => {
+                let title = format!("Really {} image {}?", operation.verb(), image.name);
+                let dialog = Dialog::new(title, image.tags.clone(), keymap);
+                match *operation {
+                    ImageOperation::Delete(opts) => dialog
+                        .with_option(
+                            'f',
+                            "Force, even with several tags or stopped containers",
+                            opts.force,
+                        )
+                        .with_option('p', "Keep the untagged parent images", opts.no_prune),
+                    _ => dialog,
+                }
+            }
+        };
+        Confirmation { dialog, operation }
+    }
+
+    /// Returns the operation, with the options chosen in the dialog.
+    fn into_operation(self) -> Operation {
+        match self.operation {
+            Operation::Containers(ContainerOperation::Delete(_), targets) => {
+                let opts = DeleteOptions {
+                    force: self.dialog.is_checked('f'),
+                    volumes: self.dialog.is_checked('v'),
+                };
+                Operation::Containers(ContainerOperation::Delete(opts), targets)
+            }
+            Operation::Image(ImageOperation::Delete(_), image) => {
+                let opts = ImageDeleteOptions {
+                    force: self.dialog.is_checked('f'),
+                    no_prune: self.dialog.is_checked('p'),
+                };
+                Operation::Image(ImageOperation::Delete(opts), image)
+            }
+            operation => operation,
+        }
+    }
 }
 
 /// Contains the state of the application.
 pub struct App {
-    /// The client used to access the Docker daemon
-    docker: Arc<DockerExecutor>,
+    /// The client used to access the Docker daemon of the active context
+    docker: Arc<dyn DockerBackend>,
+    /// Performs the refreshes in the background
+    worker: Worker,
+    /// Used to notify the main loop of completed refreshes and Docker events
+    events_tx: Sender<AppEvent>,
+    /// Keeps the subscription to the events of the active daemon alive, while it is reachable
+    _subscription: Option<Subscription>,
     /// The current size of the application
     pub size: Rect,
-    /// Version info of the Docker daemon
-    docker_version: Version,
-    /// System info of the Docker daemon
-    info: Info,
+    /// The available contexts
+    contexts: Vec<Context>,
+    /// Name of the active context
+    context: String,
+    /// Clients of the contexts that have been used so far, by context name
+    backends: HashMap<String, Arc<dyn DockerBackend>>,
+    /// Context being switched to
+    pending_switch: Option<PendingSwitch>,
+    /// Whether the Docker daemon of the active context is reachable
+    connection: Connection,
+    /// Reconnection attempt in progress
+    pending_reconnect: Pending<Result<(Version, Info), Error>>,
+    /// Version info of the Docker daemon, unknown until it has been reached
+    docker_version: Option<Version>,
+    /// System info of the Docker daemon, unknown until it has been reached
+    info: Option<Info>,
+    /// System info being fetched in the background
+    pending_info: Pending<Result<Info, Error>>,
+    /// Whether the system info needs to be refreshed, following a Docker event
+    stale_info: bool,
+    /// Whether the current view needs to be refreshed, following a Docker event
+    stale_view: bool,
+    /// Most recent events reported by the Docker daemon, oldest first
+    events: VecDeque<DockerEvent>,
     /// View stack: The top (=front) of the stack is the view that is displayed
     view_stack: VecDeque<Box<dyn View>>,
     err_msg: Option<String>,
+    /// Outcome of the last operation, when it succeeded
+    message: Option<String>,
+    /// Operations applied to containers and images in the background, whose outcome is the
+    /// message to display, which is an error if they failed
+    pending_operations: Vec<Pending<Result<String, String>>>,
+    /// Operation waiting for the user to confirm it, whose dialog is displayed above the view
+    confirmation: Option<Confirmation>,
+    /// When each operation asks for confirmation, by name
+    confirm: HashMap<&'static str, Confirm>,
+    /// Command to run when opening a shell in a container
+    exec_command: Vec<String>,
+    /// Whether the container list only shows the running containers initially
+    only_running: bool,
+    /// Filters applied to the container list
+    filters: Vec<ListFilter>,
+    /// Whether the operations that change the state of the daemon are refused
+    read_only: bool,
+    /// Translates the key presses to actions
+    keymap: Keymap,
+    /// Columns of the container list
+    container_columns: Vec<ContainerColumn>,
+    /// Columns of the image list
+    image_columns: Vec<ImageColumn>,
+    /// Whether the screen needs to be cleared before the next draw, e.g. because something else
+    /// has been using the terminal
+    needs_clear: Cell<bool>,
 }
 
 impl App {
-    /// Create a new instance of `App`. It will initialize the Docker client and make a couple of
-    /// calls to the Docker daemon to get some system info and version info.
-    pub fn new() -> Result<App, Error> {
-        let docker = Arc::new(DockerExecutor::new()?);
-        let info = docker.info()?;
-        let docker_version = docker.version()?;
+    /// Create a new instance of `App`, using the given context. It will initialize the Docker
+    /// client and make a couple of calls to the Docker daemon to get some system info and version
+    /// info. If the daemon can't be reached, the application starts disconnected and keeps trying
+    /// to reach it in the background.
+    ///
+    /// `events` is used by the background worker to notify the main loop of completed refreshes.
+    pub fn new(
+        contexts: Vec<Context>,
+        context: &str,
+        options: &Options,
+        config: &Config,
+        events: Sender<AppEvent>,
+    ) -> Result<App, Error> {
+        let connection_config = &contexts
+            .iter()
+            .find(|c| c.name == context)
+            .ok_or_else(|| format_err!("Unknown context {}", context))?
+            .config;
+        let docker = client(connection_config, options.read_only)?;
+        let mut connection = Connection::Connected;
+        let (docker_version, info, subscription) = match docker
+            .version()
+            .and_then(|version| Ok((version, docker.info()?)))
+        {
+            Ok((version, info)) => (
+                Some(version),
+                Some(info),
+                Some(docker.events(events.clone())),
+            ),
+            Err(err) => {
+                warn!("Failed to connect to the Docker daemon: {}", err);
+                connection.disconnected(err.to_string(), Instant::now());
+                (None, None, None)
+            }
+        };
+        let mut backends = HashMap::new();
+        backends.insert(context.to_string(), docker.clone());
         let mut app = App {
+            worker: Worker::new(docker.clone(), events.clone()),
+            _subscription: subscription,
+            events_tx: events,
             docker,
             size: Rect::default(),
+            contexts,
+            context: context.to_string(),
+            backends,
+            pending_switch: None,
+            connection,
+            pending_reconnect: Pending::none(),
             docker_version,
             info,
+            pending_info: Pending::none(),
+            stale_info: false,
+            stale_view: false,
+            events: VecDeque::new(),
             view_stack: VecDeque::new(),
             err_msg: None,
+            message: None,
+            pending_operations: Vec::new(),
+            confirmation: None,
+            confirm: config.confirm.clone(),
+            exec_command: options
+                .exec_command
+                .as_deref()
+                .unwrap_or(DEFAULT_EXEC_COMMAND)
+                .split_whitespace()
+                .map(|s| s.to_string())
+                .collect(),
+            needs_clear: Cell::new(false),
+            only_running: options.only_running,
+            filters: options.filters.clone(),
+            read_only: options.read_only,
+            keymap: config.keymap.clone(),
+            container_columns: config.container_columns.clone(),
+            image_columns: config.image_columns.clone(),
         };
+        // The container list is always at the bottom of the stack
         app.new_view(ViewType::ContainerList);
+        let view = options.view.clone().unwrap_or(ViewType::ContainerList);
+        if view != ViewType::ContainerList {
+            app.new_view(view);
+        }
 
         Ok(app)
     }
 
     /// Refreshes the state of the application (i.e. list of containers, system information, etc).
+    /// The data is fetched in the background, and picked up by `update()` once available. While
+    /// the daemon is unreachable, this tries reconnecting to it right away instead.
     pub fn refresh(&mut self) {
-        let info = self.docker.info().unwrap();
-        self.info = info;
-        let docker = self.docker.clone();
-        self.current_view_mut().refresh(docker);
+        if !self.connection.is_connected() {
+            self.connection.retry_now(Instant::now());
+            return;
+        }
+        self.pending_info = self.worker.run(|docker| docker.info());
+        self.view_stack
+            .front_mut()
+            .expect("View stack is empty!")
+            .refresh(&self.worker);
+    }
+
+    /// Automatically refreshes the application. This is skipped if the previous refresh hasn't
+    /// completed yet, so that refreshes don't pile up when the daemon is slow, and while the daemon
+    /// is unreachable.
+    pub fn tick(&mut self) {
+        if self.worker.is_busy() || !self.connection.is_connected() {
+            return;
+        }
+        self.pending_info = self.worker.run(|docker| docker.info());
+        self.view_stack
+            .front_mut()
+            .expect("View stack is empty!")
+            .tick(&self.worker);
+    }
+
+    /// Handles an event reported by the Docker daemon. The refreshes it triggers are deferred
+    /// until the worker is idle, so that bursts of events only cause a single refresh.
+    pub fn handle_docker_event(&mut self, event: DockerEvent) {
+        debug!(
+            "Docker event: {} {} {}",
+            event.typ,
+            event.action,
+            event.actor()
+        );
+        if event.changes_state() && (event.typ == "container" || event.typ == "image") {
+            self.stale_info = true;
+        }
+        if self.current_view_mut().handle_docker_event(&event) {
+            self.stale_view = true;
+        }
+        self.events.push_back(event);
+        if self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    /// Handles the loss of the connection to the Docker daemon: the last known data stays on
+    /// screen, and reconnection attempts are made in `update()` with an exponential backoff.
+    pub fn handle_disconnect(&mut self, error: String) {
+        if self.connection.is_connected() {
+            warn!("Lost the connection to the Docker daemon: {}", error);
+        } else {
+            debug!("Docker daemon still unreachable: {}", error);
+        }
+        self._subscription = None;
+        self.pending_info = Pending::none();
+        self.stale_info = false;
+        self.stale_view = false;
+        self.connection.disconnected(error, Instant::now());
+    }
+
+    /// Starts a reconnection attempt if one is due, and completes it if the daemon has responded.
+    fn reconnect(&mut self) {
+        if self.connection.should_retry(Instant::now()) {
+            info!("Trying to reconnect to the Docker daemon");
+            self.pending_reconnect = self
+                .worker
+                .run(|docker| Ok((docker.version()?, docker.info()?)));
+        }
+        match self.pending_reconnect.take() {
+            Some(Ok((version, info))) => {
+                if self.connection.connected() {
+                    info!("Reconnected to the Docker daemon");
+                }
+                self._subscription = Some(self.docker.events(self.events_tx.clone()));
+                self.docker_version = Some(version);
+                self.info = Some(info);
+                self.err_msg = None;
+                // Missed events can't be replayed, so reload the current view
+                self.refresh();
+            }
+            Some(Err(err)) => self.handle_disconnect(err.to_string()),
+            None => {}
+        }
+    }
+
+    /// Starts switching to the given context. The switch completes in `update()` once its daemon
+    /// has responded, and the view stack is then rebuilt against it.
+    fn switch_context(&mut self, name: &str) {
+        if name == self.context {
+            return;
+        }
+        let context = match self.contexts.iter().find(|c| c.name == name) {
+            Some(context) => context,
+            None => {
+                self.err_msg = Some(format!("Unknown context {}", name));
+                return;
+            }
+        };
+        let docker = match self.backends.get(name) {
+            Some(docker) => docker.clone(),
+            None => match client(&context.config, self.read_only) {
+                Ok(docker) => {
+                    self.backends.insert(name.to_string(), docker.clone());
+                    docker
+                }
+                Err(err) => {
+                    error!("Failed to create a client for context {}: {}", name, err);
+                    self.err_msg = Some(format!("Failed to switch to context {}: {}", name, err));
+                    return;
+                }
+            },
+        };
+
+        info!("Switching to context {}", name);
+        let worker = Worker::new(docker.clone(), self.events_tx.clone());
+        let result = worker.run(|docker| Ok((docker.version()?, docker.info()?)));
+        self.pending_switch = Some(PendingSwitch {
+            context: name.to_string(),
+            docker,
+            worker,
+            result,
+        });
+    }
+
+    /// Completes the switch to another context, if its daemon has responded.
+    fn complete_switch(&mut self) {
+        let result = match self.pending_switch {
+            Some(ref mut switch) => match switch.result.take() {
+                Some(result) => result,
+                None => return,
+            },
+            None => return,
+        };
+        let switch = self.pending_switch.take().unwrap();
+        match result {
+            Ok((version, info)) => {
+                info!("Switched to context {}", switch.context);
+                self._subscription = Some(switch.docker.events(self.events_tx.clone()));
+                self.docker = switch.docker;
+                self.worker = switch.worker;
+                self.context = switch.context;
+                self.connection.connected();
+                self.pending_reconnect = Pending::none();
+                self.docker_version = Some(version);
+                self.info = Some(info);
+                self.pending_info = Pending::none();
+                self.stale_info = false;
+                self.stale_view = false;
+                self.events.clear();
+                self.err_msg = None;
+                self.view_stack.clear();
+                self.new_view(ViewType::ContainerList);
+                self.refresh();
+            }
+            Err(err) => {
+                error!("Failed to connect to context {}: {}", switch.context, err);
+                self.err_msg = Some(format!(
+                    "Failed to connect to context {}: {}",
+                    switch.context, err
+                ));
+            }
+        }
+    }
+
+    /// Picks up the data that has been fetched in the background since the last call.
+    pub fn update(&mut self) {
+        self.complete_switch();
+        self.reconnect();
+        if !self.worker.is_busy() && self.connection.is_connected() {
+            if self.stale_info {
+                self.stale_info = false;
+                self.pending_info = self.worker.run(|docker| docker.info());
+            }
+            if self.stale_view {
+                self.stale_view = false;
+                self.view_stack
+                    .front_mut()
+                    .expect("View stack is empty!")
+                    .refresh(&self.worker);
+            }
+        }
+        let outcomes: Vec<_> = self
+            .pending_operations
+            .iter_mut()
+            .filter_map(Pending::take)
+            .collect();
+        self.pending_operations.retain(Pending::is_waiting);
+        for outcome in outcomes {
+            match outcome {
+                Ok(message) => {
+                    self.err_msg = None;
+                    self.message = Some(message);
+                }
+                Err(message) => {
+                    self.err_msg = Some(message);
+                    self.message = None;
+                }
+            }
+            self.stale_view = true;
+        }
+        match self.pending_info.take() {
+            Some(Ok(info)) => self.info = Some(info),
+            Some(Err(err)) => {
+                error!("Failed to get system info: {}", err);
+                self.handle_disconnect(err.to_string());
+            }
+            None => {}
+        }
+        self.current_view_mut().update();
+    }
+
+    /// Returns the action the given key is bound to in the current view.
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.keymap.action(self.current_view().name(), key)
+    }
+
+    /// Returns `true` if the key presses go to the confirmation dialog or to some text being
+    /// typed in the current view, rather than being bound to actions.
+    pub fn captures_input(&self) -> bool {
+        self.confirmation.is_some() || self.current_view().captures_input()
     }
 
     /// Handles the given key press. Returns `false` to signify to the main loop that the
     /// application should exit.
     pub fn handle_input(&mut self, key: Key) -> bool {
+        // The confirmation dialog captures all the key presses until it is dismissed
+        if let Some(mut confirmation) = self.confirmation.take() {
+            match confirmation.dialog.handle_input(key, &self.keymap) {
+                Some(Answer::Confirmed) => self.run_operation(confirmation.into_operation()),
+                Some(Answer::Cancelled) => {}
+                None => self.confirmation = Some(confirmation),
+            }
+            return true;
+        }
+
         let docker = self.docker.clone();
-        let command = self
-            .handle_global_keys(key)
-            .or_else(|| self.current_view_mut().handle_input(key, docker))
-            .unwrap_or(AppCommand::NoOp);
+        let command = if self.current_view().captures_input() {
+            self.current_view_mut().handle_input(key, docker)
+        } else {
+            match self.action(key) {
+                Some(action) => self
+                    .handle_global_action(action)
+                    .or_else(|| self.current_view_mut().handle_action(action, docker)),
+                None => self.current_view_mut().handle_input(key, docker),
+            }
+        }
+        .unwrap_or(AppCommand::NoOp);
 
         match command {
             AppCommand::SwitchToView(view_type) => {
@@ -100,15 +597,67 @@ impl App {
             AppCommand::NoOp => { /* NoOp */ }
             AppCommand::ErrorMsg(msg) => self.err_msg = Some(msg),
             AppCommand::Refresh => self.refresh(),
+            AppCommand::RunOperation(operation, targets) => {
+                self.request_operation(Operation::Containers(operation, targets))
+            }
+            AppCommand::RunImageOperation(operation, image) => {
+                self.request_operation(Operation::Image(operation, image))
+            }
+            AppCommand::SwitchContext(name) => self.switch_context(&name),
+            AppCommand::Exec(ContainerId(id)) => {
+                info!("Opening a shell in container {}", id);
+                if let Err(err) = shell::run(&*self.docker, &id, &self.exec_command) {
+                    error!("Failed to exec in container: {}", err);
+                    self.err_msg = Some(format!("Failed to exec in container: {}", err));
+                }
+                self.needs_clear.set(true);
+            }
         }
 
         true
     }
 
+    /// Runs the operation, once the user has confirmed it if it needs to be.
+    fn request_operation(&mut self, operation: Operation) {
+        let needs_confirmation = self
+            .confirm
+            .get(operation.name())
+            .is_some_and(|confirm| confirm.is_needed(operation.is_running()));
+        if needs_confirmation {
+            self.confirmation = Some(Confirmation::new(operation, &self.keymap));
+        } else {
+            self.run_operation(operation);
+        }
+    }
+
+    /// Applies the operation in the background. Operations on containers run on their own thread,
+    /// as stopping many of them can take a while.
+    fn run_operation(&mut self, operation: Operation) {
+        let outcome = match operation {
+            Operation::Containers(operation, targets) => {
+                self.worker.run_in_parallel(move |docker| {
+                    let summary = bulk::run(docker, operation, targets);
+                    if summary.has_failures() {
+                        Err(summary.message())
+                    } else {
+                        Ok(summary.message())
+                    }
+                })
+            }
+            Operation::Image(operation, image) => {
+                self.worker.run(move |docker| operation.run(docker, &image))
+            }
+        };
+        self.pending_operations.push(outcome);
+    }
+
     /// Draws the application in the given terminal.
     pub fn draw(&self, t: &mut Terminal<Backend>) {
+        if self.needs_clear.replace(false) {
+            t.clear().unwrap();
+        }
         let size = t.size().unwrap();
-        let main_view_height = size.height - 2;
+        let main_view_height = size.height.saturating_sub(2);
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -124,8 +673,14 @@ impl App {
             // title bar
             self.draw_status_bar(&mut f, chunks[0]);
 
-            // current view
+            // current view, greyed out while its data can't be refreshed
             self.current_view().draw(&mut f, chunks[1]);
+            if !self.connection.is_connected() {
+                Dimmed.render(&mut f, chunks[1]);
+            }
+            if let Some(ref confirmation) = self.confirmation {
+                confirmation.dialog.draw(&mut f, chunks[1]);
+            }
 
             // Status message
             self.draw_status_message(&mut f, chunks[2]);
@@ -133,15 +688,39 @@ impl App {
         .unwrap();
     }
 
+    /// Makes the next draw redraw the whole screen, e.g. after rkr has been suspended.
+    pub fn force_redraw(&self) {
+        self.needs_clear.set(true);
+    }
+
     /// Instantiate a view of the type `view_type` and pushes it onto the view stack.
     fn new_view(&mut self, view_type: ViewType) {
         let new_view = match view_type {
-            ViewType::ContainerList => Box::new(ContainerListView::new()) as Box<dyn View>,
+            ViewType::ContainerList => {
+                let mut view = ContainerListView::new();
+                view.only_running = self.only_running;
+                view.filters = self.filters.clone();
+                view.columns = self.container_columns.clone();
+                Box::new(view) as Box<dyn View>
+            }
             ViewType::ContainerDetails(id) => Box::new(ContainerInfo::new(id)) as Box<dyn View>,
-            ViewType::ContainerLogs(id) => unimplemented!(), //Box::new(ContainerLogsView::new(id)) as Box<dyn View>,
+            ViewType::ContainerGraphs(id) => {
+                Box::new(ContainerGraphsView::new(id)) as Box<dyn View>
+            }
+            ViewType::ContainerLogs(id) => Box::new(ContainerLogsView::new(id)) as Box<dyn View>,
+            ViewType::ContainerStats => Box::new(ContainerStatsView::new()) as Box<dyn View>,
+            ViewType::Contexts => Box::new(ContextsView::new(
+                self.contexts.clone(),
+                self.context.clone(),
+            )) as Box<dyn View>,
             ViewType::DockerInfo => Box::new(DockerInfo::new()) as Box<dyn View>,
-            ViewType::Help => Box::new(HelpView::new()) as Box<dyn View>,
-            ViewType::ImagesList => Box::new(ImagesListView::new()) as Box<dyn View>,
+            ViewType::Events => Box::new(EventsView::new(self.events.clone())) as Box<dyn View>,
+            ViewType::Help => Box::new(HelpView::new(&self.keymap)) as Box<dyn View>,
+            ViewType::ImagesList => {
+                let mut view = ImagesListView::new();
+                view.columns = self.image_columns.clone();
+                Box::new(view) as Box<dyn View>
+            }
             ViewType::AppLogs => Box::new(AppLogsView::new()) as Box<dyn View>,
         };
 
@@ -186,59 +765,115 @@ impl App {
             .as_mut()
     }
 
-    /// Handle global shortcuts. If not handled here, then the current view will get a chance to
+    /// Handle global actions. If not handled here, then the current view will get a chance to
     /// handle it.
-    fn handle_global_keys(&mut self, key: Key) -> Option<AppCommand> {
-        match key {
-            Key::Char('q') => Some(AppCommand::ExitView),
-            Key::Char('i') => Some(AppCommand::SwitchToView(ViewType::ImagesList)),
-            Key::Char('v') => Some(AppCommand::SwitchToView(ViewType::DockerInfo)),
-            Key::Char('?') => Some(AppCommand::SwitchToView(ViewType::Help)),
-            Key::Char('L') => Some(AppCommand::SwitchToView(ViewType::AppLogs)),
-            Key::Char('R') => Some(AppCommand::Refresh),
+    fn handle_global_action(&mut self, action: Action) -> Option<AppCommand> {
+        match action {
+            Action::Quit => Some(AppCommand::ExitView),
+            Action::Images => Some(AppCommand::SwitchToView(ViewType::ImagesList)),
+            Action::Info => Some(AppCommand::SwitchToView(ViewType::DockerInfo)),
+            Action::Stats => Some(AppCommand::SwitchToView(ViewType::ContainerStats)),
+            Action::Events => Some(AppCommand::SwitchToView(ViewType::Events)),
+            Action::Contexts => Some(AppCommand::SwitchToView(ViewType::Contexts)),
+            Action::Help => Some(AppCommand::SwitchToView(ViewType::Help)),
+            Action::AppLogs => Some(AppCommand::SwitchToView(ViewType::AppLogs)),
+            Action::Refresh => Some(AppCommand::Refresh),
             _ => None,
         }
     }
 
     /// Draws the title bar at the top
     fn draw_status_bar(&self, t: &mut Frame<Backend>, rect: Rect) {
-        let green = Style::default().fg(Color::LightGreen);
-        let text = vec![
+        let theme = theme::current();
+        let accent = Style::default().fg(theme.accent);
+        let warning = Style::default().fg(theme.warning);
+        let host = self
+            .contexts
+            .iter()
+            .find(|c| c.name == self.context)
+            .map(|c| c.config.host.as_str())
+            .unwrap_or_default();
+        let (containers, images) = match self.info {
+            Some(ref info) => (info.containers.to_string(), info.images.to_string()),
+            None => ("?".to_string(), "?".to_string()),
+        };
+        let docker_version = match self.docker_version {
+            Some(ref version) => format!("docker v{} ({})", version.version, version.api_version),
+            None => "docker".to_string(),
+        };
+        let mut text = vec![
+            Text::styled(format!("[{}] ", self.context), warning),
             Text::raw(format!("Rocker v{}     ", crate_version!())),
-            Text::styled(format!("{}", self.info.containers), green),
+            Text::styled(containers, accent),
             Text::raw(" containers, "),
-            Text::styled(format!("{}", self.info.images), green),
+            Text::styled(images, accent),
             Text::raw(" images, "),
-            Text::raw(format!(
-                "docker v{} ({})",
-                self.docker_version.version, self.docker_version.api_version
-            )),
+            Text::raw(format!("{} on {}", docker_version, host)),
         ];
+        if self.read_only {
+            text.push(Text::styled(
+                " [read-only]",
+                Style::default().fg(theme.error),
+            ));
+        }
 
         Paragraph::new(text.iter())
             .wrap(true)
             .style(
                 Style::default()
-                    .bg(Color::Black)
-                    .fg(Color::White)
+                    .bg(theme.background)
+                    .fg(theme.foreground)
                     .modifier(Modifier::BOLD),
             )
             .render(t, rect);
     }
 
     fn draw_status_message(&self, t: &mut Frame<Backend>, rect: Rect) {
-        let text = if let Some(ref msg) = self.err_msg {
+        let theme = theme::current();
+        let text = if let Some(ref switch) = self.pending_switch {
+            Text::styled(
+                format!("Connecting to context {}...", switch.context),
+                Style::default().bg(theme.background).fg(theme.warning),
+            )
+        } else if let Connection::Disconnected {
+            ref error,
+            retry_at,
+            retrying,
+            ..
+        } = self.connection
+        {
+            let retry = if retrying {
+                "reconnecting...".to_string()
+            } else {
+                let now = Instant::now();
+                let secs = if retry_at > now {
+                    (retry_at - now).as_secs() + 1
+                } else {
+                    0
+                };
+                format!("retrying in {}s (R to retry now)", secs)
+            };
+            Text::styled(
+                format!("Disconnected from the Docker daemon ({}), {}", error, retry),
+                Style::default()
+                    .bg(theme.error)
+                    .fg(theme.foreground)
+                    .modifier(Modifier::BOLD),
+            )
+        } else if let Some(ref msg) = self.err_msg {
             Text::styled(
                 msg,
                 Style::default()
-                    .bg(Color::Red)
-                    .fg(Color::White)
+                    .bg(theme.error)
+                    .fg(theme.foreground)
                     .modifier(Modifier::BOLD),
             )
+        } else if let Some(ref msg) = self.message {
+            Text::styled(msg, Style::default().bg(theme.background).fg(theme.running))
         } else {
             Text::styled(
                 "No message",
-                Style::default().bg(Color::Black).fg(Color::White),
+                Style::default().bg(theme.background).fg(theme.foreground),
             )
         };
 
@@ -246,6 +881,30 @@ impl App {
     }
 }
 
+/// Creates a client for the given daemon, which refuses the operations that change its state in
+/// read-only mode.
+fn client(config: &ConnectionConfig, read_only: bool) -> Result<Arc<dyn DockerBackend>, Error> {
+    let docker: Arc<dyn DockerBackend> = Arc::new(DockerExecutor::new(config)?);
+    if read_only {
+        Ok(Arc::new(ReadOnlyDocker(docker)))
+    } else {
+        Ok(docker)
+    }
+}
+
+/// Greys out what has already been drawn in an area.
+struct Dimmed;
+
+impl Widget for Dimmed {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                buf.get_mut(x, y).set_fg(theme::current().disabled);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ContainerId(pub String);
 
@@ -256,4 +915,8 @@ pub enum AppCommand {
     NoOp,
     ErrorMsg(String),
     Refresh,
+    RunOperation(ContainerOperation, Vec<Target>),
+    RunImageOperation(ImageOperation, ImageTarget),
+    Exec(ContainerId),
+    SwitchContext(String),
 }
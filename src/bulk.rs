@@ -0,0 +1,353 @@
+//! Operations applied to several containers at once.
+use std::sync::Arc;
+use std::thread;
+
+use failure::*;
+
+use crate::app::ContainerId;
+use crate::docker::{DeleteOptions, DockerBackend};
+
+/// Names of the operations, as used in the `[confirm]` table of the configuration file
+pub const OPERATIONS: &[&str] = &[
+    "pause",
+    "unpause",
+    "stop",
+    "start",
+    "restart",
+    "delete",
+    "delete-image",
+];
+
+/// An operation that changes the state of a container.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContainerOperation {
+    Pause,
+    Unpause,
+    Stop,
+    Start,
+    Restart,
+    Delete(DeleteOptions),
+}
+
+impl ContainerOperation {
+    /// Applies the operation to the given container.
+    pub fn apply(self, docker: &dyn DockerBackend, id: &str) -> Result<(), Error> {
+        match self {
+            ContainerOperation::Pause => docker.container_pause(id),
+            ContainerOperation::Unpause => docker.container_unpause(id),
+            ContainerOperation::Stop => docker.container_stop(id),
+            ContainerOperation::Start => docker.container_start(id),
+            ContainerOperation::Restart => docker.container_restart(id),
+            ContainerOperation::Delete(ref opts) => docker.container_delete(id, opts),
+        }
+    }
+
+    /// Returns the name of the operation, which is also the verb used in messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            ContainerOperation::Pause => "pause",
+            ContainerOperation::Unpause => "unpause",
+            ContainerOperation::Stop => "stop",
+            ContainerOperation::Start => "start",
+            ContainerOperation::Restart => "restart",
+            ContainerOperation::Delete(_) => "delete",
+        }
+    }
+
+    fn past_participle(self) -> &'static str {
+        match self {
+            ContainerOperation::Pause => "Paused",
+            ContainerOperation::Unpause => "Unpaused",
+            ContainerOperation::Stop => "Stopped",
+            ContainerOperation::Start => "Started",
+            ContainerOperation::Restart => "Restarted",
+            ContainerOperation::Delete(_) => "Deleted",
+        }
+    }
+}
+
+/// A container an operation is applied to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub id: ContainerId,
+    /// Name of the container, used in the summary
+    pub name: String,
+    /// Whether the container is running or paused, in which case deleting it requires force
+    pub running: bool,
+}
+
+/// When an operation asks for confirmation before being applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Confirm {
+    Always,
+    Never,
+    /// Only when at least one of the containers is running
+    OnlyForRunning,
+}
+
+impl Confirm {
+    /// Parses a policy, as given in the configuration file.
+    pub fn parse(s: &str) -> Result<Confirm, Error> {
+        match s {
+            "always" => Ok(Confirm::Always),
+            "never" => Ok(Confirm::Never),
+            "only-for-running" => Ok(Confirm::OnlyForRunning),
+            _ => bail!(
+                "invalid value {}, expected always, never or only-for-running",
+                s
+            ),
+        }
+    }
+
+    /// Returns `true` if an operation needs to be confirmed, given whether some of the containers
+    /// it applies to are running.
+    pub fn is_needed(self, running: bool) -> bool {
+        match self {
+            Confirm::Always => true,
+            Confirm::Never => false,
+            Confirm::OnlyForRunning => running,
+        }
+    }
+}
+
+/// The outcome of an operation applied to several containers.
+#[derive(Debug)]
+pub struct Summary {
+    pub operation: ContainerOperation,
+    /// The result of the operation for each container, in the order they were given
+    pub results: Vec<(Target, Result<(), Error>)>,
+}
+
+impl Summary {
+    /// Returns `true` if the operation failed for at least one container.
+    pub fn has_failures(&self) -> bool {
+        self.results.iter().any(|(_, result)| result.is_err())
+    }
+
+    /// Returns a one-line description of the outcome, e.g. `Stopped 2 of 3 containers, failed to
+    /// stop db: permission denied`.
+    pub fn message(&self) -> String {
+        let total = self.results.len();
+        let failures: Vec<_> = self
+            .results
+            .iter()
+            .filter_map(|(target, result)| {
+                result
+                    .as_ref()
+                    .err()
+                    .map(|err| format!("{}: {}", target.name, err))
+            })
+            .collect();
+        let succeeded = total - failures.len();
+        let verb = self.operation.name();
+        let done = self.operation.past_participle();
+        match (total, succeeded) {
+            (1, 1) => format!("{} container {}", done, self.results[0].0.name),
+            (1, 0) => format!("Failed to {} container {}", verb, failures[0]),
+            (_, 0) => format!("Failed to {} {}", verb, failures.join(", ")),
+            (_, _) if failures.is_empty() => format!("{} {} containers", done, total),
+            (_, _) => format!(
+                "{} {} of {} containers, failed to {} {}",
+                done,
+                succeeded,
+                total,
+                verb,
+                failures.join(", ")
+            ),
+        }
+    }
+}
+
+/// Maximum number of operations sent to the daemon at the same time
+const MAX_WORKERS: usize = 8;
+
+/// Applies the operation to all the targets concurrently, and waits for all of them to complete.
+/// At most `MAX_WORKERS` operations run at the same time.
+pub fn run(
+    docker: Arc<dyn DockerBackend>,
+    operation: ContainerOperation,
+    targets: Vec<Target>,
+) -> Summary {
+    info!(
+        "Applying {} to {} container(s)",
+        operation.name(),
+        targets.len()
+    );
+    let (jobs_tx, jobs) = crossbeam_channel::unbounded();
+    for (i, target) in targets.iter().enumerate() {
+        jobs_tx.send((i, target.id.0.clone())).unwrap();
+    }
+    drop(jobs_tx);
+
+    let (results_tx, results_rx) = crossbeam_channel::unbounded();
+    for _ in 0..targets.len().min(MAX_WORKERS) {
+        let docker = docker.clone();
+        let jobs = jobs.clone();
+        let results_tx = results_tx.clone();
+        thread::spawn(move || {
+            for (i, id) in jobs {
+                let _ = results_tx.send((i, operation.apply(docker.as_ref(), &id)));
+            }
+        });
+    }
+    // The results are all in once every worker has dropped its sender
+    drop(results_tx);
+    let mut outcomes: Vec<_> = targets.iter().map(|_| None).collect();
+    for (i, result) in results_rx {
+        outcomes[i] = Some(result);
+    }
+
+    let results = targets
+        .into_iter()
+        .zip(outcomes)
+        .map(|(target, result)| {
+            let result = result.unwrap_or_else(|| Err(format_err!("the operation panicked")));
+            if let Err(ref err) = result {
+                error!(
+                    "Failed to {} container {}: {}",
+                    operation.name(),
+                    target.name,
+                    err
+                );
+            }
+            (target, result)
+        })
+        .collect();
+    Summary { operation, results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::fake::FakeDocker;
+
+    fn target(name: &str) -> Target {
+        Target {
+            id: ContainerId(name.to_string()),
+            name: name.to_string(),
+            running: false,
+        }
+    }
+
+    #[test]
+    fn applies_to_all_targets() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_container("0123", "web", "nginx", "running")
+                .with_container("4567", "db", "postgres", "running")
+                .with_container("89ab", "cache", "redis", "exited"),
+        );
+        let summary = run(
+            docker.clone(),
+            ContainerOperation::Stop,
+            vec![target("web"), target("db")],
+        );
+        assert!(!summary.has_failures());
+        assert_eq!(summary.message(), "Stopped 2 containers");
+        assert_eq!(docker.container_state("web").unwrap(), "exited");
+        assert_eq!(docker.container_state("db").unwrap(), "exited");
+        assert_eq!(docker.container_state("cache").unwrap(), "exited");
+    }
+
+    #[test]
+    fn reports_each_failure() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_container("0123", "web", "nginx", "running")
+                .with_container("4567", "db", "postgres", "exited")
+                .with_container("89ab", "cache", "redis", "exited"),
+        );
+        let summary = run(
+            docker.clone(),
+            ContainerOperation::Stop,
+            vec![target("web"), target("db"), target("cache")],
+        );
+        assert!(summary.has_failures());
+        assert_eq!(
+            summary.message(),
+            "Stopped 1 of 3 containers, failed to stop db: Container db is exited, cache: \
+             Container cache is exited"
+        );
+        let names: Vec<_> = summary.results.iter().map(|(t, _)| &t.name).collect();
+        assert_eq!(names, vec!["web", "db", "cache"]);
+    }
+
+    #[test]
+    fn more_targets_than_workers() {
+        let names: Vec<_> = (0..MAX_WORKERS * 3)
+            .map(|i| format!("web-{:02}", i))
+            .collect();
+        let docker = names.iter().fold(FakeDocker::new(), |docker, name| {
+            docker.with_container(name, name, "nginx", "running")
+        });
+        let docker = Arc::new(docker);
+        let targets = names.iter().map(|name| target(name)).collect();
+        let summary = run(docker.clone(), ContainerOperation::Pause, targets);
+        assert!(!summary.has_failures());
+        let paused: Vec<_> = summary.results.iter().map(|(t, _)| &t.name).collect();
+        assert_eq!(paused, names.iter().collect::<Vec<_>>());
+        for name in &names {
+            assert_eq!(docker.container_state(name).unwrap(), "paused");
+        }
+    }
+
+    #[test]
+    fn single_container_messages() {
+        let docker = Arc::new(FakeDocker::new().with_container("0123", "web", "nginx", "running"));
+        let summary = run(
+            docker.clone(),
+            ContainerOperation::Pause,
+            vec![target("web")],
+        );
+        assert_eq!(summary.message(), "Paused container web");
+
+        docker.fail("container_unpause", "permission denied");
+        let summary = run(docker, ContainerOperation::Unpause, vec![target("web")]);
+        assert_eq!(
+            summary.message(),
+            "Failed to unpause container web: permission denied"
+        );
+    }
+
+    #[test]
+    fn force_deletes_running_containers() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_container("0123", "web", "nginx", "running")
+                .with_container("4567", "db", "postgres", "running"),
+        );
+        let summary = run(
+            docker.clone(),
+            ContainerOperation::Delete(DeleteOptions::default()),
+            vec![target("web")],
+        );
+        assert_eq!(
+            summary.message(),
+            "Failed to delete container web: You cannot remove a running container web"
+        );
+        let force = DeleteOptions {
+            force: true,
+            volumes: false,
+        };
+        let summary = run(
+            docker.clone(),
+            ContainerOperation::Delete(force),
+            vec![target("web"), target("db")],
+        );
+        assert_eq!(summary.message(), "Deleted 2 containers");
+        assert_eq!(docker.container_state("web"), None);
+    }
+
+    #[test]
+    fn confirmation_policies() {
+        assert!(Confirm::Always.is_needed(false));
+        assert!(!Confirm::Never.is_needed(true));
+        assert!(!Confirm::OnlyForRunning.is_needed(false));
+        assert!(Confirm::OnlyForRunning.is_needed(true));
+        assert_eq!(
+            Confirm::parse("only-for-running").unwrap(),
+            Confirm::OnlyForRunning
+        );
+        assert!(Confirm::parse("sometimes").is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Command line arguments.
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use failure::*;
+use log::LevelFilter;
+
+use crate::app::ContainerId;
+use crate::docker::ListFilter;
+use crate::views::ViewType;
+
+pub const USAGE: &str = "Usage: rkr [options]
+
+Options:
+    -H, --host <host>           Docker daemon to connect to, e.g. unix:///var/run/docker.sock or
+                                tcp://build-1:2376 (defaults to $DOCKER_HOST)
+    -c, --config <file>         Configuration file to use (defaults to
+                                $XDG_CONFIG_HOME/rkr/config.toml)
+        --view <view>           View to open on startup: containers (default), images, info,
+                                stats, events or logs <container>
+    -a, --all                   List all the containers (default)
+    -r, --running               Only list the running containers
+    -f, --filter <key=value>    Only list the containers matching the filter: label=<name>,
+                                label=<name>=<value>, status=<state>, exited=<code>, name=<name>,
+                                ancestor=<image>, network=<network>, volume=<volume>,
+                                health=<state>, before=<container> or since=<container>. Can be
+                                given several times.
+        --refresh-interval <s>  Interval between automatic refreshes, in seconds (defaults to
+                                $RKR_REFRESH_INTERVAL or 5, 0 disables them)
+        --log-level <level>     Level of the application logs: off, error, warn, info, debug or
+                                trace (defaults to trace)
+        --log-file <file>       Also write the application logs to the given file
+        --read-only             Don't allow any change to the containers (no start, stop, delete,
+                                shell...)
+        --exec-command <cmd>    Command to run when opening a shell in a container (defaults to
+                                $RKR_EXEC_COMMAND or /bin/sh)
+    -V, --version               Print the version
+    -h, --help                  Print this message
+
+TLS connections are configured with $DOCKER_TLS_VERIFY and $DOCKER_CERT_PATH, like with the docker
+CLI. Unless --host or $DOCKER_HOST is set, the current docker CLI context (or $DOCKER_CONTEXT) is
+used.";
+
+/// What rkr has been asked to do.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(Options),
+    Help,
+    Version,
+}
+
+/// The options rkr runs with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Docker daemon to connect to, overriding the environment and the contexts
+    pub host: Option<String>,
+    /// Configuration file to use
+    pub config: Option<PathBuf>,
+    /// View to open on startup
+    pub view: Option<ViewType>,
+    /// Whether to only list the running containers
+    pub only_running: bool,
+    /// Filters applied to the list of containers
+    pub filters: Vec<ListFilter>,
+    /// Interval between automatic refreshes, in seconds
+    pub refresh_interval: Option<u64>,
+    /// Level of the application logs
+    pub log_level: LevelFilter,
+    /// File to write the application logs to
+    pub log_file: Option<PathBuf>,
+    /// Whether to refuse the operations that change the state of the daemon
+    pub read_only: bool,
+    /// Command to run when opening a shell in a container
+    pub exec_command: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            host: None,
+            config: None,
+            view: None,
+            only_running: false,
+            filters: Vec::new(),
+            refresh_interval: None,
+            log_level: LevelFilter::Trace,
+            log_file: None,
+            read_only: false,
+            exec_command: None,
+        }
+    }
+}
+
+/// Parses the command line arguments (without the program name).
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, Error> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // Support --flag=value as well as --flag value
+        let (flag, inline_value) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => (&arg[..i], Some(arg[i + 1..].to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format_err!("Missing value for {}", flag))
+        };
+        match flag {
+            "-H" | "--host" => options.host = Some(value()?),
+            "-c" | "--config" => options.config = Some(PathBuf::from(value()?)),
+            "--view" => {
+                let view = value()?;
+                options.view = Some(match ViewType::from_name(&view) {
+                    Some(view) => view,
+                    None if view == "logs" => ViewType::ContainerLogs(ContainerId(
+                        args.next()
+                            .ok_or_else(|| format_err!("Missing container for --view logs"))?,
+                    )),
+                    None => bail!("Unknown view {}", view),
+                })
+            }
+            "-a" | "--all" => options.only_running = false,
+            "-r" | "--running" => options.only_running = true,
+            "-f" | "--filter" => options.filters.push(value()?.parse()?),
+            "--refresh-interval" => {
+                let interval = value()?;
+                options.refresh_interval = Some(
+                    interval
+                        .parse()
+                        .map_err(|_| format_err!("Invalid refresh interval {}", interval))?,
+                );
+            }
+            "--log-level" => {
+                let level = value()?;
+                options.log_level = LevelFilter::from_str(&level)
+                    .map_err(|_| format_err!("Invalid log level {}", level))?;
+            }
+            "--log-file" => options.log_file = Some(PathBuf::from(value()?)),
+            "--read-only" => options.read_only = true,
+            "--exec-command" => options.exec_command = Some(value()?),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => bail!("Unknown argument: {}", arg),
+        }
+    }
+    Ok(Command::Run(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Command, Error> {
+        parse(args.iter().map(|s| s.to_string()))
+    }
+
+    fn options(args: &[&str]) -> Options {
+        match parse_args(args).unwrap() {
+            Command::Run(options) => options,
+            command => panic!("Unexpected command {:?}", command),
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(options(&[]), Options::default());
+    }
+
+    #[test]
+    fn all_options() {
+        let options = options(&[
+            "-H",
+            "tcp://build-1:2376",
+            "--config=/tmp/rkr.toml",
+            "--view",
+            "logs",
+            "web",
+            "--running",
+            "--filter",
+            "label=com.docker.compose.project=shop",
+            "-f",
+            "status=running",
+            "--refresh-interval",
+            "10",
+            "--log-level=debug",
+            "--log-file",
+            "/tmp/rkr.log",
+            "--read-only",
+            "--exec-command=/bin/bash -l",
+        ]);
+        assert_eq!(
+            options,
+            Options {
+                host: Some("tcp://build-1:2376".to_string()),
+                config: Some(PathBuf::from("/tmp/rkr.toml")),
+                view: Some(ViewType::ContainerLogs(ContainerId("web".to_string()))),
+                only_running: true,
+                filters: vec![
+                    ListFilter::Label(
+                        "com.docker.compose.project".to_string(),
+                        Some("shop".to_string())
+                    ),
+                    ListFilter::Status("running".to_string()),
+                ],
+                refresh_interval: Some(10),
+                log_level: LevelFilter::Debug,
+                log_file: Some(PathBuf::from("/tmp/rkr.log")),
+                read_only: true,
+                exec_command: Some("/bin/bash -l".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn help_and_version() {
+        assert_eq!(parse_args(&["--running", "-h"]).unwrap(), Command::Help);
+        assert_eq!(parse_args(&["--version"]).unwrap(), Command::Version);
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert!(parse_args(&["--host"]).is_err());
+        assert!(parse_args(&["--view", "volumes"]).is_err());
+        assert!(parse_args(&["--view", "logs"]).is_err());
+        assert!(parse_args(&["--refresh-interval", "soon"]).is_err());
+        assert!(parse_args(&["--log-level", "loud"]).is_err());
+        assert!(parse_args(&["--filter", "dangling=true"]).is_err());
+        assert!(parse_args(&["--frobnicate"]).is_err());
+    }
+}
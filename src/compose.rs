@@ -0,0 +1,146 @@
+//! Groups of containers created by Docker Compose, by project and service.
+use std::collections::{BTreeMap, HashMap};
+
+/// Label set by Compose on the containers of a project
+pub const PROJECT_LABEL: &str = "com.docker.compose.project";
+/// Label set by Compose on the containers of a service
+pub const SERVICE_LABEL: &str = "com.docker.compose.service";
+
+/// A group of containers, which can be collapsed in the container list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Group {
+    /// The containers of a project, or the ones that weren't created by Compose
+    Project(Option<String>),
+    /// The containers of a service, in a project
+    Service(String, String),
+}
+
+impl Group {
+    /// Returns the name of the group, as displayed in its header.
+    pub fn title(&self) -> &str {
+        match *self {
+            Group::Project(Some(ref project)) => project,
+            Group::Project(None) => "Other containers",
+            // The service label is missing
+            Group::Service(_, ref service) if service.is_empty() => "No service",
+            Group::Service(_, ref service) => service,
+        }
+    }
+}
+
+/// The containers of a project, by service.
+#[derive(Debug, PartialEq)]
+pub struct Project {
+    pub group: Group,
+    /// Positions of the containers of the project, in order
+    pub containers: Vec<usize>,
+    /// The group and the positions of the containers of each service, which is empty for the
+    /// containers that weren't created by Compose
+    pub services: Vec<(Group, Vec<usize>)>,
+}
+
+/// Groups containers, given by their labels, by project and service. Projects and services are
+/// sorted by name, followed by the containers that weren't created by Compose, and the containers
+/// keep their order within each group.
+pub fn group<'a, I>(labels: I) -> Vec<Project>
+where
+    I: IntoIterator<Item = &'a HashMap<String, String>>,
+{
+    let mut projects = BTreeMap::new();
+    let mut others = Vec::new();
+    for (position, labels) in labels.into_iter().enumerate() {
+        match labels.get(PROJECT_LABEL) {
+            Some(project) => {
+                let service = labels.get(SERVICE_LABEL).map_or("", String::as_str);
+                projects
+                    .entry(project.as_str())
+                    .or_insert_with(BTreeMap::new)
+                    .entry(service)
+                    .or_insert_with(Vec::new)
+                    .push(position);
+            }
+            None => others.push(position),
+        }
+    }
+
+    let mut groups: Vec<_> = projects
+        .into_iter()
+        .map(|(project, services)| {
+            let mut containers: Vec<_> = services.values().flatten().cloned().collect();
+            containers.sort();
+            Project {
+                group: Group::Project(Some(project.to_string())),
+                containers,
+                services: services
+                    .into_iter()
+                    .map(|(service, positions)| {
+                        let group = Group::Service(project.to_string(), service.to_string());
+                        (group, positions)
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    if !others.is_empty() {
+        groups.push(Project {
+            group: Group::Project(None),
+            containers: others,
+            services: Vec::new(),
+        });
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(project: Option<&str>, service: &str) -> HashMap<String, String> {
+        let mut labels = HashMap::new();
+        if let Some(project) = project {
+            labels.insert(PROJECT_LABEL.to_string(), project.to_string());
+            labels.insert(SERVICE_LABEL.to_string(), service.to_string());
+        }
+        labels
+    }
+
+    #[test]
+    fn groups_by_project_and_service() {
+        let containers = vec![
+            labels(Some("shop"), "web"),
+            labels(None, ""),
+            labels(Some("blog"), "db"),
+            labels(Some("shop"), "db"),
+            labels(Some("shop"), "web"),
+        ];
+        let service =
+            |project: &str, service: &str| Group::Service(project.to_string(), service.to_string());
+        assert_eq!(
+            group(&containers),
+            vec![
+                Project {
+                    group: Group::Project(Some("blog".to_string())),
+                    containers: vec![2],
+                    services: vec![(service("blog", "db"), vec![2])],
+                },
+                Project {
+                    group: Group::Project(Some("shop".to_string())),
+                    containers: vec![0, 3, 4],
+                    services: vec![
+                        (service("shop", "db"), vec![3]),
+                        (service("shop", "web"), vec![0, 4]),
+                    ],
+                },
+                Project {
+                    group: Group::Project(None),
+                    containers: vec![1],
+                    services: vec![],
+                },
+            ]
+        );
+        assert_eq!(Group::Project(None).title(), "Other containers");
+        assert_eq!(service("shop", "").title(), "No service");
+        let none: Vec<HashMap<String, String>> = Vec::new();
+        assert!(group(&none).is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+//! The configuration file, `$XDG_CONFIG_HOME/rkr/config.toml` by default:
+//!
+//! ```toml
+//! [general]
+//! view = "containers"       # containers, images, info, stats or events
+//! refresh_interval = 5      # in seconds, 0 disables auto-refresh
+//! exec_command = "/bin/sh"  # run when opening a shell in a container
+//!
+//! [theme]
+//! selected = "lightyellow"  # name, #rrggbb or 0-255
+//!
+//! [keys.global]             # or the name of a view, e.g. [keys.containers]
+//! quit = ["q", "esc"]
+//!
+//! [columns]
+//! containers = ["name", "image", "status", "ports"]
+//! images = ["tag", "created", "size"]
+//!
+//! [confirm]                 # always, never or only-for-running
+//! delete = "always"
+//! stop = "only-for-running"
+//! ```
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::*;
+use serde::Deserialize;
+
+use crate::bulk::{self, Confirm};
+use crate::keys::{self, Binding, Keymap};
+use crate::theme::{self, Theme};
+use crate::views::{parse_columns, Column, ContainerColumn, ImageColumn, ViewType};
+
+/// The contents of the configuration file, before validation.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct File {
+    general: General,
+    // Maps are ordered so that errors are reported in a stable order
+    theme: BTreeMap<String, String>,
+    keys: BTreeMap<String, BTreeMap<String, Keys>>,
+    columns: BTreeMap<String, Vec<String>>,
+    confirm: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct General {
+    view: Option<String>,
+    refresh_interval: Option<u64>,
+    exec_command: Option<String>,
+}
+
+/// The keys an action is bound to, either a single one or a list.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The validated configuration.
+#[derive(Debug)]
+pub struct Config {
+    /// View to open on startup
+    pub view: Option<ViewType>,
+    /// Interval between automatic refreshes, in seconds
+    pub refresh_interval: Option<u64>,
+    /// Command to run when opening a shell in a container
+    pub exec_command: Option<String>,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    /// Columns of the container list
+    pub container_columns: Vec<ContainerColumn>,
+    /// Columns of the image list
+    pub image_columns: Vec<ImageColumn>,
+    /// When each operation (see `bulk::OPERATIONS`) asks for confirmation
+    pub confirm: HashMap<&'static str, Confirm>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            view: None,
+            refresh_interval: None,
+            exec_command: None,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            container_columns: ContainerColumn::DEFAULT.to_vec(),
+            image_columns: ImageColumn::DEFAULT.to_vec(),
+            confirm: bulk::OPERATIONS
+                .iter()
+                .map(|&op| {
+                    let confirm = match op {
+                        "delete" | "delete-image" => Confirm::Always,
+                        _ => Confirm::Never,
+                    };
+                    (op, confirm)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the given configuration file, or the default one if it exists.
+    pub fn load(path: Option<&Path>) -> Result<Config, Error> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(ref path) if path.is_file() => path.clone(),
+                _ => return Ok(Config::default()),
+            },
+        };
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format_err!("Failed to read {}: {}", path.display(), e))?;
+        Config::parse(&text)
+            .map_err(|e| format_err!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    /// Parses and validates the contents of a configuration file. All the invalid entries are
+    /// reported at once, one per line.
+    fn parse(text: &str) -> Result<Config, Error> {
+        let file: File = toml::from_str(text)?;
+        let mut config = Config::default();
+        let mut errors = Vec::new();
+
+        if let Some(ref view) = file.general.view {
+            match ViewType::from_name(view) {
+                Some(view) => config.view = Some(view),
+                None => errors.push(format!(
+                    "general.view: unknown view {}, expected containers, images, info, stats or \
+                     events",
+                    view
+                )),
+            }
+        }
+        config.refresh_interval = file.general.refresh_interval;
+        config.exec_command = file.general.exec_command;
+
+        for (name, color) in &file.theme {
+            if let Err(e) = theme::parse_color(color).and_then(|c| config.theme.set(name, c)) {
+                errors.push(format!("theme.{}: {}", name, e));
+            }
+        }
+
+        let mut overrides: Vec<(&'static Binding, Vec<_>)> = Vec::new();
+        for (scope, actions) in &file.keys {
+            for (id, bound) in actions {
+                let binding = match keys::find_binding(scope, id) {
+                    Some(binding) => binding,
+                    None => {
+                        errors.push(format!("keys.{}.{}: unknown action", scope, id));
+                        continue;
+                    }
+                };
+                let names = match *bound {
+                    Keys::One(ref name) => vec![name.clone()],
+                    Keys::Many(ref names) => names.clone(),
+                };
+                match names.iter().map(|n| keys::parse_key(n)).collect() {
+                    Ok(bound) => overrides.push((binding, bound)),
+                    Err(e) => errors.push(format!("keys.{}.{}: {}", scope, id, e)),
+                }
+            }
+        }
+        match Keymap::new(&overrides) {
+            Ok(keymap) => config.keymap = keymap,
+            Err(e) => errors.push(format!("keys: {}", e)),
+        }
+
+        for (table, names) in &file.columns {
+            let result = match table.as_str() {
+                "containers" => parse_columns(names).map(|c| config.container_columns = c),
+                "images" => parse_columns(names).map(|c| config.image_columns = c),
+                _ => Err("unknown table, expected containers or images".to_string()),
+            };
+            if let Err(e) = result {
+                errors.push(format!("columns.{}: {}", table, e));
+            }
+        }
+
+        for (name, policy) in &file.confirm {
+            let op = match bulk::OPERATIONS.iter().cloned().find(|op| op == name) {
+                Some(op) => op,
+                None => {
+                    errors.push(format!(
+                        "confirm.{}: unknown operation, expected one of {}",
+                        name,
+                        bulk::OPERATIONS.join(", ")
+                    ));
+                    continue;
+                }
+            };
+            match Confirm::parse(policy) {
+                Ok(policy) => {
+                    config.confirm.insert(op, policy);
+                }
+                Err(e) => errors.push(format!("confirm.{}: {}", name, e)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            bail!("\n  {}", errors.join("\n  "))
+        }
+    }
+}
+
+/// Returns the path of the default configuration file.
+fn default_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("rkr").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Action;
+    use termion::event::Key;
+    use tui::style::Color;
+
+    #[test]
+    fn full_config() {
+        let config = Config::parse(
+            r##"
+            [general]
+            view = "images"
+            refresh_interval = 10
+            exec_command = "/bin/bash -l"
+
+            [theme]
+            selected = "#ff8000"
+            running = "lightgreen"
+
+            [keys.global]
+            quit = ["q", "esc"]
+
+            [keys.containers]
+            stop = "x"
+
+            [columns]
+            containers = ["name", "status", "ports"]
+            images = ["tag", "size"]
+
+            [confirm]
+            delete = "only-for-running"
+            stop = "always"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.view, Some(ViewType::ImagesList));
+        assert_eq!(config.refresh_interval, Some(10));
+        assert_eq!(config.exec_command.as_deref(), Some("/bin/bash -l"));
+        assert_eq!(config.theme.selected, Color::Rgb(255, 128, 0));
+        assert_eq!(config.theme.running, Color::LightGreen);
+        assert_eq!(config.theme.error, Theme::default().error);
+        assert_eq!(config.keymap.action("images", Key::Esc), Some(Action::Quit));
+        assert_eq!(
+            config.keymap.action("containers", Key::Char('x')),
+            Some(Action::Stop)
+        );
+        assert_eq!(
+            config.container_columns,
+            vec![
+                ContainerColumn::Name,
+                ContainerColumn::Status,
+                ContainerColumn::Ports
+            ]
+        );
+        assert_eq!(
+            config.image_columns,
+            vec![ImageColumn::Tag, ImageColumn::Size]
+        );
+        assert_eq!(config.confirm["delete"], Confirm::OnlyForRunning);
+        assert_eq!(config.confirm["stop"], Confirm::Always);
+        assert_eq!(config.confirm["pause"], Confirm::Never);
+    }
+
+    #[test]
+    fn empty_config() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.view, None);
+        assert_eq!(config.theme, Theme::default());
+        assert_eq!(config.container_columns, ContainerColumn::DEFAULT.to_vec());
+        assert_eq!(config.confirm["delete"], Confirm::Always);
+        assert_eq!(config.confirm["delete-image"], Confirm::Always);
+        assert_eq!(config.confirm["start"], Confirm::Never);
+    }
+
+    #[test]
+    fn all_errors_are_reported() {
+        let err = Config::parse(
+            r#"
+            [general]
+            view = "volumes"
+
+            [theme]
+            selected = "orange"
+            highlight = "red"
+
+            [keys.containers]
+            stpo = "x"
+            delete = "ctrl-"
+
+            [columns]
+            containers = ["name", "uptime"]
+
+            [confirm]
+            delete = "sometimes"
+            kill = "always"
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "
+  general.view: unknown view volumes, expected containers, images, info, stats or events
+  theme.highlight: unknown color, expected one of foreground, background, selected, running, \
+             error, warning, title, accent, secondary or disabled
+  theme.selected: invalid color orange, expected a name (e.g. lightblue), #rrggbb or 0-255
+  keys.containers.delete: invalid key ctrl-
+  keys.containers.stpo: unknown action
+  columns.containers: unknown column uptime, expected one of id, name, image, command, \
+             status, state, created, ports
+  confirm.delete: invalid value sometimes, expected always, never or only-for-running
+  confirm.kill: unknown operation, expected one of pause, unpause, stop, start, restart, delete, \
+             delete-image"
+        );
+    }
+
+    #[test]
+    fn conflicting_keys_are_reported() {
+        let err = Config::parse("[keys.containers]\nstop = \"d\"").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("keys: d is bound to both containers.stop and containers.delete"));
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        assert!(Config::parse("[general]\nview = ").is_err());
+        assert!(Config::parse("[general]\nmode = \"dark\"").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Tracks whether the Docker daemon is reachable, and when to try reconnecting to it after it has
+//! gone away (e.g. because it is being restarted).
+use std::cmp;
+use std::time::{Duration, Instant};
+
+/// Delay before the first reconnection attempt
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Maximum delay between two reconnection attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// The state of the connection to the Docker daemon.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Connection {
+    Connected,
+    Disconnected {
+        /// Why the daemon is unreachable
+        error: String,
+        /// Number of failed reconnection attempts so far
+        attempts: u32,
+        /// When to try reconnecting next
+        retry_at: Instant,
+        /// Whether a reconnection attempt is in progress
+        retrying: bool,
+    },
+}
+
+impl Connection {
+    pub fn is_connected(&self) -> bool {
+        *self == Connection::Connected
+    }
+
+    /// Records that the daemon is unreachable. If a reconnection attempt was in progress, the next
+    /// one is scheduled with an exponential backoff, otherwise it is scheduled right away.
+    pub fn disconnected(&mut self, error: String, now: Instant) {
+        let attempts = match *self {
+            Connection::Connected => 0,
+            Connection::Disconnected {
+                attempts, retrying, ..
+            } => {
+                if retrying {
+                    attempts + 1
+                } else {
+                    attempts
+                }
+            }
+        };
+        *self = Connection::Disconnected {
+            error,
+            attempts,
+            retry_at: now + retry_delay(attempts),
+            retrying: false,
+        };
+    }
+
+    /// Records that the daemon has responded. Returns `true` if it was previously unreachable.
+    pub fn connected(&mut self) -> bool {
+        let reconnected = !self.is_connected();
+        *self = Connection::Connected;
+        reconnected
+    }
+
+    /// Returns `true` if it is time to try reconnecting, in which case the attempt is considered
+    /// to be in progress until `connected()` or `disconnected()` is called.
+    pub fn should_retry(&mut self, now: Instant) -> bool {
+        match *self {
+            Connection::Disconnected {
+                retry_at,
+                ref mut retrying,
+                ..
+            } if !*retrying && now >= retry_at => {
+                *retrying = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Schedules the next reconnection attempt right away, e.g. when the user asks for a refresh.
+    pub fn retry_now(&mut self, now: Instant) {
+        if let Connection::Disconnected {
+            ref mut retry_at, ..
+        } = *self
+        {
+            *retry_at = now;
+        }
+    }
+}
+
+/// Returns the delay before the reconnection attempt following `attempts` failed ones.
+fn retry_delay(attempts: u32) -> Duration {
+    // 1s, 2s, 4s, ... up to MAX_RETRY_DELAY
+    let factor = 1u32 << cmp::min(attempts, 5);
+    cmp::min(INITIAL_RETRY_DELAY * factor, MAX_RETRY_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retry_at(connection: &Connection) -> Instant {
+        match *connection {
+            Connection::Disconnected { retry_at, .. } => retry_at,
+            Connection::Connected => panic!("Not disconnected"),
+        }
+    }
+
+    #[test]
+    fn retries_with_backoff() {
+        let now = Instant::now();
+        let mut connection = Connection::Connected;
+        connection.disconnected("Connection refused".to_string(), now);
+        assert!(!connection.is_connected());
+        assert_eq!(retry_at(&connection), now + Duration::from_secs(1));
+        assert!(!connection.should_retry(now));
+
+        let mut now = now;
+        for &delay in &[2, 4, 8, 16, 30, 30] {
+            now = retry_at(&connection);
+            assert!(connection.should_retry(now));
+            // Only one attempt at a time
+            assert!(!connection.should_retry(now));
+            connection.disconnected("Connection refused".to_string(), now);
+            assert_eq!(retry_at(&connection), now + Duration::from_secs(delay));
+        }
+
+        assert!(connection.connected());
+        assert!(!connection.connected());
+        assert!(!connection.should_retry(now + MAX_RETRY_DELAY));
+    }
+
+    #[test]
+    fn repeated_failures_do_not_delay_the_first_attempt() {
+        let now = Instant::now();
+        let mut connection = Connection::Connected;
+        connection.disconnected("Connection refused".to_string(), now);
+        // e.g. the event stream ending, then a request failing
+        connection.disconnected("Connection reset".to_string(), now);
+        assert_eq!(retry_at(&connection), now + INITIAL_RETRY_DELAY);
+
+        connection.retry_now(now);
+        assert!(connection.should_retry(now));
+    }
+}
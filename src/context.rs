@@ -0,0 +1,182 @@
+//! Docker contexts, i.e. named connections to Docker daemons, as managed by `docker context`.
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::docker::ConnectionConfig;
+
+/// Name of the context using the connection configured with the environment (or `--host`)
+pub const DEFAULT_CONTEXT: &str = "default";
+
+/// A named connection to a Docker daemon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Context {
+    pub name: String,
+    pub description: String,
+    pub config: ConnectionConfig,
+}
+
+/// Returns the available contexts: the default one, using the given configuration, followed by
+/// the contexts of the docker CLI. Also returns the name of the context to use initially, which is
+/// the current context of the docker CLI unless `host_overridden` is `true`, like with the docker
+/// CLI.
+pub fn load_contexts(default: ConnectionConfig, host_overridden: bool) -> (Vec<Context>, String) {
+    let mut contexts = vec![Context {
+        name: DEFAULT_CONTEXT.to_string(),
+        description: "Current DOCKER_HOST based configuration".to_string(),
+        config: default,
+    }];
+    let mut current = None;
+
+    if let Some(dir) = docker_config_dir() {
+        match read_cli_contexts(&dir) {
+            Ok(cli_contexts) => contexts.extend(cli_contexts),
+            Err(e) => warn!("Failed to read the docker CLI contexts: {}", e),
+        }
+        current = current_cli_context(&dir);
+    }
+    if let Ok(name) = env::var("DOCKER_CONTEXT") {
+        current = Some(name);
+    }
+
+    let current = match current {
+        Some(ref name) if !host_overridden && contexts.iter().any(|c| &c.name == name) => {
+            name.clone()
+        }
+        Some(ref name) if !host_overridden => {
+            warn!("Unknown context {}, using the default one", name);
+            DEFAULT_CONTEXT.to_string()
+        }
+        _ => DEFAULT_CONTEXT.to_string(),
+    };
+    (contexts, current)
+}
+
+/// Returns the configuration directory of the docker CLI.
+fn docker_config_dir() -> Option<PathBuf> {
+    env::var_os("DOCKER_CONFIG")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".docker")))
+}
+
+/// Returns the name of the current context of the docker CLI, if it isn't the default one.
+fn current_cli_context(dir: &Path) -> Option<String> {
+    let config = fs::read(dir.join("config.json")).ok()?;
+    let config: Value = serde_json::from_slice(&config).ok()?;
+    config["currentContext"]
+        .as_str()
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+}
+
+/// Reads the contexts created with `docker context create`. Each of them is stored in a directory
+/// of `contexts/meta` containing a `meta.json` file, and its TLS material (if any) is stored in
+/// the directory with the same name in `contexts/tls`.
+fn read_cli_contexts(dir: &Path) -> Result<Vec<Context>, failure::Error> {
+    let meta_dir = dir.join("contexts").join("meta");
+    if !meta_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut contexts = Vec::new();
+    for entry in fs::read_dir(&meta_dir)? {
+        let entry = entry?;
+        let meta_file = entry.path().join("meta.json");
+        let meta: Value = match fs::read(&meta_file) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) => {
+                warn!("Failed to read {}: {}", meta_file.display(), e);
+                continue;
+            }
+        };
+        let name = match meta["Name"].as_str() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let endpoint = &meta["Endpoints"]["docker"];
+        let host = match endpoint["Host"].as_str() {
+            Some(host) => host.to_string(),
+            None => {
+                warn!("Context {} doesn't have a docker endpoint", name);
+                continue;
+            }
+        };
+        let tls_dir = dir
+            .join("contexts")
+            .join("tls")
+            .join(entry.file_name())
+            .join("docker");
+        let cert_path = if tls_dir.exists() {
+            Some(tls_dir)
+        } else {
+            None
+        };
+        let skip_tls_verify = endpoint["SkipTLSVerify"].as_bool().unwrap_or(false);
+
+        contexts.push(Context {
+            name,
+            description: meta["Metadata"]["Description"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            config: ConnectionConfig {
+                host,
+                tls_verify: cert_path.is_some() && !skip_tls_verify,
+                cert_path,
+            },
+        });
+    }
+    contexts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(contexts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_cli_contexts() {
+        let dir = env::temp_dir().join(format!("rkr-contexts-{}", std::process::id()));
+        let meta = dir.join("contexts/meta");
+        for (hash, name, host) in &[
+            ("a1", "staging", "tcp://staging:2376"),
+            ("b2", "ci", "unix:///run/ci.sock"),
+        ] {
+            fs::create_dir_all(meta.join(hash)).unwrap();
+            let json = format!(
+                r#"{{"Name":"{}","Metadata":{{"Description":"The {} host"}},
+                   "Endpoints":{{"docker":{{"Host":"{}","SkipTLSVerify":false}}}}}}"#,
+                name, name, host
+            );
+            fs::write(meta.join(hash).join("meta.json"), json).unwrap();
+        }
+        fs::create_dir_all(dir.join("contexts/tls/a1/docker")).unwrap();
+        fs::write(dir.join("config.json"), r#"{"currentContext":"staging"}"#).unwrap();
+
+        let contexts = read_cli_contexts(&dir).unwrap();
+        let current = current_cli_context(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(current, Some("staging".to_string()));
+        assert_eq!(contexts.len(), 2);
+        assert_eq!(contexts[0].name, "ci");
+        assert_eq!(contexts[0].config.host, "unix:///run/ci.sock");
+        assert_eq!(contexts[0].config.cert_path, None);
+        assert_eq!(contexts[1].name, "staging");
+        assert_eq!(contexts[1].description, "The staging host");
+        assert_eq!(
+            contexts[1].config.cert_path,
+            Some(dir.join("contexts/tls/a1/docker"))
+        );
+        assert!(contexts[1].config.tls_verify);
+    }
+
+    #[test]
+    fn no_cli_contexts() {
+        let dir = env::temp_dir().join("rkr-contexts-missing");
+        assert_eq!(read_cli_contexts(&dir).unwrap(), Vec::new());
+        assert_eq!(current_cli_context(&dir), None);
+    }
+}
@@ -1,74 +1,1140 @@
-use std::sync::Mutex;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
 use failure::*;
+use futures::{
+    future::Either,
+    sync::{mpsc, oneshot},
+};
+use hyper::{
+    client::{HttpConnector, ResponseFuture},
+    header::{self, HeaderValue},
+    Body, Client, Method, Request, StatusCode, Uri,
+};
+use hyper_openssl::HttpsConnector;
+use hyperlocal::UnixConnector;
+use openssl::ssl::{SslConnector, SslConnectorBuilder, SslFiletype, SslMethod, SslVerifyMode};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
 use shiplift::{
-    rep::{Container, ContainerDetails, Image, Info, Version},
-    ContainerListOptions, Docker, ImageListOptions,
+    rep::{Container, Image, Info, Version},
+    LogsOptions,
 };
-use tokio::prelude::Future;
+use tokio::codec::{BytesCodec, FramedRead};
+use tokio::prelude::{future, stream, AsyncRead, Future, Stream};
 use tokio::runtime::Runtime;
 
+use crate::app::AppEvent;
+use crate::events::EventDecoder;
+use crate::stats::{self, Stats};
+use crate::tty::{InterlacedTty, TtyLine};
+
+#[cfg(test)]
+pub mod fake;
+
+const DEFAULT_HOST: &str = "unix:///var/run/docker.sock";
+const DEFAULT_PORT: u16 = 2375;
+const DEFAULT_TLS_PORT: u16 = 2376;
+
+/// How to connect to the Docker daemon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionConfig {
+    /// Address of the daemon, e.g. `unix:///var/run/docker.sock` or `tcp://build-1:2376`. A plain
+    /// path is a Unix socket.
+    pub host: String,
+    /// Directory containing the `ca.pem`, and optionally the `cert.pem` and `key.pem` files used to
+    /// connect to the daemon over TLS. TLS is only used when this is set.
+    pub cert_path: Option<PathBuf>,
+    /// Whether to verify the certificate of the daemon against `ca.pem`
+    pub tls_verify: bool,
+}
+
+impl ConnectionConfig {
+    /// Reads the configuration from the same environment variables as the `docker` CLI:
+    /// `DOCKER_HOST`, `DOCKER_TLS_VERIFY` and `DOCKER_CERT_PATH`.
+    pub fn from_env() -> ConnectionConfig {
+        let tls_verify = env::var("DOCKER_TLS_VERIFY")
+            .map(|v| !v.is_empty())
+            .unwrap_or(false);
+        let cert_path = match env::var_os("DOCKER_CERT_PATH") {
+            Some(ref path) if !path.is_empty() => Some(PathBuf::from(path)),
+            // Same default as the docker CLI
+            _ if tls_verify => env::var_os("HOME").map(|home| PathBuf::from(home).join(".docker")),
+            _ => None,
+        };
+        ConnectionConfig {
+            host: env::var("DOCKER_HOST")
+                .ok()
+                .filter(|host| !host.is_empty())
+                .unwrap_or_else(|| DEFAULT_HOST.to_string()),
+            cert_path,
+            tls_verify,
+        }
+    }
+
+    /// Returns the path of the socket to connect to, if the daemon is reached through a Unix
+    /// socket.
+    fn socket_path(&self) -> Option<&str> {
+        if self.host.starts_with("unix://") {
+            Some(&self.host[7..])
+        } else if self.host.starts_with('/') {
+            Some(&self.host)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the base URL of the daemon, if it is reached over TCP.
+    fn base_url(&self) -> Result<String, Error> {
+        let address = if self.host.starts_with("tcp://") {
+            &self.host[6..]
+        } else if self.host.contains("://") {
+            bail!("Unsupported Docker host: {}", self.host);
+        } else {
+            &self.host
+        };
+        let address = address.trim_end_matches('/');
+        let scheme = if self.cert_path.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        if address.contains(':') {
+            Ok(format!("{}://{}", scheme, address))
+        } else if self.cert_path.is_some() {
+            Ok(format!("{}://{}:{}", scheme, address, DEFAULT_TLS_PORT))
+        } else {
+            Ok(format!("{}://{}:{}", scheme, address, DEFAULT_PORT))
+        }
+    }
+
+    /// Builds the TLS configuration from the files in `cert_path`.
+    fn ssl_connector(&self, cert_path: &Path) -> Result<SslConnectorBuilder, Error> {
+        let file = |name: &str| {
+            let path = cert_path.join(name);
+            if path.exists() {
+                Ok(path)
+            } else {
+                Err(format_err!("{} not found", path.display()))
+            }
+        };
+        let mut ssl = SslConnector::builder(SslMethod::tls())?;
+        // The client certificate is only needed when the daemon authenticates its clients, the
+        // directory may only contain `ca.pem` otherwise
+        if cert_path.join("cert.pem").exists() {
+            ssl.set_certificate_file(file("cert.pem")?, SslFiletype::PEM)?;
+            ssl.set_private_key_file(file("key.pem")?, SslFiletype::PEM)?;
+        }
+        if self.tls_verify {
+            ssl.set_ca_file(file("ca.pem")?)?;
+        } else {
+            ssl.set_verify(SslVerifyMode::NONE);
+        }
+        Ok(ssl)
+    }
+}
+
+/// A minimal HTTP client to the Docker daemon. shiplift can only be configured for TLS through the
+/// environment, and doesn't give access to the raw response body where we need it (e.g. logs,
+/// which we demultiplex ourselves), so all the requests go through this.
+enum RawTransport {
+    Unix {
+        client: Client<UnixConnector>,
+        path: String,
+    },
+    Tcp {
+        client: Client<HttpConnector>,
+        host: String,
+    },
+    Tls {
+        client: Client<HttpsConnector<HttpConnector>>,
+        host: String,
+    },
+}
+
+impl RawTransport {
+    fn new(config: &ConnectionConfig) -> Result<RawTransport, Error> {
+        if let Some(path) = config.socket_path() {
+            return Ok(RawTransport::Unix {
+                client: Client::builder()
+                    .keep_alive(false)
+                    .build(UnixConnector::new()),
+                path: path.to_string(),
+            });
+        }
+
+        let host = config.base_url()?;
+        match config.cert_path {
+            Some(ref cert_path) => {
+                let mut http = HttpConnector::new(1);
+                http.enforce_http(false);
+                let ssl = config.ssl_connector(cert_path)?;
+                Ok(RawTransport::Tls {
+                    client: Client::builder()
+                        .keep_alive(false)
+                        .build(HttpsConnector::with_connector(http, ssl)?),
+                    host,
+                })
+            }
+            None => Ok(RawTransport::Tcp {
+                client: Client::builder()
+                    .keep_alive(false)
+                    .build(HttpConnector::new(1)),
+                host,
+            }),
+        }
+    }
+
+    fn uri(&self, endpoint: &str) -> Result<Uri, Error> {
+        match self {
+            RawTransport::Unix { path, .. } => Ok(hyperlocal::Uri::new(path, endpoint).into()),
+            RawTransport::Tcp { host, .. } | RawTransport::Tls { host, .. } => {
+                Ok(format!("{}{}", host, endpoint).parse()?)
+            }
+        }
+    }
+
+    /// Builds a POST request to the given endpoint, with the given JSON body.
+    fn post(&self, endpoint: &str, body: &Value) -> Result<Request<Body>, Error> {
+        Ok(Request::post(self.uri(endpoint)?)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))?)
+    }
+
+    fn request(&self, req: Request<Body>) -> ResponseFuture {
+        match self {
+            RawTransport::Unix { client, .. } => client.request(req),
+            RawTransport::Tcp { client, .. } => client.request(req),
+            RawTransport::Tls { client, .. } => client.request(req),
+        }
+    }
+
+    /// Sends the given request, and returns the whole body of the response. Error responses are
+    /// turned into an `Error` containing the message sent by the daemon.
+    fn send(&self, req: Request<Body>) -> impl Future<Item = Vec<u8>, Error = Error> {
+        self.request(req)
+            .and_then(|resp| {
+                let status = resp.status();
+                resp.into_body()
+                    .concat2()
+                    .map(move |body| (status, body.to_vec()))
+            })
+            .map_err(Error::from)
+            .and_then(|(status, body)| {
+                if status.is_success() {
+                    Ok(body)
+                } else {
+                    Err(error_from_response(status, &body))
+                }
+            })
+    }
+
+    /// Sends a GET request to the given endpoint, and returns the body of the response as a stream
+    /// of byte chunks.
+    fn stream_get(&self, endpoint: &str) -> Box<dyn Stream<Item = Vec<u8>, Error = Error> + Send> {
+        let uri = match self.uri(endpoint) {
+            Ok(uri) => uri,
+            Err(e) => return Box::new(stream::once(Err(e))),
+        };
+        let body = self
+            .request(Request::get(uri).body(Body::empty()).unwrap())
+            .map_err(Error::from)
+            .and_then(|resp| {
+                let status = resp.status();
+                if status.is_success() {
+                    Ok(resp.into_body())
+                } else {
+                    Err(error_from_response(status, &[]))
+                }
+            })
+            .map(|body| body.map(|chunk| chunk.to_vec()).map_err(Error::from))
+            .flatten_stream();
+        Box::new(body)
+    }
+}
+
+/// Builds an error from an error response sent by the daemon, which normally contains a JSON
+/// object with a `message` field.
+fn error_from_response(status: StatusCode, body: &[u8]) -> Error {
+    let message = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|v| v["message"].as_str().map(|m| m.to_string()));
+    match message {
+        Some(message) => format_err!("{}", message),
+        None => format_err!("The Docker daemon returned an error: {}", status),
+    }
+}
+
+/// An interactive session in a container, started with `DockerExecutor::exec()`. The output of the
+/// session is written directly to stdout.
+pub struct ExecSession {
+    id: String,
+    input: mpsc::UnboundedSender<Vec<u8>>,
+    done: Receiver<()>,
+}
+
+impl ExecSession {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Sends the given bytes to the stdin of the session.
+    pub fn send(&self, bytes: &[u8]) -> Result<(), Error> {
+        self.input
+            .unbounded_send(bytes.to_vec())
+            .map_err(|_| format_err!("The session has ended"))
+    }
+
+    /// Returns `true` once the session has ended, i.e. its output has been closed.
+    pub fn is_done(&self) -> bool {
+        !matches!(self.done.try_recv(), Err(TryRecvError::Empty))
+    }
+}
+
+/// Keeps a stream running in the background: the stream is stopped when this is dropped.
+pub struct Subscription {
+    _stop: oneshot::Sender<()>,
+}
+
+impl Subscription {
+    /// Returns a new subscription, along with a future that completes when it is dropped.
+    fn new() -> (Subscription, oneshot::Receiver<()>) {
+        let (stop, stopped) = oneshot::channel();
+        (Subscription { _stop: stop }, stopped)
+    }
+}
+
+/// Container states accepted by the `status` filter
+const CONTAINER_STATES: &[&str] = &[
+    "created",
+    "restarting",
+    "running",
+    "removing",
+    "paused",
+    "exited",
+    "dead",
+];
+
+/// Health states accepted by the `health` filter
+const HEALTH_STATES: &[&str] = &["starting", "healthy", "unhealthy", "none"];
+
+/// Filters supported by the container list
+const CONTAINER_FILTERS: &[&str] = &[
+    "label", "status", "exited", "name", "ancestor", "network", "volume", "health", "before",
+    "since",
+];
+
+/// Filters supported by the image list
+const IMAGE_FILTERS: &[&str] = &["label", "dangling", "reference", "before", "since"];
+
+/// The lists the daemon can filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListKind {
+    Containers,
+    Images,
+}
+
+/// A filter applied by the daemon to the list of containers or images, written `key=value` like
+/// with `docker ps --filter` and `docker images --filter`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListFilter {
+    /// `label=<name>` or `label=<name>=<value>`
+    Label(String, Option<String>),
+    /// `status=<state>`
+    Status(String),
+    /// `exited=<code>`
+    ExitCode(u64),
+    /// `name=<name>`, which matches part of the name
+    Name(String),
+    /// `ancestor=<image>`, an image or one of its descendants
+    Ancestor(String),
+    /// `network=<name or id>`
+    Network(String),
+    /// `volume=<name or mount point>`
+    Volume(String),
+    /// `health=<state>`
+    Health(String),
+    /// `before=<container or image>`, created before the given one
+    Before(String),
+    /// `since=<container or image>`, created after the given one
+    Since(String),
+    /// `dangling=<true or false>`, untagged images
+    Dangling(bool),
+    /// `reference=<pattern>`, images whose reference matches the pattern, e.g. `nginx:*`
+    Reference(String),
+}
+
+impl ListFilter {
+    /// Parses a filter of the given list.
+    pub fn parse(s: &str, kind: ListKind) -> Result<ListFilter, Error> {
+        let mut parts = s.splitn(2, '=');
+        let key = parts.next().unwrap_or_default();
+        let value = match parts.next() {
+            Some(value) if !value.is_empty() => value,
+            _ => bail!("Invalid filter {}, expected key=value", s),
+        };
+        let supported = match kind {
+            ListKind::Containers => CONTAINER_FILTERS,
+            ListKind::Images => IMAGE_FILTERS,
+        };
+        if !supported.contains(&key) {
+            bail!(
+                "Unsupported filter {}, expected one of {}",
+                key,
+                supported.join(", ")
+            );
+        }
+        match key {
+            "label" => {
+                let mut label = value.splitn(2, '=');
+                let name = label.next().unwrap_or_default().to_string();
+                Ok(ListFilter::Label(name, label.next().map(|v| v.to_string())))
+            }
+            "status" if CONTAINER_STATES.contains(&value) => {
+                Ok(ListFilter::Status(value.to_string()))
+            }
+            "status" => bail!(
+                "Invalid status {}, expected one of {}",
+                value,
+                CONTAINER_STATES.join(", ")
+            ),
+            "exited" => value
+                .parse()
+                .map(ListFilter::ExitCode)
+                .map_err(|_| format_err!("Invalid exit code {}", value)),
+            "name" => Ok(ListFilter::Name(value.to_string())),
+            "ancestor" => Ok(ListFilter::Ancestor(value.to_string())),
+            "network" => Ok(ListFilter::Network(value.to_string())),
+            "volume" => Ok(ListFilter::Volume(value.to_string())),
+            "health" if HEALTH_STATES.contains(&value) => Ok(ListFilter::Health(value.to_string())),
+            "health" => bail!(
+                "Invalid health {}, expected one of {}",
+                value,
+                HEALTH_STATES.join(", ")
+            ),
+            "before" => Ok(ListFilter::Before(value.to_string())),
+            "since" => Ok(ListFilter::Since(value.to_string())),
+            "dangling" => value.parse().map(ListFilter::Dangling).map_err(|_| {
+                format_err!(
+                    "Invalid value {} for dangling, expected true or false",
+                    value
+                )
+            }),
+            "reference" => Ok(ListFilter::Reference(value.to_string())),
+            // A filter of CONTAINER_FILTERS or IMAGE_FILTERS that isn't parsed above
+            _ => bail!("Unsupported filter {}", key),
+        }
+    }
+
+    /// Parses a list of filters separated by spaces.
+    pub fn parse_all(s: &str, kind: ListKind) -> Result<Vec<ListFilter>, Error> {
+        s.split_whitespace()
+            .map(|filter| ListFilter::parse(filter, kind))
+            .collect()
+    }
+
+    /// Returns the key of the filter in the Docker API.
+    pub fn key(&self) -> &'static str {
+        match *self {
+            ListFilter::Label(..) => "label",
+            ListFilter::Status(_) => "status",
+            ListFilter::ExitCode(_) => "exited",
+            ListFilter::Name(_) => "name",
+            ListFilter::Ancestor(_) => "ancestor",
+            ListFilter::Network(_) => "network",
+            ListFilter::Volume(_) => "volume",
+            ListFilter::Health(_) => "health",
+            ListFilter::Before(_) => "before",
+            ListFilter::Since(_) => "since",
+            ListFilter::Dangling(_) => "dangling",
+            ListFilter::Reference(_) => "reference",
+        }
+    }
+
+    /// Returns the value of the filter in the Docker API.
+    pub fn value(&self) -> String {
+        match *self {
+            ListFilter::Label(ref name, Some(ref value)) => format!("{}={}", name, value),
+            ListFilter::Label(ref name, None) => name.clone(),
+            ListFilter::ExitCode(code) => code.to_string(),
+            ListFilter::Dangling(dangling) => dangling.to_string(),
+            ListFilter::Status(ref value)
+            | ListFilter::Name(ref value)
+            | ListFilter::Ancestor(ref value)
+            | ListFilter::Network(ref value)
+            | ListFilter::Volume(ref value)
+            | ListFilter::Health(ref value)
+            | ListFilter::Before(ref value)
+            | ListFilter::Since(ref value)
+            | ListFilter::Reference(ref value) => value.clone(),
+        }
+    }
+}
+
+impl fmt::Display for ListFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.key(), self.value())
+    }
+}
+
+/// Parses a filter of the container list, as given with `--filter`.
+impl FromStr for ListFilter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ListFilter, Error> {
+        ListFilter::parse(s, ListKind::Containers)
+    }
+}
+
+/// Options of the container and image lists.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListOptions {
+    /// List the stopped containers too, or the intermediate images
+    pub all: bool,
+    /// Filters applied by the daemon
+    pub filters: Vec<ListFilter>,
+}
+
+impl ListOptions {
+    /// Returns the query string of the list endpoints, e.g. `all=1&filters=...`.
+    fn query(&self) -> String {
+        let mut query = if self.all { "all=1" } else { "all=0" }.to_string();
+        if !self.filters.is_empty() {
+            // Filters with the same key are sent together
+            let mut filters = BTreeMap::new();
+            for filter in &self.filters {
+                filters
+                    .entry(filter.key())
+                    .or_insert_with(Vec::new)
+                    .push(filter.value());
+            }
+            query.push_str("&filters=");
+            query.push_str(&percent_encode(&json!(filters).to_string()));
+        }
+        query
+    }
+}
+
+/// Encodes a string to be used in a query string.
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// How a container is deleted.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DeleteOptions {
+    /// Kill the container first if it is running
+    pub force: bool,
+    /// Also remove the anonymous volumes of the container
+    pub volumes: bool,
+}
+
+/// How an image is deleted.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ImageDeleteOptions {
+    /// Delete the image even if it has several tags or is used by stopped containers
+    pub force: bool,
+    /// Keep the untagged parents of the image
+    pub no_prune: bool,
+}
+
+/// The operations rocker performs on the Docker daemon. This is implemented by `DockerExecutor`
+/// to talk to an actual daemon, and by `fake::FakeDocker` in tests.
+pub trait DockerBackend: Send + Sync {
+    /// Returns the system info of the daemon.
+    fn info(&self) -> Result<Info, Error>;
+
+    /// Returns the version info of the daemon.
+    fn version(&self) -> Result<Version, Error>;
+
+    /// Returns the full details of the given container (including its size), as returned by the
+    /// daemon.
+    fn container_json(&self, name: &str) -> Result<Value, Error>;
+
+    /// Returns the containers matching the given options.
+    fn containers(&self, opts: &ListOptions) -> Result<Vec<Container>, Error>;
+
+    /// Returns the images matching the given options.
+    fn images(&self, opts: &ListOptions) -> Result<Vec<Image>, Error>;
+
+    fn container_pause(&self, name: &str) -> Result<(), Error>;
+
+    fn container_unpause(&self, name: &str) -> Result<(), Error>;
+
+    fn container_start(&self, name: &str) -> Result<(), Error>;
+
+    fn container_stop(&self, name: &str) -> Result<(), Error>;
+
+    fn container_restart(&self, name: &str) -> Result<(), Error>;
+
+    fn container_delete(&self, name: &str, opts: &DeleteOptions) -> Result<(), Error>;
+
+    /// Deletes the given image. If it is given by one of its tags and has other ones, only that
+    /// tag is removed.
+    fn image_delete(&self, name: &str, opts: &ImageDeleteOptions) -> Result<(), Error>;
+
+    /// Tags the given image as `repo:tag`.
+    fn image_tag(&self, name: &str, repo: &str, tag: &str) -> Result<(), Error>;
+
+    /// Streams the logs of the given container in the background, sending each line to `tx` as
+    /// soon as it is received. The stream is stopped when the receiving end of `tx` is dropped.
+    ///
+    /// `tty` indicates whether the container was started with a TTY, in which case its logs are a
+    /// raw stream rather than a multiplexed one.
+    fn container_logs(&self, name: &str, tty: bool, opts: &LogsOptions, tx: Sender<TtyLine>);
+
+    /// Starts an interactive session in the given container, running `cmd` with a TTY. The output
+    /// of the session is written to stdout, and its input is sent with `ExecSession::send()`.
+    fn exec(&self, container: &str, cmd: &[String]) -> Result<ExecSession, Error>;
+
+    /// Resizes the TTY of the given exec session.
+    fn exec_resize(&self, id: &str, size: (u16, u16)) -> Result<(), Error>;
+
+    /// Returns the exit code of the given exec session, if it has exited.
+    fn exec_exit_code(&self, id: &str) -> Result<Option<i64>, Error>;
+
+    /// Streams the resource usage statistics of the given container in the background, sending
+    /// each sample to `tx` along with `name` (the name or id of the container). The stream is
+    /// stopped when the container stops, or when the receiving end of `tx` is dropped. Once it has
+    /// ended, `None` is sent instead of a sample.
+    fn container_stats(&self, name: &str, tx: Sender<(String, Option<Stats>)>);
+
+    /// Subscribes to the events of the Docker daemon in the background, sending each of them to
+    /// `tx` as an `AppEvent::DockerEvent` as soon as it is received. The subscription is stopped
+    /// when the returned `Subscription` or the receiving end of `tx` is dropped. If the stream
+    /// ends otherwise (e.g. the daemon went away), an `AppEvent::Disconnected` is sent.
+    fn events(&self, tx: Sender<AppEvent>) -> Subscription;
+}
+
+/// Talks to the Docker daemon, using shiplift for the types of the responses and a raw HTTP
+/// transport for the requests.
 pub struct DockerExecutor {
-    docker: Docker,
+    raw: RawTransport,
     runtime: Mutex<Runtime>,
 }
 
 impl DockerExecutor {
-    pub fn new() -> Result<DockerExecutor, Error> {
-        let rt = Runtime::new()?;
-        let docker = Docker::new();
+    pub fn new(config: &ConnectionConfig) -> Result<DockerExecutor, Error> {
         Ok(DockerExecutor {
-            docker,
-            runtime: Mutex::new(rt),
+            raw: RawTransport::new(config)?,
+            runtime: Mutex::new(Runtime::new()?),
         })
     }
 
+    /// Sends a GET request to the given endpoint, and decodes the JSON response.
+    fn get_json<T>(&self, endpoint: &str) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let req = Request::get(self.raw.uri(endpoint)?).body(Body::empty())?;
+        let body = self.execute(self.raw.send(req))?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Sends a request without a body to the given endpoint, and ignores the response.
+    fn send_empty(&self, method: Method, endpoint: &str) -> Result<(), Error> {
+        let req = Request::builder()
+            .method(method)
+            .uri(self.raw.uri(endpoint)?)
+            .body(Body::empty())?;
+        self.execute(self.raw.send(req)).map(|_| ())
+    }
+
+    /// Runs the given request on the runtime, and waits for its result.
     fn execute<F, U>(&self, fut: F) -> Result<U, Error>
     where
         U: Send + 'static,
-        F: Send + 'static + Future<Item = U, Error = shiplift::errors::Error>,
+        F: Send + 'static + Future<Item = U, Error = Error>,
     {
+        // The runtime is only locked while spawning the request, so that requests made from
+        // several threads (e.g. bulk operations) run concurrently.
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        self.runtime.lock().unwrap().spawn(fut.then(move |result| {
+            let _ = tx.send(result);
+            Ok::<(), ()>(())
+        }));
+        rx.recv()
+            .map_err(|_| format_err!("The request was dropped by the runtime"))?
+    }
+}
+
+impl DockerBackend for DockerExecutor {
+    fn info(&self) -> Result<Info, Error> {
+        self.get_json("/info")
+    }
+
+    fn version(&self) -> Result<Version, Error> {
+        self.get_json("/version")
+    }
+
+    fn container_json(&self, name: &str) -> Result<Value, Error> {
+        self.get_json(&format!("/containers/{}/json", name))
+    }
+
+    fn containers(&self, opts: &ListOptions) -> Result<Vec<Container>, Error> {
+        self.get_json(&format!("/containers/json?{}", opts.query()))
+    }
+
+    fn images(&self, opts: &ListOptions) -> Result<Vec<Image>, Error> {
+        self.get_json(&format!("/images/json?{}", opts.query()))
+    }
+
+    fn container_pause(&self, name: &str) -> Result<(), Error> {
+        self.send_empty(Method::POST, &format!("/containers/{}/pause", name))
+    }
+
+    fn container_unpause(&self, name: &str) -> Result<(), Error> {
+        self.send_empty(Method::POST, &format!("/containers/{}/unpause", name))
+    }
+
+    fn container_start(&self, name: &str) -> Result<(), Error> {
+        self.send_empty(Method::POST, &format!("/containers/{}/start", name))
+    }
+
+    fn container_stop(&self, name: &str) -> Result<(), Error> {
+        self.send_empty(Method::POST, &format!("/containers/{}/stop", name))
+    }
+
+    fn container_restart(&self, name: &str) -> Result<(), Error> {
+        self.send_empty(Method::POST, &format!("/containers/{}/restart", name))
+    }
+
+    fn container_delete(&self, name: &str, opts: &DeleteOptions) -> Result<(), Error> {
+        let endpoint = format!(
+            "/containers/{}?force={}&v={}",
+            name, opts.force, opts.volumes
+        );
+        self.send_empty(Method::DELETE, &endpoint)
+    }
+
+    fn image_delete(&self, name: &str, opts: &ImageDeleteOptions) -> Result<(), Error> {
+        let endpoint = format!(
+            "/images/{}?force={}&noprune={}",
+            name, opts.force, opts.no_prune
+        );
+        self.send_empty(Method::DELETE, &endpoint)
+    }
+
+    fn image_tag(&self, name: &str, repo: &str, tag: &str) -> Result<(), Error> {
+        let endpoint = format!(
+            "/images/{}/tag?repo={}&tag={}",
+            name,
+            percent_encode(repo),
+            percent_encode(tag)
+        );
+        self.send_empty(Method::POST, &endpoint)
+    }
+
+    fn container_logs(&self, name: &str, tty: bool, opts: &LogsOptions, tx: Sender<TtyLine>) {
+        let mut endpoint = format!("/containers/{}/logs", name);
+        if let Some(query) = opts.serialize() {
+            endpoint.push('?');
+            endpoint.push_str(&query);
+        }
+        let name = name.to_string();
+        let mut tty = if tty {
+            InterlacedTty::raw()
+        } else {
+            InterlacedTty::new()
+        };
+        let fut = self
+            .raw
+            .stream_get(&endpoint)
+            // Signal the end of the stream with `None`, so that the decoder can be flushed
+            .map(Some)
+            .chain(stream::once(Ok(None)))
+            .map_err(|e| error!("Error while streaming logs: {}", e))
+            .for_each(move |chunk| {
+                let lines = match chunk {
+                    Some(bytes) => tty.feed(&bytes),
+                    None => tty.finish(),
+                };
+                for line in lines {
+                    // The receiver is gone: the view has been closed, so stop streaming
+                    tx.send(line).map_err(|_| ())?;
+                }
+                Ok(())
+            })
+            .then(move |_| {
+                debug!("Log stream for container {} ended", name);
+                Ok::<(), ()>(())
+            });
+        self.runtime.lock().unwrap().spawn(fut);
+    }
+
+    fn exec(&self, container: &str, cmd: &[String]) -> Result<ExecSession, Error> {
+        let create = json!({
+            "AttachStdin": true,
+            "AttachStdout": true,
+            "AttachStderr": true,
+            "Tty": true,
+            "Cmd": cmd,
+        });
+        let req = self
+            .raw
+            .post(&format!("/containers/{}/exec", container), &create)?;
+        let body = self.execute(self.raw.send(req))?;
+        let id = serde_json::from_slice::<Value>(&body)?["Id"]
+            .as_str()
+            .ok_or_else(|| format_err!("Missing exec id in response"))?
+            .to_string();
+
+        // Ask the daemon to hijack the connection, so that we can write to the session's stdin.
+        let mut req = self
+            .raw
+            .post(&format!("/exec/{}/start", id), &json!({ "Tty": true }))?;
+        req.headers_mut()
+            .insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+        req.headers_mut()
+            .insert(header::UPGRADE, HeaderValue::from_static("tcp"));
+        let upgraded =
+            self.execute(self.raw.request(req).map_err(Error::from).and_then(|resp| {
+                let status = resp.status();
+                if status == StatusCode::SWITCHING_PROTOCOLS {
+                    Either::A(resp.into_body().on_upgrade().map_err(Error::from))
+                } else {
+                    Either::B(future::err(error_from_response(status, &[])))
+                }
+            }))?;
+        let (reader, writer) = upgraded.split();
+
+        let (done_tx, done) = crossbeam_channel::bounded(0);
+        let output = FramedRead::new(reader, BytesCodec::new())
+            .for_each(|bytes| {
+                let mut stdout = io::stdout();
+                stdout.write_all(&bytes)?;
+                stdout.flush()
+            })
+            .map_err(|e| error!("Error while reading from exec session: {}", e))
+            .then(move |_| {
+                // Disconnecting the channel signals the end of the session
+                drop(done_tx);
+                Ok::<(), ()>(())
+            });
+
+        let (input, input_rx) = mpsc::unbounded::<Vec<u8>>();
+        let input_fut = input_rx
+            .fold(writer, |writer, bytes| {
+                tokio::io::write_all(writer, bytes)
+                    .map(|(writer, _)| writer)
+                    .map_err(|e| error!("Error while writing to exec session: {}", e))
+            })
+            .map(|_| ());
+
         let mut rt = self.runtime.lock().unwrap();
-        Ok(rt.block_on(fut)?)
+        rt.spawn(output);
+        rt.spawn(input_fut);
+        Ok(ExecSession { id, input, done })
     }
 
-    pub fn info(&self) -> Result<Info, Error> {
-        self.execute(self.docker.info())
+    fn exec_resize(&self, id: &str, (width, height): (u16, u16)) -> Result<(), Error> {
+        let endpoint = format!("/exec/{}/resize?h={}&w={}", id, height, width);
+        self.send_empty(Method::POST, &endpoint)
     }
 
-    pub fn version(&self) -> Result<Version, Error> {
-        self.execute(self.docker.version())
+    fn exec_exit_code(&self, id: &str) -> Result<Option<i64>, Error> {
+        let details: Value = self.get_json(&format!("/exec/{}/json", id))?;
+        Ok(details["ExitCode"].as_i64())
     }
 
-    pub fn container(&self, name: &str) -> Result<ContainerDetails, Error> {
-        self.execute(self.docker.containers().get(name).inspect())
+    fn container_stats(&self, name: &str, tx: Sender<(String, Option<Stats>)>) {
+        let name = name.to_string();
+        let ended_tx = tx.clone();
+        // The samples are JSON objects separated by newlines
+        let mut buf = Vec::new();
+        let fut = self
+            .raw
+            .stream_get(&format!("/containers/{}/stats", name))
+            .map_err(|e| error!("Error while streaming stats: {}", e))
+            .for_each({
+                let name = name.clone();
+                move |chunk| {
+                    buf.extend_from_slice(&chunk);
+                    while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = buf.drain(..=pos).collect();
+                        if line.iter().all(u8::is_ascii_whitespace) {
+                            continue;
+                        }
+                        let stats = stats::parse(&line).map_err(|e| {
+                            error!("Failed to parse stats: {}", e);
+                        })?;
+                        tx.send((name.clone(), Some(stats))).map_err(|_| ())?;
+                    }
+                    Ok(())
+                }
+            })
+            .then(move |_| {
+                debug!("Stats stream for container {} ended", name);
+                let _ = ended_tx.send((name, None));
+                Ok::<(), ()>(())
+            });
+        self.runtime.lock().unwrap().spawn(fut);
     }
 
-    pub fn containers(&self, opts: &ContainerListOptions) -> Result<Vec<Container>, Error> {
-        self.execute(self.docker.containers().list(opts))
+    fn events(&self, tx: Sender<AppEvent>) -> Subscription {
+        let (subscription, stopped) = Subscription::new();
+        let mut decoder = EventDecoder::new();
+        let ended_tx = tx.clone();
+        let fut = self
+            .raw
+            .stream_get("/events")
+            .map_err(|e| error!("Error while streaming events: {}", e))
+            .for_each(move |chunk| {
+                for event in decoder.feed(&chunk) {
+                    // The main loop is gone
+                    tx.send(AppEvent::DockerEvent(event)).map_err(|_| ())?;
+                }
+                Ok(())
+            })
+            .select2(stopped)
+            .then(move |res| {
+                match res {
+                    Ok(Either::B(_)) | Err(Either::B(_)) => debug!("Unsubscribed from events"),
+                    _ => {
+                        warn!("Event stream ended");
+                        // Fails if the main loop is gone, which is fine
+                        let _ = ended_tx
+                            .send(AppEvent::Disconnected("The event stream ended".to_string()));
+                    }
+                }
+                Ok::<(), ()>(())
+            });
+        self.runtime.lock().unwrap().spawn(fut);
+        subscription
     }
+}
 
-    pub fn images(&self, opts: &ImageListOptions) -> Result<Vec<Image>, Error> {
-        self.execute(self.docker.images().list(opts))
+/// Wraps a backend to refuse the operations that change the state of the daemon, for
+/// `--read-only`.
+pub struct ReadOnlyDocker(pub Arc<dyn DockerBackend>);
+
+impl ReadOnlyDocker {
+    fn refuse<T>(&self, operation: &str) -> Result<T, Error> {
+        warn!("Refusing to {} in read-only mode", operation);
+        bail!("Can't {} in read-only mode", operation)
+    }
+}
+
+impl DockerBackend for ReadOnlyDocker {
+    fn info(&self) -> Result<Info, Error> {
+        self.0.info()
+    }
+
+    fn version(&self) -> Result<Version, Error> {
+        self.0.version()
+    }
+
+    fn container_json(&self, name: &str) -> Result<Value, Error> {
+        self.0.container_json(name)
+    }
+
+    fn containers(&self, opts: &ListOptions) -> Result<Vec<Container>, Error> {
+        self.0.containers(opts)
+    }
+
+    fn images(&self, opts: &ListOptions) -> Result<Vec<Image>, Error> {
+        self.0.images(opts)
+    }
+
+    fn container_pause(&self, _name: &str) -> Result<(), Error> {
+        self.refuse("pause containers")
+    }
+
+    fn container_unpause(&self, _name: &str) -> Result<(), Error> {
+        self.refuse("unpause containers")
+    }
+
+    fn container_start(&self, _name: &str) -> Result<(), Error> {
+        self.refuse("start containers")
+    }
+
+    fn container_stop(&self, _name: &str) -> Result<(), Error> {
+        self.refuse("stop containers")
+    }
+
+    fn container_restart(&self, _name: &str) -> Result<(), Error> {
+        self.refuse("restart containers")
+    }
+
+    fn container_delete(&self, _name: &str, _opts: &DeleteOptions) -> Result<(), Error> {
+        self.refuse("delete containers")
     }
 
-    pub fn container_pause(&self, name: &str) -> Result<(), Error> {
-        self.execute(self.docker.containers().get(name).pause())
+    fn image_delete(&self, _name: &str, _opts: &ImageDeleteOptions) -> Result<(), Error> {
+        self.refuse("delete images")
     }
 
-    pub fn container_unpause(&self, name: &str) -> Result<(), Error> {
-        self.execute(self.docker.containers().get(name).unpause())
+    fn image_tag(&self, _name: &str, _repo: &str, _tag: &str) -> Result<(), Error> {
+        self.refuse("tag images")
     }
 
-    pub fn container_start(&self, name: &str) -> Result<(), Error> {
-        self.execute(self.docker.containers().get(name).start())
+    fn container_logs(&self, name: &str, tty: bool, opts: &LogsOptions, tx: Sender<TtyLine>) {
+        self.0.container_logs(name, tty, opts, tx)
     }
 
-    pub fn container_stop(&self, name: &str) -> Result<(), Error> {
-        self.execute(self.docker.containers().get(name).stop(None))
+    fn exec(&self, _container: &str, _cmd: &[String]) -> Result<ExecSession, Error> {
+        self.refuse("open a shell")
+    }
+
+    fn exec_resize(&self, id: &str, size: (u16, u16)) -> Result<(), Error> {
+        self.0.exec_resize(id, size)
+    }
+
+    fn exec_exit_code(&self, id: &str) -> Result<Option<i64>, Error> {
+        self.0.exec_exit_code(id)
+    }
+
+    fn container_stats(&self, name: &str, tx: Sender<(String, Option<Stats>)>) {
+        self.0.container_stats(name, tx)
+    }
+
+    fn events(&self, tx: Sender<AppEvent>) -> Subscription {
+        self.0.events(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::fake::FakeDocker;
+
+    fn config(host: &str, tls: bool) -> ConnectionConfig {
+        ConnectionConfig {
+            host: host.to_string(),
+            cert_path: if tls {
+                Some(PathBuf::from("/certs"))
+            } else {
+                None
+            },
+            tls_verify: tls,
+        }
+    }
+
+    #[test]
+    fn unix_sockets() {
+        let c = config("unix:///run/user/1000/podman/podman.sock", false);
+        assert_eq!(c.socket_path(), Some("/run/user/1000/podman/podman.sock"));
+        let c = config("/var/run/docker.sock", false);
+        assert_eq!(c.socket_path(), Some("/var/run/docker.sock"));
+        assert_eq!(config("tcp://build-1:2375", false).socket_path(), None);
+    }
+
+    #[test]
+    fn tcp_hosts() {
+        let url = |host, tls| config(host, tls).base_url().unwrap();
+        assert_eq!(url("tcp://build-1:2375", false), "http://build-1:2375");
+        assert_eq!(url("tcp://build-1:4243/", false), "http://build-1:4243");
+        assert_eq!(url("tcp://build-1", false), "http://build-1:2375");
+        assert_eq!(url("tcp://build-1", true), "https://build-1:2376");
+        assert_eq!(url("10.0.0.3:2376", true), "https://10.0.0.3:2376");
+        assert!(config("ssh://build-1", false).base_url().is_err());
+    }
+
+    #[test]
+    fn optional_client_certificate() {
+        let dir = env::temp_dir().join(format!("rocker-certs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let c = config("tcp://build-1", false);
+        assert!(c.ssl_connector(&dir).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn list_filters() {
+        let parse = |s: &str| s.parse::<ListFilter>();
+        assert_eq!(
+            parse("label=com.docker.compose.project=web").unwrap(),
+            ListFilter::Label(
+                "com.docker.compose.project".to_string(),
+                Some("web".to_string())
+            )
+        );
+        assert_eq!(
+            parse("label=traefik.enable").unwrap(),
+            ListFilter::Label("traefik.enable".to_string(), None)
+        );
+        assert_eq!(
+            parse("status=exited").unwrap(),
+            ListFilter::Status("exited".to_string())
+        );
+        assert_eq!(parse("exited=137").unwrap(), ListFilter::ExitCode(137));
+        assert_eq!(
+            parse("health=unhealthy").unwrap(),
+            ListFilter::Health("unhealthy".to_string())
+        );
+        assert!(parse("status=sleeping").is_err());
+        assert!(parse("exited=-1").is_err());
+        assert!(parse("label").is_err());
+        assert!(parse("dangling=true").is_err());
+
+        let images = |s: &str| ListFilter::parse_all(s, ListKind::Images);
+        assert_eq!(
+            images("dangling=false  reference=nginx:*").unwrap(),
+            vec![
+                ListFilter::Dangling(false),
+                ListFilter::Reference("nginx:*".to_string())
+            ]
+        );
+        assert!(images("dangling=maybe").is_err());
+        assert!(images("status=running").is_err());
+    }
+
+    #[test]
+    fn list_query() {
+        let opts = ListOptions {
+            all: true,
+            filters: vec![
+                "status=exited".parse().unwrap(),
+                "label=com.docker.compose.project=shop".parse().unwrap(),
+                "status=dead".parse().unwrap(),
+            ],
+        };
+        let query = opts.query();
+        assert!(query.starts_with("all=1&filters="));
+        assert!(!query[14..].contains(['"', '{', '=']));
+        // The filters are sent as a JSON object of lists, grouped by key
+        let encoded: String = json!({
+            "label": ["com.docker.compose.project=shop"],
+            "status": ["exited", "dead"],
+        })
+        .to_string();
+        assert_eq!(&query[14..], percent_encode(&encoded));
+        assert_eq!(ListOptions::default().query(), "all=0");
+        assert_eq!(
+            opts.filters[1].to_string(),
+            "label=com.docker.compose.project=shop"
+        );
     }
 
-    pub fn container_delete(&self, name: &str) -> Result<(), Error> {
-        self.execute(self.docker.containers().get(name).delete())
+    #[test]
+    fn read_only_refuses_changes() {
+        let fake = Arc::new(
+            FakeDocker::new()
+                .with_container("0123456789ab", "web", "nginx", "running")
+                .with_image("sha256:1234", "nginx:latest", 1024),
+        );
+        let docker = ReadOnlyDocker(fake.clone());
+        assert!(docker.container_stop("web").is_err());
+        assert!(docker.exec("web", &["/bin/sh".to_string()]).is_err());
+        assert!(docker
+            .image_delete("nginx:latest", &ImageDeleteOptions::default())
+            .is_err());
+        assert!(docker.image_tag("nginx:latest", "web", "v2").is_err());
+        assert!(fake.calls().is_empty());
+        assert_eq!(docker.containers(&ListOptions::default()).unwrap().len(), 1);
+        assert_eq!(fake.container_state("web").unwrap(), "running");
     }
 }
@@ -0,0 +1,857 @@
+//! An in-memory implementation of `DockerBackend`, which tests can populate and inspect.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crossbeam_channel::Sender;
+use failure::*;
+use serde_json::{json, Value};
+use shiplift::{
+    rep::{Container, Image, Info, Version},
+    LogsOptions,
+};
+
+use crate::app::AppEvent;
+use crate::docker::{
+    DeleteOptions, DockerBackend, ExecSession, ImageDeleteOptions, ListFilter, ListOptions,
+    Subscription,
+};
+use crate::events::DockerEvent;
+use crate::stats::Stats;
+use crate::tty::TtyLine;
+
+/// Creation time of everything the fake creates: 5 minutes ago, so that the times displayed
+/// relative to now don't change between runs.
+fn created() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    now.as_secs() - 300
+}
+
+/// A container known to the fake daemon.
+#[derive(Debug, Clone)]
+pub struct FakeContainer {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    /// `created`, `running`, `paused` or `exited`
+    pub state: String,
+    pub tty: bool,
+    pub labels: HashMap<String, String>,
+}
+
+impl FakeContainer {
+    fn matches(&self, name: &str) -> bool {
+        self.name == name || self.id.starts_with(name)
+    }
+
+    /// Returns whether the container matches the given filter of the container list.
+    fn matches_filter(&self, filter: &ListFilter) -> Result<bool, Error> {
+        Ok(match *filter {
+            ListFilter::Label(ref key, ref value) => match self.labels.get(key) {
+                Some(v) => value.as_ref().is_none_or(|value| v == value),
+                None => false,
+            },
+            ListFilter::Status(ref state) => self.state == *state,
+            // The fake containers always exit with 0
+            ListFilter::ExitCode(code) => self.state == "exited" && code == 0,
+            ListFilter::Name(ref name) => self.name.contains(name.as_str()),
+            ListFilter::Ancestor(ref image) => self.image == *image,
+            _ => bail!(
+                "The {} filter isn't supported by the fake backend",
+                filter.key()
+            ),
+        })
+    }
+
+    fn status(&self) -> &'static str {
+        match self.state.as_str() {
+            "running" => "Up 5 minutes",
+            "paused" => "Up 5 minutes (Paused)",
+            "exited" => "Exited (0) 5 minutes ago",
+            _ => "Created",
+        }
+    }
+
+    /// The container as returned by `GET /containers/json`
+    fn to_list_json(&self) -> Value {
+        json!({
+            "Id": self.id,
+            "Names": [format!("/{}", self.name)],
+            "Image": self.image,
+            "ImageID": format!("sha256:{}", self.id),
+            "Command": "/bin/sh",
+            "Created": created(),
+            "State": self.state,
+            "Status": self.status(),
+            "Ports": [],
+            "Labels": self.labels,
+            "SizeRw": 0,
+            "SizeRootFs": 0,
+            "HostConfig": { "NetworkMode": "default" },
+            "NetworkSettings": { "Networks": {} },
+            "Mounts": [],
+        })
+    }
+
+    /// The container as returned by `GET /containers/{id}/json`
+    fn to_inspect_json(&self) -> Value {
+        json!({
+            "Id": self.id,
+            "Created": "2018-08-28T00:00:00Z",
+            "Path": "/bin/sh",
+            "Args": [],
+            "State": {
+                "Status": self.state,
+                "Running": self.state == "running" || self.state == "paused",
+                "Paused": self.state == "paused",
+                "Restarting": false,
+                "OOMKilled": false,
+                "Dead": false,
+                "Pid": 0,
+                "ExitCode": 0,
+                "Error": "",
+                "StartedAt": "2018-08-28T00:00:00Z",
+                "FinishedAt": "0001-01-01T00:00:00Z",
+            },
+            "Image": format!("sha256:{}", self.id),
+            "Name": format!("/{}", self.name),
+            "RestartCount": 0,
+            "Config": {
+                "Hostname": &self.id[..self.id.len().min(12)],
+                "Env": [],
+                "Cmd": ["/bin/sh"],
+                "Image": self.image,
+                "Tty": self.tty,
+                "Labels": self.labels,
+            },
+            "HostConfig": { "RestartPolicy": { "Name": "no", "MaximumRetryCount": 0 } },
+            "Mounts": [],
+            "NetworkSettings": { "IPAddress": "", "Networks": {} },
+            "SizeRw": 0,
+            "SizeRootFs": 0,
+        })
+    }
+}
+
+/// An image known to the fake daemon.
+#[derive(Debug, Clone)]
+pub struct FakeImage {
+    pub id: String,
+    /// `repository:tag`, or `<none>:<none>` for dangling images
+    pub tags: Vec<String>,
+    pub size: u64,
+}
+
+impl FakeImage {
+    /// Returns whether the image has the given id, or an id starting with it.
+    fn has_id(&self, id: &str) -> bool {
+        self.id.starts_with(id) || self.id.trim_start_matches("sha256:").starts_with(id)
+    }
+
+    /// Returns the id of the image, as displayed in the messages of the daemon.
+    fn short_id(&self) -> &str {
+        let id = self.id.trim_start_matches("sha256:");
+        &id[..id.len().min(12)]
+    }
+
+    /// Returns whether the given container was created from the image.
+    fn is_used_by(&self, container: &FakeContainer) -> bool {
+        let latest = format!("{}:latest", container.image);
+        self.has_id(&container.image)
+            || self
+                .tags
+                .iter()
+                .any(|t| *t == container.image || *t == latest)
+    }
+
+    /// Returns whether the image matches the given filter of the image list.
+    fn matches_filter(&self, filter: &ListFilter) -> Result<bool, Error> {
+        let untagged = self.tags.iter().all(|t| t == "<none>:<none>");
+        Ok(match *filter {
+            // The fake images have no labels
+            ListFilter::Label(..) => false,
+            ListFilter::Dangling(dangling) => untagged == dangling,
+            ListFilter::Reference(ref reference) => self
+                .tags
+                .iter()
+                .any(|t| t == reference || t.split(':').next() == Some(reference)),
+            _ => bail!(
+                "The {} filter isn't supported by the fake backend",
+                filter.key()
+            ),
+        })
+    }
+
+    /// The image as returned by `GET /images/json`
+    fn to_list_json(&self) -> Value {
+        json!({
+            "Id": self.id,
+            "ParentId": "",
+            "RepoTags": self.tags,
+            "RepoDigests": [],
+            "Created": created(),
+            "Size": self.size,
+            "VirtualSize": self.size,
+            "Labels": {},
+        })
+    }
+}
+
+/// Returns whether something matches all the given filters, with the same semantics as the
+/// daemon: filters with different keys must all match, while it is enough for one of the filters
+/// with a given key to match, except for labels which must all match.
+fn matches_all<F>(filters: &[ListFilter], matches: F) -> Result<bool, Error>
+where
+    F: Fn(&ListFilter) -> Result<bool, Error>,
+{
+    for filter in filters {
+        let matched = if filter.key() == "label" {
+            matches(filter)?
+        } else {
+            let mut any = false;
+            for f in filters.iter().filter(|f| f.key() == filter.key()) {
+                any |= matches(f)?;
+            }
+            any
+        };
+        if !matched {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[derive(Default)]
+struct State {
+    containers: Vec<FakeContainer>,
+    images: Vec<FakeImage>,
+    /// Log lines returned for each container, by name
+    logs: HashMap<String, Vec<TtyLine>>,
+    /// Error messages returned by the operations that should fail, by operation name
+    failures: HashMap<String, String>,
+    /// Operations performed so far, e.g. `container_pause web`
+    calls: Vec<String>,
+    /// Subscriber of the events
+    events: Option<Sender<AppEvent>>,
+}
+
+impl State {
+    /// Records a call to `operation`, and returns the error it should fail with, if any.
+    fn record(&mut self, operation: &str, arg: &str) -> Result<(), Error> {
+        self.calls.push(format!("{} {}", operation, arg));
+        match self.failures.get(operation) {
+            Some(message) => Err(format_err!("{}", message)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An in-memory Docker daemon. Containers and images are added with the `with_*()` methods, and
+/// the operations performed on them can be checked with `calls()`. The fake doesn't produce any
+/// stats, and doesn't support exec sessions.
+pub struct FakeDocker {
+    state: Mutex<State>,
+}
+
+impl FakeDocker {
+    pub fn new() -> FakeDocker {
+        FakeDocker {
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Adds a container in the given state (`created`, `running`, `paused` or `exited`).
+    pub fn with_container(self, id: &str, name: &str, image: &str, state: &str) -> FakeDocker {
+        self.state.lock().unwrap().containers.push(FakeContainer {
+            id: id.to_string(),
+            name: name.to_string(),
+            image: image.to_string(),
+            state: state.to_string(),
+            tty: false,
+            labels: HashMap::new(),
+        });
+        self
+    }
+
+    /// Sets a label on the given container.
+    pub fn with_label(self, name: &str, key: &str, value: &str) -> FakeDocker {
+        self.state
+            .lock()
+            .unwrap()
+            .containers
+            .iter_mut()
+            .filter(|c| c.matches(name))
+            .for_each(|c| {
+                c.labels.insert(key.to_string(), value.to_string());
+            });
+        self
+    }
+
+    pub fn with_image(self, id: &str, tag: &str, size: u64) -> FakeDocker {
+        self.state.lock().unwrap().images.push(FakeImage {
+            id: id.to_string(),
+            tags: vec![tag.to_string()],
+            size,
+        });
+        self
+    }
+
+    /// Adds another tag to the image with the given id.
+    pub fn with_tag(self, id: &str, tag: &str) -> FakeDocker {
+        self.state
+            .lock()
+            .unwrap()
+            .images
+            .iter_mut()
+            .filter(|i| i.has_id(id))
+            .for_each(|i| i.tags.push(tag.to_string()));
+        self
+    }
+
+    /// Sets the log lines returned for the given container.
+    pub fn with_logs(self, name: &str, lines: Vec<TtyLine>) -> FakeDocker {
+        self.state
+            .lock()
+            .unwrap()
+            .logs
+            .insert(name.to_string(), lines);
+        self
+    }
+
+    /// Makes all the subsequent calls to the given operation (e.g. `container_pause`) fail with
+    /// the given message.
+    pub fn fail(&self, operation: &str, message: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .failures
+            .insert(operation.to_string(), message.to_string());
+    }
+
+    /// Returns the operations performed so far, e.g. `container_pause web`. Listing operations
+    /// aren't recorded.
+    pub fn calls(&self) -> Vec<String> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// Returns the current state of the given container, or `None` if it doesn't exist.
+    pub fn container_state(&self, name: &str) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .containers
+            .iter()
+            .find(|c| c.matches(name))
+            .map(|c| c.state.clone())
+    }
+
+    /// Returns the tags of the given image, or `None` if it doesn't exist.
+    pub fn image_tags(&self, id: &str) -> Option<Vec<String>> {
+        let state = self.state.lock().unwrap();
+        state
+            .images
+            .iter()
+            .find(|i| i.has_id(id))
+            .map(|i| i.tags.clone())
+    }
+
+    /// Sends the given event to the subscriber, if any.
+    pub fn emit(&self, event: DockerEvent) {
+        if let Some(ref tx) = self.state.lock().unwrap().events {
+            let _ = tx.send(AppEvent::DockerEvent(event));
+        }
+    }
+
+    /// Checks that the given container is in one of the `from` states, and moves it to `to`.
+    fn transition(
+        &self,
+        operation: &str,
+        name: &str,
+        from: &[&str],
+        to: &str,
+    ) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        state.record(operation, name)?;
+        let container = state
+            .containers
+            .iter_mut()
+            .find(|c| c.matches(name))
+            .ok_or_else(|| format_err!("No such container: {}", name))?;
+        if !from.contains(&container.state.as_str()) {
+            bail!("Container {} is {}", name, container.state);
+        }
+        container.state = to.to_string();
+        Ok(())
+    }
+}
+
+impl DockerBackend for FakeDocker {
+    fn info(&self) -> Result<Info, Error> {
+        let state = self.state.lock().unwrap();
+        let count = |s: &str| state.containers.iter().filter(|c| c.state == s).count();
+        let listeners = if state.events.is_some() { 1 } else { 0 };
+        let info = json!({
+            "ID": "FAKE:DOCKER",
+            "Containers": state.containers.len(),
+            "ContainersRunning": count("running"),
+            "ContainersPaused": count("paused"),
+            "ContainersStopped": count("exited"),
+            "Images": state.images.len(),
+            "Driver": "overlay2",
+            "DriverStatus": [["Backing Filesystem", "extfs"]],
+            "DockerRootDir": "/var/lib/docker",
+            "KernelVersion": "4.18.0",
+            "MemTotal": 8_589_934_592u64,
+            "MemoryLimit": true,
+            "NCPU": 4,
+            "NEventsListener": listeners,
+            "NGoroutines": 42,
+            "Name": "fake",
+            "OperatingSystem": "Fake Linux",
+            "SwapLimit": true,
+            "SystemTime": "2018-08-28T00:00:00Z",
+        });
+        Ok(serde_json::from_value(info)?)
+    }
+
+    fn version(&self) -> Result<Version, Error> {
+        let version = json!({
+            "Version": "18.06.1-ce",
+            "ApiVersion": "1.38",
+            "MinAPIVersion": "1.12",
+            "GitCommit": "e68fc7a",
+            "GoVersion": "go1.10.3",
+            "Os": "linux",
+            "Arch": "amd64",
+            "KernelVersion": "4.18.0",
+            "BuildTime": "2018-08-21T17:24:56.000000000+00:00",
+        });
+        Ok(serde_json::from_value(version)?)
+    }
+
+    fn container_json(&self, name: &str) -> Result<Value, Error> {
+        let mut state = self.state.lock().unwrap();
+        state.record("container_json", name)?;
+        state
+            .containers
+            .iter()
+            .find(|c| c.matches(name))
+            .map(FakeContainer::to_inspect_json)
+            .ok_or_else(|| format_err!("No such container: {}", name))
+    }
+
+    fn containers(&self, opts: &ListOptions) -> Result<Vec<Container>, Error> {
+        let state = self.state.lock().unwrap();
+        if let Some(message) = state.failures.get("containers") {
+            bail!("{}", message);
+        }
+        // Like the daemon, filtering by status lists the stopped containers too
+        let all = opts.all || opts.filters.iter().any(|f| f.key() == "status");
+        let mut containers = Vec::new();
+        for c in &state.containers {
+            let listed = all || c.state == "running" || c.state == "paused";
+            if listed && matches_all(&opts.filters, |f| c.matches_filter(f))? {
+                containers.push(serde_json::from_value(c.to_list_json())?);
+            }
+        }
+        Ok(containers)
+    }
+
+    fn images(&self, opts: &ListOptions) -> Result<Vec<Image>, Error> {
+        let state = self.state.lock().unwrap();
+        if let Some(message) = state.failures.get("images") {
+            bail!("{}", message);
+        }
+        let mut images = Vec::new();
+        for i in &state.images {
+            if matches_all(&opts.filters, |f| i.matches_filter(f))? {
+                images.push(serde_json::from_value(i.to_list_json())?);
+            }
+        }
+        Ok(images)
+    }
+
+    fn container_pause(&self, name: &str) -> Result<(), Error> {
+        self.transition("container_pause", name, &["running"], "paused")
+    }
+
+    fn container_unpause(&self, name: &str) -> Result<(), Error> {
+        self.transition("container_unpause", name, &["paused"], "running")
+    }
+
+    fn container_start(&self, name: &str) -> Result<(), Error> {
+        self.transition("container_start", name, &["created", "exited"], "running")
+    }
+
+    fn container_stop(&self, name: &str) -> Result<(), Error> {
+        self.transition("container_stop", name, &["running", "paused"], "exited")
+    }
+
+    fn container_restart(&self, name: &str) -> Result<(), Error> {
+        let from = &["created", "running", "exited"];
+        self.transition("container_restart", name, from, "running")
+    }
+
+    fn container_delete(&self, name: &str, opts: &DeleteOptions) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        let mut arg = name.to_string();
+        if opts.force {
+            arg.push_str(" --force");
+        }
+        if opts.volumes {
+            arg.push_str(" --volumes");
+        }
+        state.record("container_delete", &arg)?;
+        let index = state
+            .containers
+            .iter()
+            .position(|c| c.matches(name))
+            .ok_or_else(|| format_err!("No such container: {}", name))?;
+        if state.containers[index].state == "running" && !opts.force {
+            bail!("You cannot remove a running container {}", name);
+        }
+        state.containers.remove(index);
+        Ok(())
+    }
+
+    fn image_delete(&self, name: &str, opts: &ImageDeleteOptions) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        let mut arg = name.to_string();
+        if opts.force {
+            arg.push_str(" --force");
+        }
+        if opts.no_prune {
+            arg.push_str(" --no-prune");
+        }
+        state.record("image_delete", &arg)?;
+        let by_tag = state
+            .images
+            .iter()
+            .position(|i| i.tags.iter().any(|t| t == name));
+        let index = by_tag
+            .or_else(|| state.images.iter().position(|i| i.has_id(name)))
+            .ok_or_else(|| format_err!("No such image: {}", name))?;
+        let image = &mut state.images[index];
+        // Like the daemon, deleting one of the tags of an image only untags it
+        if by_tag.is_some() && image.tags.len() > 1 {
+            image.tags.retain(|t| t != name);
+            return Ok(());
+        }
+        if by_tag.is_none() && image.tags.len() > 1 && !opts.force {
+            bail!(
+                "conflict: unable to delete {} (must be forced) - image is referenced in multiple \
+                 repositories",
+                image.short_id()
+            );
+        }
+        let image = &state.images[index];
+        if let Some(container) = state.containers.iter().find(|c| image.is_used_by(c)) {
+            let running = container.state == "running" || container.state == "paused";
+            if running || !opts.force {
+                let (reason, state) = if running {
+                    ("cannot be forced", "running")
+                } else {
+                    ("must be forced", "stopped")
+                };
+                bail!(
+                    "conflict: unable to delete {} ({}) - image is being used by {} container {}",
+                    image.short_id(),
+                    reason,
+                    state,
+                    &container.id[..container.id.len().min(12)]
+                );
+            }
+        }
+        state.images.remove(index);
+        Ok(())
+    }
+
+    fn image_tag(&self, name: &str, repo: &str, tag: &str) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        let reference = format!("{}:{}", repo, tag);
+        state.record("image_tag", &format!("{} {}", name, reference))?;
+        let index = state
+            .images
+            .iter()
+            .position(|i| i.has_id(name) || i.tags.iter().any(|t| t == name))
+            .ok_or_else(|| format_err!("No such image: {}", name))?;
+        // The tag moves from the image that had it, if any
+        for image in &mut state.images {
+            image.tags.retain(|t| *t != reference);
+        }
+        let image = &mut state.images[index];
+        image.tags.retain(|t| t != "<none>:<none>");
+        image.tags.push(reference);
+        Ok(())
+    }
+
+    fn container_logs(&self, name: &str, _tty: bool, _opts: &LogsOptions, tx: Sender<TtyLine>) {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(format!("container_logs {}", name));
+        for line in state.logs.get(name).cloned().unwrap_or_default() {
+            let _ = tx.send(line);
+        }
+    }
+
+    fn exec(&self, container: &str, _cmd: &[String]) -> Result<ExecSession, Error> {
+        let mut state = self.state.lock().unwrap();
+        state.record("exec", container)?;
+        bail!("Exec sessions aren't supported by the fake backend")
+    }
+
+    fn exec_resize(&self, _id: &str, _size: (u16, u16)) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn exec_exit_code(&self, _id: &str) -> Result<Option<i64>, Error> {
+        Ok(Some(0))
+    }
+
+    fn container_stats(&self, name: &str, _tx: Sender<(String, Option<Stats>)>) {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(format!("container_stats {}", name));
+    }
+
+    fn events(&self, tx: Sender<AppEvent>) -> Subscription {
+        self.state.lock().unwrap().events = Some(tx);
+        Subscription::new().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::ListKind;
+    use crossbeam_channel::unbounded;
+
+    fn fake() -> FakeDocker {
+        FakeDocker::new()
+            .with_container("0123456789ab", "web", "nginx", "running")
+            .with_container("ba9876543210", "db", "postgres", "exited")
+            .with_image("sha256:1234", "nginx:latest", 1024)
+    }
+
+    #[test]
+    fn lists_running_containers_by_default() {
+        let docker = fake();
+        let running = docker.containers(&ListOptions::default()).unwrap();
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].names, vec!["/web".to_string()]);
+        assert_eq!(running[0].status, "Up 5 minutes");
+
+        let all = ListOptions {
+            all: true,
+            filters: Vec::new(),
+        };
+        assert_eq!(docker.containers(&all).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn filters() {
+        let docker = fake()
+            .with_container("456789abcdef", "cache", "redis", "exited")
+            .with_label("web", "com.docker.compose.project", "shop")
+            .with_label("db", "com.docker.compose.project", "shop")
+            .with_image("sha256:5678", "<none>:<none>", 512);
+        let containers = |filters: &str| -> Vec<String> {
+            let opts = ListOptions {
+                all: false,
+                filters: ListFilter::parse_all(filters, ListKind::Containers).unwrap(),
+            };
+            let containers = docker.containers(&opts).unwrap();
+            containers.into_iter().map(|c| c.names[0].clone()).collect()
+        };
+        assert_eq!(containers("status=exited"), vec!["/db", "/cache"]);
+        assert_eq!(containers("status=exited status=running").len(), 3);
+        assert_eq!(
+            containers("status=exited label=com.docker.compose.project=shop"),
+            vec!["/db"]
+        );
+        assert_eq!(containers("name=we"), vec!["/web"]);
+        assert!(docker
+            .containers(&ListOptions {
+                all: true,
+                filters: vec![ListFilter::Health("healthy".to_string())],
+            })
+            .is_err());
+
+        let images = |filters: &str| -> Vec<String> {
+            let opts = ListOptions {
+                all: true,
+                filters: ListFilter::parse_all(filters, ListKind::Images).unwrap(),
+            };
+            let images = docker.images(&opts).unwrap();
+            images.into_iter().map(|i| i.id).collect()
+        };
+        assert_eq!(images("dangling=true"), vec!["sha256:5678"]);
+        assert_eq!(images("reference=nginx"), vec!["sha256:1234"]);
+    }
+
+    #[test]
+    fn operations_change_the_container_state() {
+        let docker = fake();
+        docker.container_pause("web").unwrap();
+        assert_eq!(docker.container_state("web").unwrap(), "paused");
+        docker.container_unpause("0123").unwrap();
+        docker.container_restart("web").unwrap();
+        assert_eq!(docker.container_state("web").unwrap(), "running");
+        docker.container_stop("web").unwrap();
+        assert_eq!(docker.container_state("web").unwrap(), "exited");
+        docker
+            .container_delete("web", &DeleteOptions::default())
+            .unwrap();
+        assert_eq!(docker.container_state("web"), None);
+        assert_eq!(
+            docker.calls(),
+            vec![
+                "container_pause web",
+                "container_unpause 0123",
+                "container_restart web",
+                "container_stop web",
+                "container_delete web",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_operations_fail() {
+        let docker = fake();
+        assert!(docker.container_unpause("web").is_err());
+        assert!(docker
+            .container_delete("web", &DeleteOptions::default())
+            .is_err());
+        assert!(docker.container_start("nope").is_err());
+        assert_eq!(docker.container_state("web").unwrap(), "running");
+    }
+
+    #[test]
+    fn running_containers_can_be_force_deleted() {
+        let docker = fake();
+        let opts = DeleteOptions {
+            force: true,
+            volumes: true,
+        };
+        docker.container_delete("web", &opts).unwrap();
+        assert_eq!(docker.container_state("web"), None);
+        assert_eq!(
+            docker.calls(),
+            vec!["container_delete web --force --volumes"]
+        );
+    }
+
+    #[test]
+    fn image_operations() {
+        let docker = FakeDocker::new()
+            .with_container("0123456789ab", "web", "nginx", "running")
+            .with_container("ba9876543210", "db", "postgres:10", "exited")
+            .with_image("sha256:1234", "nginx:latest", 1024)
+            .with_image("sha256:5678", "postgres:10", 2048)
+            .with_tag("sha256:5678", "postgres:latest");
+        let delete = |name, force| {
+            let opts = ImageDeleteOptions {
+                force,
+                no_prune: false,
+            };
+            docker.image_delete(name, &opts).map_err(|e| e.to_string())
+        };
+        // Deleting one of the tags of an image only untags it
+        delete("postgres:latest", false).unwrap();
+        assert_eq!(docker.image_tags("5678").unwrap(), vec!["postgres:10"]);
+        docker.image_tag("postgres:10", "db", "v2").unwrap();
+        assert_eq!(
+            docker.image_tags("5678").unwrap(),
+            vec!["postgres:10", "db:v2"]
+        );
+        assert_eq!(
+            delete("5678", false).unwrap_err(),
+            "conflict: unable to delete 5678 (must be forced) - image is referenced in multiple \
+             repositories"
+        );
+        delete("db:v2", false).unwrap();
+
+        assert_eq!(
+            delete("postgres:10", false).unwrap_err(),
+            "conflict: unable to delete 5678 (must be forced) - image is being used by stopped \
+             container ba9876543210"
+        );
+        assert_eq!(
+            delete("nginx:latest", true).unwrap_err(),
+            "conflict: unable to delete 1234 (cannot be forced) - image is being used by running \
+             container 0123456789ab"
+        );
+        delete("postgres:10", true).unwrap();
+        assert_eq!(docker.image_tags("5678"), None);
+        assert_eq!(
+            docker.calls(),
+            vec![
+                "image_delete postgres:latest",
+                "image_tag postgres:10 db:v2",
+                "image_delete 5678",
+                "image_delete db:v2",
+                "image_delete postgres:10",
+                "image_delete nginx:latest --force",
+                "image_delete postgres:10 --force",
+            ]
+        );
+    }
+
+    #[test]
+    fn scripted_failures() {
+        let docker = fake();
+        docker.fail("container_stop", "permission denied");
+        let err = docker.container_stop("web").unwrap_err();
+        assert_eq!(err.to_string(), "permission denied");
+        assert_eq!(docker.container_state("web").unwrap(), "running");
+
+        docker.fail("containers", "daemon is down");
+        assert!(docker.containers(&ListOptions::default()).is_err());
+    }
+
+    #[test]
+    fn info_reflects_the_state() {
+        let docker = fake();
+        let info = docker.info().unwrap();
+        assert_eq!(info.containers, 2);
+        assert_eq!(info.images, 1);
+        assert_eq!(docker.version().unwrap().api_version, "1.38");
+
+        let images = docker.images(&ListOptions::default()).unwrap();
+        assert_eq!(images[0].id, "sha256:1234");
+    }
+
+    #[test]
+    fn inspect() {
+        let docker = fake();
+        let details = docker.container_json("db").unwrap();
+        assert_eq!(details["Name"], "/db");
+        assert_eq!(details["State"]["Status"], "exited");
+        assert_eq!(details["Config"]["Tty"], false);
+        assert!(docker.container_json("nope").is_err());
+    }
+
+    #[test]
+    fn logs_and_events() {
+        let docker = fake().with_logs("web", vec![TtyLine::StdOut("hello".to_string())]);
+        let (tx, rx) = unbounded();
+        let options = LogsOptions::builder().build();
+        docker.container_logs("web", false, &options, tx);
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![TtyLine::StdOut("hello".to_string())]
+        );
+
+        let (tx, rx) = unbounded();
+        let _subscription = docker.events(tx);
+        let event = DockerEvent::from_json(&json!({
+            "Type": "container",
+            "Action": "start",
+            "Actor": { "ID": "0123456789ab", "Attributes": { "name": "web" } },
+            "time": created(),
+        }))
+        .unwrap();
+        docker.emit(event.clone());
+        match rx.try_recv() {
+            Ok(AppEvent::DockerEvent(received)) => assert_eq!(received, event),
+            _ => panic!("No event received"),
+        }
+    }
+}
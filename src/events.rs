@@ -0,0 +1,110 @@
+//! Events sent by the Docker daemon, as returned by the `/events` endpoint.
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+/// Maximum number of events kept in memory. Older events are discarded first.
+pub const MAX_EVENTS: usize = 1000;
+
+/// A single event reported by the Docker daemon, e.g. a container starting or an image being
+/// pulled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DockerEvent {
+    /// Type of object the event is about, e.g. `container`, `image`, `network` or `volume`
+    pub typ: String,
+    /// What happened to the object, e.g. `start`, `die` or `pull`
+    pub action: String,
+    /// ID of the object
+    pub actor_id: String,
+    /// Attributes of the object, e.g. its name or image
+    pub attributes: HashMap<String, String>,
+    /// When the event happened
+    pub time: SystemTime,
+}
+
+impl DockerEvent {
+    /// Parses an event from its JSON representation. Returns `None` if it doesn't look like an
+    /// event.
+    pub fn from_json(json: &Value) -> Option<DockerEvent> {
+        let attributes = json["Actor"]["Attributes"]
+            .as_object()
+            .map(|attrs| {
+                attrs
+                    .iter()
+                    .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let time = match json["timeNano"].as_u64() {
+            Some(nanos) => UNIX_EPOCH + Duration::from_nanos(nanos),
+            None => UNIX_EPOCH + Duration::from_secs(json["time"].as_u64().unwrap_or(0)),
+        };
+
+        Some(DockerEvent {
+            typ: json["Type"].as_str()?.to_string(),
+            action: json["Action"].as_str()?.to_string(),
+            actor_id: json["Actor"]["ID"].as_str().unwrap_or_default().to_string(),
+            attributes,
+            time,
+        })
+    }
+
+    /// Returns a human readable name for the object the event is about: its name if it has one,
+    /// its (short) ID otherwise.
+    pub fn actor(&self) -> &str {
+        match self.attributes.get("name") {
+            Some(name) => name,
+            None if self.actor_id.len() > 12 && !self.actor_id.contains(':') => {
+                &self.actor_id[..12]
+            }
+            None => &self.actor_id,
+        }
+    }
+
+    /// Returns `true` if the event may have changed the list of containers or images, or the
+    /// state of one of them. Events like execs being started or TTYs being resized don't.
+    pub fn changes_state(&self) -> bool {
+        let action = self.action.as_str();
+        !(action.starts_with("exec_")
+            || action == "attach"
+            || action == "resize"
+            || action == "top"
+            || action == "archive-path"
+            || action == "extract-to-dir")
+    }
+}
+
+/// Incremental decoder for the `/events` stream, which is made of JSON objects separated by
+/// newlines. Objects split across chunks are buffered until they are complete.
+pub struct EventDecoder {
+    buf: Vec<u8>,
+}
+
+impl EventDecoder {
+    pub fn new() -> EventDecoder {
+        EventDecoder { buf: Vec::new() }
+    }
+
+    /// Feeds the next chunk of the stream to the decoder, and returns the events it completes.
+    /// Lines that can't be parsed are logged and skipped.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<DockerEvent> {
+        self.buf.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=pos).collect();
+            let line = &line[..pos];
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match serde_json::from_slice::<Value>(line) {
+                Ok(json) => match DockerEvent::from_json(&json) {
+                    Some(event) => events.push(event),
+                    None => warn!("Ignoring unknown event: {}", json),
+                },
+                Err(e) => warn!("Failed to parse event: {}", e),
+            }
+        }
+        events
+    }
+}
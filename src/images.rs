@@ -0,0 +1,124 @@
+//! Operations on images: deleting, tagging and untagging them.
+use crate::docker::{DockerBackend, ImageDeleteOptions};
+
+/// An operation on an image.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageOperation {
+    Delete(ImageDeleteOptions),
+    /// Adds a tag, given as a repository and a tag
+    Tag(String, String),
+    /// Removes one of the tags of the image, which must have other ones
+    Untag(String),
+}
+
+/// An image an operation is applied to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageTarget {
+    pub id: String,
+    /// Name of the image, used in the messages: its first tag, or its id if it has none
+    pub name: String,
+    /// Tags of the image
+    pub tags: Vec<String>,
+}
+
+impl ImageOperation {
+    /// Returns the name of the operation.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ImageOperation::Delete(_) => "delete-image",
+            ImageOperation::Tag(..) => "tag",
+            ImageOperation::Untag(_) => "untag",
+        }
+    }
+
+    /// Returns the verb used in messages.
+    pub fn verb(&self) -> &'static str {
+        match *self {
+            ImageOperation::Delete(_) => "delete",
+            ImageOperation::Tag(..) => "tag",
+            ImageOperation::Untag(_) => "untag",
+        }
+    }
+
+    fn past_participle(&self) -> &'static str {
+        match *self {
+            ImageOperation::Delete(_) => "Deleted",
+            ImageOperation::Tag(..) => "Tagged",
+            ImageOperation::Untag(_) => "Untagged",
+        }
+    }
+
+    /// Applies the operation to the given image. Returns a description of the outcome, e.g.
+    /// `Tagged image nginx:latest as web:v2`, which is an error if the operation failed.
+    pub fn run(&self, docker: &dyn DockerBackend, image: &ImageTarget) -> Result<String, String> {
+        info!("Applying {} to image {}", self.name(), image.name);
+        let (result, what) = match *self {
+            ImageOperation::Delete(ref opts) => (
+                docker.image_delete(&image.id, opts),
+                format!("image {}", image.name),
+            ),
+            ImageOperation::Tag(ref repo, ref tag) => (
+                docker.image_tag(&image.id, repo, tag),
+                format!("image {} as {}:{}", image.name, repo, tag),
+            ),
+            // Deleting a tag of an image that has other ones only removes the tag
+            ImageOperation::Untag(ref tag) => (
+                docker.image_delete(tag, &ImageDeleteOptions::default()),
+                tag.clone(),
+            ),
+        };
+        match result {
+            Ok(()) => Ok(format!("{} {}", self.past_participle(), what)),
+            Err(err) => {
+                error!("Failed to {} {}: {}", self.verb(), what, err);
+                Err(format!("Failed to {} {}: {}", self.verb(), what, err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::fake::FakeDocker;
+
+    #[test]
+    fn image_operations() {
+        let docker = FakeDocker::new()
+            .with_container("0123", "web", "nginx", "running")
+            .with_image("sha256:1234", "nginx:latest", 1024)
+            .with_tag("sha256:1234", "nginx:1.17");
+        let image = ImageTarget {
+            id: "sha256:1234".to_string(),
+            name: "nginx:latest".to_string(),
+            tags: vec!["nginx:latest".to_string(), "nginx:1.17".to_string()],
+        };
+        let tag = ImageOperation::Tag("web".to_string(), "v2".to_string());
+        assert_eq!(
+            tag.run(&docker, &image).unwrap(),
+            "Tagged image nginx:latest as web:v2"
+        );
+        let untag = ImageOperation::Untag("nginx:1.17".to_string());
+        assert_eq!(untag.run(&docker, &image).unwrap(), "Untagged nginx:1.17");
+        assert_eq!(
+            docker.image_tags("1234").unwrap(),
+            vec!["nginx:latest", "web:v2"]
+        );
+
+        let force = ImageOperation::Delete(ImageDeleteOptions {
+            force: true,
+            no_prune: false,
+        });
+        assert_eq!(
+            force.run(&docker, &image).unwrap_err(),
+            "Failed to delete image nginx:latest: conflict: unable to delete 1234 (cannot be \
+             forced) - image is being used by running container 0123"
+        );
+        docker.container_stop("web").unwrap();
+        assert_eq!(
+            force.run(&docker, &image).unwrap(),
+            "Deleted image nginx:latest"
+        );
+        assert_eq!(docker.image_tags("1234"), None);
+    }
+}
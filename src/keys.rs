@@ -0,0 +1,485 @@
+//! The registry of the actions of the application and of the keys they are bound to, which can be
+//! changed in the configuration file.
+//!
+//! Key presses are translated to actions by the `Keymap` before being handled by the application
+//! and the views, and the help screen is generated from it.
+use std::collections::HashMap;
+
+use failure::*;
+use termion::event::Key;
+
+/// Scope of the actions available in every view
+pub const GLOBAL: &str = "global";
+
+/// An action the user can trigger with a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Refresh,
+    Suspend,
+    Help,
+    Images,
+    Info,
+    Stats,
+    Events,
+    Contexts,
+    AppLogs,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Details,
+    Logs,
+    Shell,
+    Pause,
+    Unpause,
+    Stop,
+    Start,
+    Restart,
+    Delete,
+    ForceDelete,
+    Tag,
+    Untag,
+    ToggleAll,
+    ToggleMark,
+    MarkAll,
+    MarkMatching,
+    UnmarkAll,
+    ToggleSection,
+    ExpandAll,
+    CollapseAll,
+    ToggleRaw,
+    Graphs,
+    ToggleFollow,
+    Filter,
+    NextMatch,
+    PreviousMatch,
+    Sort,
+    ReverseSort,
+    EditFilters,
+    GroupByProject,
+    SwitchContext,
+    Confirm,
+    Cancel,
+}
+
+/// The registration of an action in a scope.
+#[derive(Debug, PartialEq)]
+pub struct Binding {
+    pub action: Action,
+    /// Name of the view the action is available in (see `View::name()`), or `GLOBAL`
+    pub scope: &'static str,
+    /// Name of the action in the configuration file
+    pub id: &'static str,
+    /// Keys the action is bound to by default
+    pub keys: &'static [Key],
+    /// What the action does, displayed in the help screen
+    pub description: &'static str,
+}
+
+macro_rules! binding {
+    ($scope:expr, $id:expr, $action:ident, [$($key:expr),+], $description:expr) => {
+        Binding {
+            action: Action::$action,
+            scope: $scope,
+            id: $id,
+            keys: &[$($key),+],
+            description: $description,
+        }
+    };
+}
+
+/// All the actions, grouped by scope in the order they are listed in the help screen
+#[rustfmt::skip]
+pub const BINDINGS: &[Binding] = &[
+    binding!(GLOBAL, "help", Help, [Key::Char('?')], "Show this help"),
+    binding!(GLOBAL, "quit", Quit, [Key::Char('q')],
+             "Close the current view, or quit from the container list"),
+    binding!(GLOBAL, "refresh", Refresh, [Key::Char('R')], "Refresh the current view"),
+    binding!(GLOBAL, "suspend", Suspend, [Key::Ctrl('z')], "Suspend rkr (resume it with fg)"),
+    binding!(GLOBAL, "images", Images, [Key::Char('i')], "Show the images"),
+    binding!(GLOBAL, "info", Info, [Key::Char('v')], "Show the Docker daemon info"),
+    binding!(GLOBAL, "stats", Stats, [Key::Char('t')],
+             "Show the resource usage of the running containers"),
+    binding!(GLOBAL, "events", Events, [Key::Char('E')], "Show the Docker events"),
+    binding!(GLOBAL, "contexts", Contexts, [Key::Char('C')], "Show the docker CLI contexts"),
+    binding!(GLOBAL, "app_logs", AppLogs, [Key::Char('L')], "Show the application logs"),
+    binding!(GLOBAL, "up", Up, [Key::Up, Key::Char('k')], "Move up"),
+    binding!(GLOBAL, "down", Down, [Key::Down, Key::Char('j')], "Move down"),
+    binding!(GLOBAL, "page_up", PageUp, [Key::PageUp, Key::Ctrl('u')], "Move up one page"),
+    binding!(GLOBAL, "page_down", PageDown, [Key::PageDown, Key::Ctrl('d')], "Move down one page"),
+    binding!(GLOBAL, "top", Top, [Key::Home, Key::Char('g')], "Go to the top"),
+    binding!(GLOBAL, "bottom", Bottom, [Key::End, Key::Char('G')],
+             "Go to the bottom, and follow new lines"),
+
+    binding!("containers", "details", Details, [Key::Char('\n')],
+             "Show the details of the container, or expand or collapse the selected group"),
+    binding!("containers", "logs", Logs, [Key::Char('l')], "Show the logs of the container"),
+    binding!("containers", "shell", Shell, [Key::Char('e')], "Open a shell in the container"),
+    binding!("containers", "pause", Pause, [Key::Char('p')],
+             "Pause the marked containers, or the selected container or group"),
+    binding!("containers", "unpause", Unpause, [Key::Char('P')],
+             "Unpause the marked containers, or the selected container or group"),
+    binding!("containers", "stop", Stop, [Key::Char('s')],
+             "Stop the marked containers, or the selected container or group"),
+    binding!("containers", "start", Start, [Key::Char('S')],
+             "Start the marked containers, or the selected container or group"),
+    binding!("containers", "restart", Restart, [Key::Char('r')],
+             "Restart the marked containers, or the selected container or group"),
+    binding!("containers", "delete", Delete, [Key::Char('d')],
+             "Delete the marked containers, or the selected container or group"),
+    binding!("containers", "mark", ToggleMark, [Key::Char(' ')], "Mark or unmark the container"),
+    binding!("containers", "mark_all", MarkAll, [Key::Char('*')], "Mark all the containers"),
+    binding!("containers", "mark_matching", MarkMatching, [Key::Char('+')],
+             "Mark the containers whose name or image contains some text"),
+    binding!("containers", "unmark_all", UnmarkAll, [Key::Char('-')], "Unmark all the containers"),
+    binding!("containers", "toggle_all", ToggleAll, [Key::Char('a')],
+             "Toggle between all the containers and the running ones"),
+    binding!("containers", "filter", Filter, [Key::Char('/')],
+             "Filter the containers (Tab: fuzzy matching, key=value: labels)"),
+    binding!("containers", "next_match", NextMatch, [Key::Char('n')],
+             "Go to the next container matching the filter"),
+    binding!("containers", "previous_match", PreviousMatch, [Key::Char('N')],
+             "Go to the previous container matching the filter"),
+    binding!("containers", "sort", Sort, [Key::Char('o')],
+             "Sort by the next column, or restore the order of the daemon after the last one"),
+    binding!("containers", "reverse_sort", ReverseSort, [Key::Char('O')],
+             "Switch between ascending and descending order"),
+    binding!("containers", "daemon_filters", EditFilters, [Key::Char('F')],
+             "Edit the filters applied by the Docker daemon, e.g. status=exited name=web"),
+    binding!("containers", "group", GroupByProject, [Key::Char('c')],
+             "Group the containers by Docker Compose project and service"),
+
+    binding!("images", "filter", Filter, [Key::Char('/')],
+             "Filter the images (Tab: fuzzy matching, key=value: labels)"),
+    binding!("images", "next_match", NextMatch, [Key::Char('n')],
+             "Go to the next image matching the filter"),
+    binding!("images", "previous_match", PreviousMatch, [Key::Char('N')],
+             "Go to the previous image matching the filter"),
+    binding!("images", "sort", Sort, [Key::Char('o')],
+             "Sort by the next column, or restore the order of the daemon after the last one"),
+    binding!("images", "reverse_sort", ReverseSort, [Key::Char('O')],
+             "Switch between ascending and descending order"),
+    binding!("images", "daemon_filters", EditFilters, [Key::Char('F')],
+             "Edit the filters applied by the Docker daemon, e.g. dangling=true"),
+    binding!("images", "delete", Delete, [Key::Char('d')], "Delete the image"),
+    binding!("images", "force_delete", ForceDelete, [Key::Char('D')],
+             "Delete the image, even if it has several tags or is used by stopped containers"),
+    binding!("images", "tag", Tag, [Key::Char('T')], "Add a tag to the image"),
+    binding!("images", "untag", Untag, [Key::Char('U')],
+             "Remove one of the tags of the image (Tab: next tag)"),
+
+    binding!("container_details", "toggle_section", ToggleSection,
+             [Key::Char('\n'), Key::Char(' ')], "Expand or collapse the selected section"),
+    binding!("container_details", "expand_all", ExpandAll, [Key::Char('o')],
+             "Expand all the sections"),
+    binding!("container_details", "collapse_all", CollapseAll, [Key::Char('c')],
+             "Collapse all the sections"),
+    binding!("container_details", "toggle_raw", ToggleRaw, [Key::Char('r')], "Toggle the raw JSON"),
+    binding!("container_details", "graphs", Graphs, [Key::Char('u')],
+             "Show the resource usage graphs"),
+
+    binding!("container_stats", "graphs", Graphs, [Key::Char('\n')],
+             "Show the resource usage graphs"),
+
+    binding!("container_logs", "toggle_follow", ToggleFollow, [Key::Char('f')],
+             "Toggle follow mode"),
+
+    binding!("events", "filter", Filter, [Key::Char('/')], "Filter the events"),
+
+    binding!("contexts", "switch", SwitchContext, [Key::Char('\n')],
+             "Switch to the selected context"),
+
+    binding!("dialog", "confirm", Confirm, [Key::Char('y'), Key::Char('\n')],
+             "Confirm the operation"),
+    binding!("dialog", "cancel", Cancel, [Key::Char('n'), Key::Esc], "Cancel the operation"),
+];
+
+/// Returns the binding of the action with the given scope and id.
+pub fn find_binding(scope: &str, id: &str) -> Option<&'static Binding> {
+    BINDINGS.iter().find(|b| b.scope == scope && b.id == id)
+}
+
+/// Returns the title of the given scope in the help screen.
+pub fn scope_title(scope: &str) -> &'static str {
+    match scope {
+        GLOBAL => "Global",
+        "containers" => "Container list",
+        "images" => "Image list",
+        "container_details" => "Container details",
+        "container_stats" => "Container stats",
+        "container_logs" => "Container logs",
+        "events" => "Docker events",
+        "contexts" => "Contexts",
+        "dialog" => "Confirmation dialog",
+        _ => "Other",
+    }
+}
+
+/// Translates the keys pressed by the user to actions.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    /// For each scope, the action each key is bound to
+    actions: HashMap<(&'static str, Key), Action>,
+    /// The keys each action is bound to, in the order of `BINDINGS`
+    keys: Vec<(&'static Binding, Vec<Key>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap::new(&[]).unwrap()
+    }
+}
+
+impl Keymap {
+    /// Creates a keymap binding the given actions to the given keys instead of their default ones.
+    /// Returns an error if several actions of the same scope end up bound to the same key.
+    pub fn new(overrides: &[(&'static Binding, Vec<Key>)]) -> Result<Keymap, Error> {
+        let mut actions = HashMap::new();
+        let mut bound_to: HashMap<(&'static str, Key), &'static str> = HashMap::new();
+        let mut all_keys = Vec::new();
+        for binding in BINDINGS {
+            let keys = overrides
+                .iter()
+                .find(|&&(b, _)| b == binding)
+                .map(|(_, keys)| keys.clone())
+                .unwrap_or_else(|| binding.keys.to_vec());
+            for &key in &keys {
+                if let Some(other) = bound_to.insert((binding.scope, key), binding.id) {
+                    bail!(
+                        "{} is bound to both {}.{} and {}.{}",
+                        key_name(key),
+                        binding.scope,
+                        other,
+                        binding.scope,
+                        binding.id
+                    );
+                }
+                actions.insert((binding.scope, key), binding.action);
+            }
+            all_keys.push((binding, keys));
+        }
+        Ok(Keymap {
+            actions,
+            keys: all_keys,
+        })
+    }
+
+    /// Returns the action a key pressed in the view with the given name is bound to. Actions of
+    /// the view take precedence over the global ones.
+    pub fn action(&self, view: &'static str, key: Key) -> Option<Action> {
+        self.actions
+            .get(&(view, key))
+            .or_else(|| self.actions.get(&(GLOBAL, key)))
+            .cloned()
+    }
+
+    /// Returns the keys the given action of the given scope is bound to.
+    pub fn keys(&self, scope: &str, action: Action) -> &[Key] {
+        self.keys
+            .iter()
+            .find(|(b, _)| b.scope == scope && b.action == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    /// Returns all the actions with the keys they are bound to, in the order of `BINDINGS`.
+    pub fn bindings(&self) -> &[(&'static Binding, Vec<Key>)] {
+        &self.keys
+    }
+}
+
+/// Parses a key, given either as a single character (e.g. `s` or `S`), a name (`enter`, `space`,
+/// `tab`, `esc`, `backspace`, `delete`, `insert`, `up`, `down`, `left`, `right`, `home`, `end`,
+/// `pageup`, `pagedown`, or `f1` to `f12`), or a character with a modifier (`ctrl-d`, `alt-x`).
+pub fn parse_key(s: &str) -> Result<Key, Error> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(Key::Char(c));
+    }
+
+    let lower = s.to_lowercase();
+    let key = match lower.as_str() {
+        "enter" => Key::Char('\n'),
+        "space" => Key::Char(' '),
+        "tab" => Key::Char('\t'),
+        "esc" => Key::Esc,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "insert" => Key::Insert,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        _ => {
+            let modified = |prefix: &str| {
+                let mut chars = s[prefix.len()..].chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(c),
+                    _ => None,
+                }
+            };
+            if lower.starts_with("ctrl-") {
+                match modified("ctrl-") {
+                    Some(c) => Key::Ctrl(c.to_ascii_lowercase()),
+                    None => bail!("invalid key {}", s),
+                }
+            } else if lower.starts_with("alt-") {
+                match modified("alt-") {
+                    Some(c) => Key::Alt(c),
+                    None => bail!("invalid key {}", s),
+                }
+            } else if let Some(n) = lower.strip_prefix('f') {
+                match n.parse() {
+                    Ok(n) if (1..=12).contains(&n) => Key::F(n),
+                    _ => bail!("invalid key {}", s),
+                }
+            } else {
+                bail!("invalid key {}", s)
+            }
+        }
+    };
+    Ok(key)
+}
+
+/// Returns the name of a key, as accepted by `parse_key()`.
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Char('\n') => "enter".to_string(),
+        Key::Char(' ') => "space".to_string(),
+        Key::Char('\t') => "tab".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("ctrl-{}", c),
+        Key::Alt(c) => format!("alt-{}", c),
+        Key::F(n) => format!("f{}", n),
+        Key::Esc => "esc".to_string(),
+        Key::Backspace => "backspace".to_string(),
+        Key::Delete => "delete".to_string(),
+        Key::Insert => "insert".to_string(),
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::Home => "home".to_string(),
+        Key::End => "end".to_string(),
+        Key::PageUp => "pageup".to_string(),
+        Key::PageDown => "pagedown".to_string(),
+        _ => format!("{:?}", key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names() {
+        for &name in &[
+            "s", "S", "/", "enter", "space", "esc", "pagedown", "ctrl-d", "alt-x", "f5",
+        ] {
+            assert_eq!(key_name(parse_key(name).unwrap()), name);
+        }
+        assert_eq!(parse_key("Ctrl-D").unwrap(), Key::Ctrl('d'));
+        assert_eq!(parse_key("PageUp").unwrap(), Key::PageUp);
+        for &name in &["", "ctrl-", "ctrl-ab", "f13", "f0", "hyper-x", "spacebar"] {
+            assert!(parse_key(name).is_err(), "{} should be invalid", name);
+        }
+    }
+
+    #[test]
+    fn registry() {
+        // Every action has a single binding per scope, and every binding has a description
+        for (i, binding) in BINDINGS.iter().enumerate() {
+            assert!(!binding.description.is_empty());
+            assert!(
+                BINDINGS[i + 1..]
+                    .iter()
+                    .all(|b| b.scope != binding.scope || b.id != binding.id),
+                "{}.{} is registered twice",
+                binding.scope,
+                binding.id
+            );
+        }
+    }
+
+    #[test]
+    fn default_keymap() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action("containers", Key::Char('k')),
+            Some(Action::Up)
+        );
+        assert_eq!(
+            keymap.action("containers", Key::Char('s')),
+            Some(Action::Stop)
+        );
+        assert_eq!(keymap.action("images", Key::Char('s')), None);
+        assert_eq!(
+            keymap.action("container_stats", Key::Char('\n')),
+            Some(Action::Graphs)
+        );
+        assert_eq!(
+            keymap.action("contexts", Key::Char('\n')),
+            Some(Action::SwitchContext)
+        );
+    }
+
+    #[test]
+    fn remapped_keys() {
+        let keymap = Keymap::new(&[
+            (
+                find_binding("containers", "stop").unwrap(),
+                vec![Key::Char('x'), Key::F(9)],
+            ),
+            (find_binding(GLOBAL, "quit").unwrap(), vec![Key::Esc]),
+        ])
+        .unwrap();
+        assert_eq!(
+            keymap.action("containers", Key::Char('x')),
+            Some(Action::Stop)
+        );
+        assert_eq!(keymap.action("containers", Key::F(9)), Some(Action::Stop));
+        // The default keys aren't bound anymore
+        assert_eq!(keymap.action("containers", Key::Char('s')), None);
+        assert_eq!(keymap.action("images", Key::Char('q')), None);
+        assert_eq!(keymap.action("images", Key::Esc), Some(Action::Quit));
+        let (_, keys) = keymap
+            .bindings()
+            .iter()
+            .find(|(b, _)| b.action == Action::Stop)
+            .unwrap();
+        assert_eq!(keys, &[Key::Char('x'), Key::F(9)]);
+        // View actions take precedence
+        let keymap = Keymap::new(&[(
+            find_binding("containers", "logs").unwrap(),
+            vec![Key::Char('L')],
+        )])
+        .unwrap();
+        assert_eq!(
+            keymap.action("containers", Key::Char('L')),
+            Some(Action::Logs)
+        );
+        assert_eq!(
+            keymap.action("images", Key::Char('L')),
+            Some(Action::AppLogs)
+        );
+    }
+
+    #[test]
+    fn conflicting_keys() {
+        let err = Keymap::new(&[(
+            find_binding("containers", "stop").unwrap(),
+            vec![Key::Char('d')],
+        )])
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "d is bound to both containers.stop and containers.delete"
+        );
+    }
+}
@@ -2,43 +2,136 @@
 extern crate log;
 
 mod app;
+mod bulk;
+mod cli;
+mod compose;
+mod config;
+mod connection;
+mod context;
 mod docker;
+mod events;
+mod images;
+mod keys;
+mod shell;
+mod stats;
+mod terminal;
+mod theme;
 mod tty;
 mod views;
+mod worker;
 
-use crossbeam_channel::unbounded;
+use crossbeam_channel::{unbounded, RecvTimeoutError};
+use std::env;
 use std::io;
+use std::process;
 use std::thread;
+use std::time::Duration;
 
 use log::LevelFilter;
+use signal_hook::iterator::Signals;
 use termion::{
     input::{MouseTerminal, TermRead},
     raw::{IntoRawMode, RawTerminal},
     screen::AlternateScreen,
 };
 use tui::{backend::TermionBackend, Terminal};
-use tui_logger::{init_logger, set_default_level, set_level_for_target};
+use tui_logger::{init_logger, set_default_level, set_level_for_target, set_log_file};
 
 use crate::app::{App, AppEvent};
+use crate::cli::{Command, Options, USAGE};
+use crate::config::Config;
+use crate::docker::ConnectionConfig;
+use crate::keys::Action;
+use crate::terminal::{current_mode, install_panic_hook, Modes, RawStdin};
 
 type Backend = TermionBackend<AlternateScreen<MouseTerminal<RawTerminal<io::Stdout>>>>;
 
+/// Maximum time to wait for an event before redrawing, so that data streamed in the background
+/// (e.g. container logs) shows up even when the user is idle.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Default interval between automatic refreshes, in seconds
+const DEFAULT_REFRESH_INTERVAL: u64 = 5;
+
+/// Returns the configuration of the connection to the Docker daemon, from the environment and the
+/// command line options, and whether the host has been set explicitly (in which case the current
+/// context of the docker CLI is ignored).
+fn connection_config(options: &Options) -> (ConnectionConfig, bool) {
+    let mut config = ConnectionConfig::from_env();
+    let overridden = options.host.is_some() || env::var_os("DOCKER_HOST").is_some();
+    if let Some(ref host) = options.host {
+        config.host = host.clone();
+    }
+    (config, overridden)
+}
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("{}\n\n{}", msg, USAGE);
+    process::exit(1);
+}
+
 fn main() {
+    let mut options = match cli::parse(env::args().skip(1)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Ok(Command::Version) => {
+            println!("rkr {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Err(e) => usage_error(&e.to_string()),
+    };
+    let config = Config::load(options.config.as_deref()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    options.view = options.view.or_else(|| config.view.clone());
+    options.exec_command = options
+        .exec_command
+        .or_else(|| env::var("RKR_EXEC_COMMAND").ok())
+        .or_else(|| config.exec_command.clone());
+    theme::set_current(config.theme);
+    let (connection, overridden) = connection_config(&options);
+
     // Initialise logger
     init_logger(LevelFilter::Trace).unwrap();
     set_default_level(LevelFilter::Info);
-    set_level_for_target("rkr", LevelFilter::Trace);
+    set_level_for_target("rkr", options.log_level);
+    if let Some(ref path) = options.log_file {
+        if let Err(e) = set_log_file(&path.to_string_lossy()) {
+            eprintln!("Failed to open log file {}: {}", path.display(), e);
+            process::exit(1);
+        }
+    }
     info!("Logging system initialised");
 
     let (tx, rx) = unbounded();
     let input_tx = tx.clone();
+    let tick_tx = tx.clone();
+    let (ack_tx, ack_rx) = unbounded();
 
     // App
-    let mut app =
-        App::new().unwrap_or_else(|e| panic!("Failed to connect to the Docker daemon: {}", e));
+    let (contexts, current) = context::load_contexts(connection, overridden);
+    let host = contexts
+        .iter()
+        .find(|c| c.name == current)
+        .map(|c| c.config.host.clone())
+        .unwrap_or_default();
+    let mut app = App::new(contexts, &current, &options, &config, tx.clone()).unwrap_or_else(|e| {
+        eprintln!(
+            "Failed to create a client for the Docker daemon at {} (context {}): {}",
+            host, current, e
+        );
+        process::exit(1);
+    });
 
     // Terminal initialization
+    let cooked_mode = current_mode().expect("stdout is not a terminal");
     let stdout = io::stdout().into_raw_mode().unwrap();
+    let modes = Modes::new(cooked_mode).unwrap();
+    install_panic_hook(modes);
     let stdout = MouseTerminal::from(stdout);
     let stdout = AlternateScreen::from(stdout);
     let backend = TermionBackend::new(stdout);
@@ -52,13 +145,63 @@ fn main() {
 
     // Input handling thread
     thread::spawn(move || {
-        let stdin = io::stdin();
-        for c in stdin.keys() {
-            let key = c.unwrap();
+        for c in RawStdin.keys() {
+            let key = match c {
+                Ok(key) => key,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => panic!("Failed to read from stdin: {}", e),
+            };
             input_tx.send(AppEvent::Input(key)).unwrap();
+            // Wait for the key to be handled before reading from stdin again, as some commands
+            // (e.g. opening a shell in a container) need exclusive access to it.
+            if ack_rx.recv().is_err() {
+                break;
+            }
         }
     });
 
+    // Signal handling thread
+    let signals = Signals::new([
+        libc::SIGTERM,
+        libc::SIGINT,
+        libc::SIGHUP,
+        libc::SIGTSTP,
+        libc::SIGCONT,
+    ])
+    .unwrap();
+    let signal_tx = tx.clone();
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            let event = match signal {
+                libc::SIGTSTP => AppEvent::Suspend,
+                libc::SIGCONT => AppEvent::Continued,
+                _ => AppEvent::Terminate,
+            };
+            if signal_tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Auto-refresh thread. Setting the interval to 0 disables auto-refresh.
+    let refresh_interval = options
+        .refresh_interval
+        .or_else(|| {
+            env::var("RKR_REFRESH_INTERVAL")
+                .ok()
+                .and_then(|s| s.parse().ok())
+        })
+        .or(config.refresh_interval)
+        .unwrap_or(DEFAULT_REFRESH_INTERVAL);
+    if refresh_interval > 0 {
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(refresh_interval));
+            if tick_tx.send(AppEvent::Tick).is_err() {
+                break;
+            }
+        });
+    }
+
     app.refresh();
     // Main event loop
     info!("Starting main event loop");
@@ -71,16 +214,43 @@ fn main() {
         }
 
         // Draw app
+        app.update();
         app.draw(&mut terminal);
 
         // Handle events
-        let evt = rx.recv().unwrap();
+        let evt = match rx.recv_timeout(REDRAW_INTERVAL) {
+            Ok(evt) => evt,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
         match evt {
+            AppEvent::Input(key)
+                if !app.captures_input() && app.action(key) == Some(Action::Suspend) =>
+            {
+                modes.suspend();
+                app.force_redraw();
+                ack_tx.send(()).unwrap();
+            }
             AppEvent::Input(key) => {
-                if !app.handle_input(key) {
+                let keep_running = app.handle_input(key);
+                ack_tx.send(()).unwrap();
+                if !keep_running {
                     break;
                 }
             }
+            AppEvent::Tick => app.tick(),
+            AppEvent::Refreshed => { /* picked up by app.update() */ }
+            AppEvent::DockerEvent(event) => app.handle_docker_event(event),
+            AppEvent::Disconnected(error) => app.handle_disconnect(error),
+            AppEvent::Suspend => {
+                modes.suspend();
+                app.force_redraw();
+            }
+            AppEvent::Continued => app.force_redraw(),
+            AppEvent::Terminate => {
+                info!("Terminated by a signal");
+                break;
+            }
         };
     }
 }
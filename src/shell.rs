@@ -0,0 +1,95 @@
+//! Interactive shell sessions in containers.
+//!
+//! While a session is running, it takes over the terminal: rkr leaves the alternate screen, and
+//! forwards everything typed on stdin to the session, whose output is written to stdout.
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use failure::*;
+use termion::{
+    cursor,
+    screen::{ToAlternateScreen, ToMainScreen},
+    terminal_size,
+};
+
+use crate::docker::{DockerBackend, ExecSession};
+use crate::terminal::{RawStdin, DISABLE_MOUSE, ENABLE_MOUSE};
+
+/// How long to wait for input before checking whether the session has ended
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Runs `cmd` interactively in the given container, and returns once it has exited.
+///
+/// The terminal is expected to be in raw mode, which is left as is: echo and line editing are
+/// handled by the TTY of the session. The caller is responsible for redrawing the whole screen
+/// afterwards.
+pub fn run(docker: &dyn DockerBackend, container: &str, cmd: &[String]) -> Result<(), Error> {
+    let mut stdout = io::stdout();
+    write!(stdout, "{}{}{}", DISABLE_MOUSE, ToMainScreen, cursor::Show)?;
+    stdout.flush()?;
+
+    let result = docker
+        .exec(container, cmd)
+        .and_then(|session| forward_input(docker, &session).map(|_| session))
+        .and_then(|session| match docker.exec_exit_code(session.id())? {
+            Some(code) if code != 0 => {
+                Err(format_err!("{} exited with code {}", cmd.join(" "), code))
+            }
+            _ => Ok(()),
+        });
+
+    write!(
+        stdout,
+        "{}{}{}",
+        ToAlternateScreen,
+        ENABLE_MOUSE,
+        cursor::Hide
+    )?;
+    stdout.flush()?;
+    result
+}
+
+/// Forwards stdin to the session (and keeps its TTY size in sync with ours) until it ends.
+fn forward_input(docker: &dyn DockerBackend, session: &ExecSession) -> Result<(), Error> {
+    let mut size = (0, 0);
+    let mut buf = [0u8; 1024];
+    while !session.is_done() {
+        let new_size = terminal_size()?;
+        if new_size != size {
+            size = new_size;
+            if let Err(e) = docker.exec_resize(session.id(), size) {
+                warn!("Failed to resize exec session: {}", e);
+            }
+        }
+
+        if let Some(n) = read_stdin(&mut buf)? {
+            if session.send(&buf[..n]).is_err() {
+                // The session ended in the meantime
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads whatever is available on stdin, waiting at most `POLL_INTERVAL`.
+fn read_stdin(buf: &mut [u8]) -> Result<Option<usize>, Error> {
+    let mut fds = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let ready = unsafe { libc::poll(&mut fds, 1, POLL_INTERVAL.as_millis() as libc::c_int) };
+    if ready < 0 {
+        let err = io::Error::last_os_error();
+        return if err.kind() == io::ErrorKind::Interrupted {
+            Ok(None)
+        } else {
+            Err(err.into())
+        };
+    } else if ready == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(RawStdin.read(buf)?))
+}
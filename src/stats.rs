@@ -0,0 +1,305 @@
+//! Resource usage statistics, computed from the samples sent by the Docker stats API.
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Instant;
+
+use serde::Deserialize;
+
+/// Number of samples kept in the history of a container. The daemon sends a sample every second,
+/// so this is a bit more than 3 minutes worth of history.
+pub const HISTORY_SIZE: usize = 200;
+
+/// A stats sample sent by the Docker daemon, with only the fields rocker uses.
+///
+/// What the daemon sends depends on the host: cgroup v2 hosts don't report the per-CPU usage nor
+/// the `cache` memory stat, the block I/O lists can be null, and containers without a network
+/// (e.g. `--network none`) have no `networks` at all. So every field defaults to zero or empty.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub cpu_stats: CpuStats,
+    pub memory_stats: MemoryStats,
+    /// Counters of each network interface, by name
+    pub networks: HashMap<String, NetworkStats>,
+    pub blkio_stats: BlkioStats,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CpuStats {
+    pub cpu_usage: CpuUsage,
+    /// Total CPU time of the host, in nanoseconds
+    pub system_cpu_usage: u64,
+    pub online_cpus: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CpuUsage {
+    /// Total CPU time consumed by the container, in nanoseconds
+    pub total_usage: u64,
+    /// CPU time consumed on each CPU (cgroup v1 only)
+    pub percpu_usage: Option<Vec<u64>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MemoryStats {
+    pub usage: u64,
+    pub limit: u64,
+    /// Detailed memory stats of the cgroup, whose names depend on the cgroup version
+    pub stats: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NetworkStats {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BlkioStats {
+    pub io_service_bytes_recursive: Option<Vec<BlkioStat>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BlkioStat {
+    /// `read`, `write`... capitalized on cgroup v1
+    pub op: String,
+    pub value: u64,
+}
+
+/// The raw counters extracted from a stats sample sent by the Docker daemon.
+#[derive(Debug, Clone, Default)]
+pub struct Sample {
+    /// Total CPU time consumed by the container, in nanoseconds
+    pub cpu_total: u64,
+    /// Total CPU time of the host, in nanoseconds
+    pub system_cpu: u64,
+    /// Number of CPUs available to the container
+    pub online_cpus: usize,
+    /// Memory used, excluding the page cache
+    pub mem_usage: u64,
+    pub mem_limit: u64,
+    /// Bytes received/sent on all the network interfaces
+    pub net_rx: u64,
+    pub net_tx: u64,
+    /// Bytes read/written on all the block devices
+    pub blk_read: u64,
+    pub blk_write: u64,
+}
+
+/// Parses a stats sample sent by the Docker daemon.
+pub fn parse(sample: &[u8]) -> Result<Stats, serde_json::Error> {
+    serde_json::from_slice(sample)
+}
+
+impl From<&Stats> for Sample {
+    fn from(stats: &Stats) -> Sample {
+        let cpu = &stats.cpu_stats;
+        // Like `docker stats`, fall back to the per-CPU usage on daemons not reporting the number
+        // of online CPUs
+        let online_cpus = match cpu.online_cpus {
+            0 => cpu.cpu_usage.percpu_usage.as_ref().map_or(0, Vec::len),
+            n => n as usize,
+        };
+
+        // The page cache is reported as `cache` on cgroup v1, and the part of it that can be
+        // reclaimed as `inactive_file` on cgroup v2
+        let memory = &stats.memory_stats;
+        let cache = memory
+            .stats
+            .get("cache")
+            .or_else(|| memory.stats.get("inactive_file"))
+            .cloned()
+            .unwrap_or(0);
+
+        let blkio = stats
+            .blkio_stats
+            .io_service_bytes_recursive
+            .as_deref()
+            .unwrap_or_default();
+        let blk_total = |op: &str| {
+            blkio
+                .iter()
+                .filter(|s| s.op.eq_ignore_ascii_case(op))
+                .map(|s| s.value)
+                .sum::<u64>()
+        };
+        Sample {
+            cpu_total: cpu.cpu_usage.total_usage,
+            system_cpu: cpu.system_cpu_usage,
+            online_cpus,
+            mem_usage: memory.usage.saturating_sub(cache),
+            mem_limit: memory.limit,
+            net_rx: stats.networks.values().map(|n| n.rx_bytes).sum(),
+            net_tx: stats.networks.values().map(|n| n.tx_bytes).sum(),
+            blk_read: blk_total("read"),
+            blk_write: blk_total("write"),
+        }
+    }
+}
+
+impl Sample {
+    /// CPU usage since the `previous` sample, as a percentage of one CPU (i.e. it can go above
+    /// 100% for containers using several CPUs). This is the same formula as `docker stats`.
+    ///
+    /// Returns 0 if `previous` is empty (i.e. this is the first sample).
+    pub fn cpu_percent(&self, previous: &Sample) -> f64 {
+        let cpu_delta = self.cpu_total.saturating_sub(previous.cpu_total) as f64;
+        let system_delta = self.system_cpu.saturating_sub(previous.system_cpu) as f64;
+        if previous.system_cpu > 0 && cpu_delta > 0.0 && system_delta > 0.0 {
+            cpu_delta / system_delta * self.online_cpus as f64 * 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Memory usage as a percentage of the limit.
+    pub fn mem_percent(&self) -> f64 {
+        if self.mem_limit > 0 {
+            self.mem_usage as f64 / self.mem_limit as f64 * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Keeps track of the latest stats of a container, as well as a rolling window of its recent CPU
+/// and memory usage.
+#[derive(Debug, Clone, Default)]
+pub struct ContainerStats {
+    /// The latest sample received
+    pub current: Sample,
+    /// When the latest sample was received
+    received: Option<Instant>,
+    /// CPU usage computed from the last two samples
+    pub cpu_percent: f64,
+    /// Network throughput computed from the last two samples, in bytes/s
+    pub net_rx_rate: f64,
+    pub net_tx_rate: f64,
+    /// Recent CPU usage, in hundredths of a percent (oldest first)
+    pub cpu_history: VecDeque<u64>,
+    /// Recent memory usage, in bytes (oldest first)
+    pub mem_history: VecDeque<u64>,
+}
+
+impl ContainerStats {
+    /// Records a new sample.
+    pub fn push(&mut self, sample: Sample) {
+        let now = Instant::now();
+        if let Some(received) = self.received {
+            let elapsed = now.duration_since(received);
+            let secs = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_millis()) / 1000.0;
+            if secs > 0.0 {
+                self.net_rx_rate = sample.net_rx.saturating_sub(self.current.net_rx) as f64 / secs;
+                self.net_tx_rate = sample.net_tx.saturating_sub(self.current.net_tx) as f64 / secs;
+            }
+        }
+        self.cpu_percent = sample.cpu_percent(&self.current);
+        self.received = Some(now);
+
+        Self::record(&mut self.cpu_history, (self.cpu_percent * 100.0) as u64);
+        Self::record(&mut self.mem_history, sample.mem_usage);
+        self.current = sample;
+    }
+
+    fn record(history: &mut VecDeque<u64>, value: u64) {
+        if history.len() == HISTORY_SIZE {
+            history.pop_front();
+        }
+        history.push_back(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(cpu_total: u64, system_cpu: u64, online_cpus: usize) -> Sample {
+        Sample {
+            cpu_total,
+            system_cpu,
+            online_cpus,
+            ..Sample::default()
+        }
+    }
+
+    #[test]
+    fn cpu_percent() {
+        let previous = sample(1_000, 10_000, 4);
+        // 100 out of 1000 ns of host CPU time, on 4 CPUs
+        assert_eq!(sample(1_100, 11_000, 4).cpu_percent(&previous), 40.0);
+        assert_eq!(sample(1_100, 11_000, 1).cpu_percent(&previous), 10.0);
+        // Idle container
+        assert_eq!(sample(1_000, 11_000, 4).cpu_percent(&previous), 0.0);
+        // No system delta
+        assert_eq!(sample(1_100, 10_000, 4).cpu_percent(&previous), 0.0);
+        // First sample
+        assert_eq!(previous.cpu_percent(&Sample::default()), 0.0);
+    }
+
+    #[test]
+    fn mem_percent() {
+        let mut sample = Sample {
+            mem_usage: 256,
+            mem_limit: 1024,
+            ..Sample::default()
+        };
+        assert_eq!(sample.mem_percent(), 25.0);
+        sample.mem_limit = 0;
+        assert_eq!(sample.mem_percent(), 0.0);
+    }
+
+    fn sample_of(fixture: &str) -> Sample {
+        Sample::from(&parse(fixture.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn cgroup_v1_sample() {
+        let sample = sample_of(include_str!("fixtures/stats_cgroup_v1.json"));
+        assert_eq!(sample.cpu_total, 117_006_540);
+        assert_eq!(sample.system_cpu, 1_232_360_000_000);
+        // percpu_usage also lists the CPUs that are offline
+        assert_eq!(sample.online_cpus, 4);
+        // usage - cache
+        assert_eq!(sample.mem_usage, 2_457_600);
+        assert_eq!(sample.mem_limit, 2_083_364_864);
+        assert_eq!(sample.net_rx, 6_362);
+        assert_eq!(sample.net_tx, 2_696);
+        assert_eq!(sample.blk_read, 9_981_952);
+        assert_eq!(sample.blk_write, 8_192);
+    }
+
+    #[test]
+    fn cgroup_v2_sample() {
+        let sample = sample_of(include_str!("fixtures/stats_cgroup_v2.json"));
+        assert_eq!(sample.cpu_total, 40_744_000);
+        assert_eq!(sample.online_cpus, 8);
+        // usage - inactive_file
+        assert_eq!(sample.mem_usage, 2_777_088);
+        assert_eq!(sample.mem_limit, 16_630_308_864);
+        assert_eq!(sample.net_rx, 9_816);
+        assert_eq!(sample.net_tx, 1_292);
+        assert_eq!(sample.blk_read, 4_907_008);
+        assert_eq!(sample.blk_write, 12_288);
+    }
+
+    #[test]
+    fn sample_without_network() {
+        let sample = sample_of(include_str!("fixtures/stats_no_network.json"));
+        assert_eq!(sample.online_cpus, 8);
+        assert_eq!(sample.mem_usage, 565_248);
+        assert_eq!((sample.net_rx, sample.net_tx), (0, 0));
+        assert_eq!((sample.blk_read, sample.blk_write), (0, 0));
+    }
+
+    #[test]
+    fn cpu_count_without_online_cpus() {
+        let sample = sample_of(r#"{"cpu_stats": {"cpu_usage": {"percpu_usage": [60, 40]}}}"#);
+        assert_eq!(sample.online_cpus, 2);
+        assert_eq!(sample_of("{}").online_cpus, 0);
+    }
+}
@@ -0,0 +1,122 @@
+//! Restores the terminal to the state rkr found it in when it crashes or gets suspended, and sets
+//! it up again when it is resumed.
+//!
+//! The terminal is normally restored by the destructors of the `termion` wrappers, but these don't
+//! run when rkr is suspended, nor before the panic message is printed (or at all when a background
+//! thread panics).
+use std::io::{self, Read, Write};
+use std::mem;
+use std::panic;
+use std::process;
+
+use termion::{
+    cursor,
+    screen::{ToAlternateScreen, ToMainScreen},
+};
+
+/// Escape sequences used by `termion::input::MouseTerminal` to enable/disable mouse reporting
+pub const ENABLE_MOUSE: &str = "\x1b[?1000h\x1b[?1002h\x1b[?1015h\x1b[?1006h";
+pub const DISABLE_MOUSE: &str = "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l";
+
+/// The settings of the terminal outside of rkr, and while rkr is running.
+#[derive(Clone, Copy)]
+pub struct Modes {
+    cooked: libc::termios,
+    raw: libc::termios,
+}
+
+impl Modes {
+    /// Records the current settings as the ones to use while rkr is running, i.e. this must be
+    /// called once raw mode has been enabled. `cooked` are the settings from before that, as
+    /// returned by `current_mode()`.
+    pub fn new(cooked: libc::termios) -> io::Result<Modes> {
+        Ok(Modes {
+            cooked,
+            raw: current_mode()?,
+        })
+    }
+
+    /// Gives the terminal back to the user: leaves the alternate screen, shows the cursor, and
+    /// disables mouse reporting and raw mode.
+    pub fn restore(&self) {
+        let mut stdout = io::stdout();
+        // Nothing sensible can be done if the terminal is gone
+        let _ = write!(stdout, "{}{}{}", DISABLE_MOUSE, ToMainScreen, cursor::Show);
+        let _ = stdout.flush();
+        let _ = set_mode(&self.cooked);
+    }
+
+    /// Sets the terminal up for rkr again after `restore()`. The caller is responsible for
+    /// redrawing the whole screen afterwards.
+    pub fn resume(&self) {
+        let _ = set_mode(&self.raw);
+        let mut stdout = io::stdout();
+        let _ = write!(
+            stdout,
+            "{}{}{}",
+            ToAlternateScreen,
+            ENABLE_MOUSE,
+            cursor::Hide
+        );
+        let _ = stdout.flush();
+    }
+
+    /// Suspends rkr like Ctrl-Z does for any other program, and returns once it is continued
+    /// (e.g. with `fg`), with the terminal set up again.
+    pub fn suspend(&self) {
+        info!("Suspending");
+        self.restore();
+        // SIGTSTP is handled by rkr, so it wouldn't stop it
+        unsafe {
+            libc::raise(libc::SIGSTOP);
+        }
+        info!("Resuming");
+        self.resume();
+    }
+}
+
+/// Reads stdin directly from its file descriptor.
+///
+/// Unlike `io::stdin()`, nothing is read ahead into a buffer: the keys typed while a shell session
+/// is being opened would otherwise be stuck there, instead of being sent to the session.
+pub struct RawStdin;
+
+impl Read for RawStdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr() as *mut _, buf.len()) };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(n as usize)
+    }
+}
+
+/// Returns the current settings of the terminal.
+pub fn current_mode() -> io::Result<libc::termios> {
+    unsafe {
+        let mut termios = mem::zeroed();
+        if libc::tcgetattr(libc::STDOUT_FILENO, &mut termios) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(termios)
+    }
+}
+
+fn set_mode(termios: &libc::termios) -> io::Result<()> {
+    if unsafe { libc::tcsetattr(libc::STDOUT_FILENO, libc::TCSANOW, termios) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Makes panics restore the terminal before the panic message (and the hint about
+/// `RUST_BACKTRACE`, or the backtrace itself) is printed by the default hook, then exit: the UI
+/// can't carry on once a thread has died.
+pub fn install_panic_hook(modes: Modes) {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        modes.restore();
+        default_hook(info);
+        process::exit(101);
+    }));
+}
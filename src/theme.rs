@@ -0,0 +1,150 @@
+//! The colors used to draw the application, which can be changed in the configuration file.
+use std::cell::Cell;
+
+use failure::*;
+use tui::style::Color;
+
+/// The palette of the application.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Normal text
+    pub foreground: Color,
+    /// Background of the title bar, status line and logs
+    pub background: Color,
+    /// Selected row or section
+    pub selected: Color,
+    /// Running containers, active context, and anything going well
+    pub running: Color,
+    /// Errors, stderr, and containers dying
+    pub error: Color,
+    /// Warnings and ongoing operations
+    pub warning: Color,
+    /// Section titles
+    pub title: Color,
+    /// Counters in the title bar
+    pub accent: Color,
+    /// Secondary data, e.g. the memory usage graph
+    pub secondary: Color,
+    /// Data that can't be refreshed, e.g. while the daemon is unreachable
+    pub disabled: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            foreground: Color::White,
+            background: Color::Black,
+            selected: Color::Yellow,
+            running: Color::Green,
+            error: Color::Red,
+            warning: Color::Yellow,
+            title: Color::Blue,
+            accent: Color::LightGreen,
+            secondary: Color::Cyan,
+            disabled: Color::DarkGray,
+        }
+    }
+}
+
+impl Theme {
+    /// Sets the color with the given name (e.g. `selected`). Returns an error if there is no such
+    /// color in the palette.
+    pub fn set(&mut self, name: &str, color: Color) -> Result<(), Error> {
+        let slot = match name {
+            "foreground" => &mut self.foreground,
+            "background" => &mut self.background,
+            "selected" => &mut self.selected,
+            "running" => &mut self.running,
+            "error" => &mut self.error,
+            "warning" => &mut self.warning,
+            "title" => &mut self.title,
+            "accent" => &mut self.accent,
+            "secondary" => &mut self.secondary,
+            "disabled" => &mut self.disabled,
+            _ => bail!(
+                "unknown color, expected one of foreground, background, selected, running, error, \
+                 warning, title, accent, secondary or disabled"
+            ),
+        };
+        *slot = color;
+        Ok(())
+    }
+}
+
+thread_local! {
+    // Drawing only happens on the main thread
+    static CURRENT: Cell<Theme> = Cell::new(Theme::default());
+}
+
+/// Returns the theme used to draw the application.
+pub fn current() -> Theme {
+    CURRENT.with(Cell::get)
+}
+
+/// Changes the theme used to draw the application.
+pub fn set_current(theme: Theme) {
+    CURRENT.with(|current| current.set(theme));
+}
+
+/// Parses a color given by name (e.g. `lightblue`), as `#rrggbb`, or as an index in the 256-color
+/// palette of the terminal.
+pub fn parse_color(s: &str) -> Result<Color, Error> {
+    let color = match s.to_lowercase().as_str() {
+        "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" => Color::Gray,
+        "darkgray" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        hex if hex.starts_with('#') && hex.len() == 7 && hex.is_ascii() => {
+            let component = |i| u8::from_str_radix(&hex[i..i + 2], 16);
+            match (component(1), component(3), component(5)) {
+                (Ok(r), Ok(g), Ok(b)) => Color::Rgb(r, g, b),
+                _ => bail!("invalid color {}", s),
+            }
+        }
+        index => match index.parse() {
+            Ok(index) => Color::Indexed(index),
+            Err(_) => bail!(
+                "invalid color {}, expected a name (e.g. lightblue), #rrggbb or 0-255",
+                s
+            ),
+        },
+    };
+    Ok(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("LightBlue").unwrap(), Color::LightBlue);
+        assert_eq!(parse_color("#ff8000").unwrap(), Color::Rgb(255, 128, 0));
+        assert_eq!(parse_color("208").unwrap(), Color::Indexed(208));
+        assert!(parse_color("#ff80").is_err());
+        assert!(parse_color("#gg8000").is_err());
+        assert!(parse_color("256").is_err());
+        assert!(parse_color("orange").is_err());
+    }
+
+    #[test]
+    fn palette() {
+        let mut theme = Theme::default();
+        theme.set("selected", Color::Magenta).unwrap();
+        assert_eq!(theme.selected, Color::Magenta);
+        assert!(theme.set("highlight", Color::Magenta).is_err());
+    }
+}
@@ -1,9 +1,8 @@
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ByteOrder};
 
 use std::fmt;
-use std::io::Cursor;
-use std::io::Read;
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum TtyLine {
     StdOut(String),
     StdErr(String),
@@ -19,50 +18,414 @@ impl fmt::Display for TtyLine {
     }
 }
 
+/// Size of the header preceding each frame of a multiplexed stream.
+const HEADER_SIZE: usize = 8;
+
+/// The stream a frame belongs to, as given by the first byte of its header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StreamType {
+    StdIn,
+    StdOut,
+    StdErr,
+    Unknown(u8),
+}
+
+impl From<u8> for StreamType {
+    fn from(b: u8) -> StreamType {
+        match b {
+            0 => StreamType::StdIn,
+            1 => StreamType::StdOut,
+            2 => StreamType::StdErr,
+            n => StreamType::Unknown(n),
+        }
+    }
+}
+
+enum DecoderState {
+    /// Waiting for the rest of a frame header
+    Header,
+    /// Reading the payload of a frame: stream type and number of bytes left to read
+    Payload(StreamType, usize),
+}
+
 /// Used to demux the output of Docker log, but still keep lines from stdout and stderr interlaced
-/// in the right order. This is adapted from `shiplift::tty::Tty`.
+/// in the right order.
+///
+/// This is an incremental decoder: bytes can be fed to it in chunks of any size as they are
+/// received, and it returns the lines that have been completed so far. Partial headers and partial
+/// lines are buffered until the rest arrives.
+///
+/// Containers started with a TTY don't multiplex their output: use `InterlacedTty::raw()` for
+/// those, which just splits the stream into lines (all reported as stdout).
+// https://docs.docker.com/engine/api/v1.26/#operation/ContainerAttach
 pub struct InterlacedTty {
-    pub lines: Vec<TtyLine>,
+    /// Whether the stream is a raw stream, without any frame header
+    raw: bool,
+    state: DecoderState,
+    /// Bytes of the header currently being read
+    header: Vec<u8>,
+    /// Incomplete stdout line
+    stdout: Vec<u8>,
+    /// Incomplete stderr line
+    stderr: Vec<u8>,
+    /// Whether the incomplete stderr line was started before the incomplete stdout line
+    stderr_first: bool,
 }
 
-// https://docs.docker.com/engine/api/v1.26/#operation/ContainerAttach
 impl InterlacedTty {
-    pub fn new(mut stream: impl Read) -> InterlacedTty {
-        let mut lines: Vec<TtyLine> = vec![];
-        loop {
-            // 8 byte header [ STREAM_TYPE, 0, 0, 0, SIZE1, SIZE2, SIZE3, SIZE4 ]
-            let mut header = [0; 8];
-            match stream.read_exact(&mut header) {
-                Ok(_) => {
-                    let payload_size: Vec<u8> = header[4..8].to_vec();
-                    let mut buffer = vec![
-                        0;
-                        Cursor::new(&payload_size).read_u32::<BigEndian>().unwrap()
-                            as usize
-                    ];
-                    match stream.read_exact(&mut buffer) {
-                        Ok(_) => {
-                            match header[0] {
-                                // stdin, unhandled
-                                0 => break,
-                                // stdout
-                                1 => lines.push(TtyLine::StdOut(
-                                    String::from_utf8_lossy(&buffer).trim().to_string(),
-                                )),
-                                // stderr
-                                2 => lines.push(TtyLine::StdErr(
-                                    String::from_utf8_lossy(&buffer).trim().to_string(),
-                                )),
-                                //unhandled
-                                _ => break,
+    /// Creates a decoder for a multiplexed stream.
+    pub fn new() -> InterlacedTty {
+        InterlacedTty {
+            raw: false,
+            state: DecoderState::Header,
+            header: Vec::with_capacity(HEADER_SIZE),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            stderr_first: false,
+        }
+    }
+
+    /// Creates a decoder for a raw stream, i.e. the output of a container with a TTY.
+    pub fn raw() -> InterlacedTty {
+        InterlacedTty {
+            raw: true,
+            ..InterlacedTty::new()
+        }
+    }
+
+    /// Decodes the given chunk of the stream, and returns the lines completed by it (if any).
+    pub fn feed(&mut self, mut bytes: &[u8]) -> Vec<TtyLine> {
+        let mut lines = vec![];
+        if self.raw {
+            Self::split_lines(&mut self.stdout, bytes, TtyLine::StdOut, &mut lines);
+            return lines;
+        }
+        while !bytes.is_empty() {
+            match self.state {
+                DecoderState::Header => {
+                    // 8 byte header [ STREAM_TYPE, 0, 0, 0, SIZE1, SIZE2, SIZE3, SIZE4 ]
+                    let n = (HEADER_SIZE - self.header.len()).min(bytes.len());
+                    self.header.extend_from_slice(&bytes[..n]);
+                    bytes = &bytes[n..];
+                    if self.header.len() == HEADER_SIZE {
+                        let stream = StreamType::from(self.header[0]);
+                        let size = BigEndian::read_u32(&self.header[4..8]) as usize;
+                        if let StreamType::Unknown(n) = stream {
+                            warn!("Ignoring frame for unknown stream type {}", n);
+                        }
+                        self.header.clear();
+                        self.state = DecoderState::Payload(stream, size);
+                    }
+                }
+                DecoderState::Payload(stream, remaining) => {
+                    let n = remaining.min(bytes.len());
+                    let (payload, rest) = bytes.split_at(n);
+                    match stream {
+                        StreamType::StdOut => {
+                            let stdout = &mut self.stdout;
+                            if Self::split_lines(stdout, payload, TtyLine::StdOut, &mut lines) {
+                                self.stderr_first = !self.stderr.is_empty();
+                            }
+                        }
+                        StreamType::StdErr => {
+                            let stderr = &mut self.stderr;
+                            if Self::split_lines(stderr, payload, TtyLine::StdErr, &mut lines) {
+                                self.stderr_first = self.stdout.is_empty();
                             }
                         }
-                        Err(_) => break,
+                        // Nothing to display for those
+                        StreamType::StdIn | StreamType::Unknown(_) => {}
+                    }
+                    bytes = rest;
+                    self.state = if remaining == n {
+                        DecoderState::Header
+                    } else {
+                        DecoderState::Payload(stream, remaining - n)
                     };
                 }
-                Err(_) => break,
             }
         }
-        InterlacedTty { lines }
+        lines
+    }
+
+    /// Signals the end of the stream, and returns the last lines if they were not terminated by a
+    /// newline, in the order they were started.
+    pub fn finish(&mut self) -> Vec<TtyLine> {
+        let mut lines = vec![];
+        if !self.stdout.is_empty() {
+            lines.push(TtyLine::StdOut(Self::to_line(&self.stdout)));
+            self.stdout.clear();
+        }
+        if !self.stderr.is_empty() {
+            let line = TtyLine::StdErr(Self::to_line(&self.stderr));
+            if self.stderr_first {
+                lines.insert(0, line);
+            } else {
+                lines.push(line);
+            }
+            self.stderr.clear();
+        }
+        lines
+    }
+
+    /// Appends `payload` to the pending line in `pending`, and pushes every line that gets
+    /// completed to `lines`.
+    ///
+    /// Returns whether the line left pending was started by this payload.
+    fn split_lines<F>(
+        pending: &mut Vec<u8>,
+        payload: &[u8],
+        make_line: F,
+        lines: &mut Vec<TtyLine>,
+    ) -> bool
+    where
+        F: Fn(String) -> TtyLine,
+    {
+        let mut started = false;
+        let mut parts = payload.split(|b| *b == b'\n').peekable();
+        while let Some(part) = parts.next() {
+            started = pending.is_empty() && !part.is_empty();
+            pending.extend_from_slice(part);
+            if parts.peek().is_some() {
+                // There was a newline after this part
+                lines.push(make_line(Self::to_line(pending)));
+                pending.clear();
+            }
+        }
+        started
+    }
+
+    fn to_line(bytes: &[u8]) -> String {
+        let bytes = if bytes.ends_with(b"\r") {
+            &bytes[..bytes.len() - 1]
+        } else {
+            bytes
+        };
+        String::from_utf8_lossy(bytes).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes `payload` as a single frame of the given stream type.
+    fn frame(stream: u8, payload: &[u8]) -> Vec<u8> {
+        let mut f = vec![stream, 0, 0, 0, 0, 0, 0, 0];
+        BigEndian::write_u32(&mut f[4..8], payload.len() as u32);
+        f.extend_from_slice(payload);
+        f
+    }
+
+    fn decode_all(chunks: &[&[u8]]) -> Vec<TtyLine> {
+        let mut tty = InterlacedTty::new();
+        let mut lines = vec![];
+        for chunk in chunks {
+            lines.extend(tty.feed(chunk));
+        }
+        lines.extend(tty.finish());
+        lines
+    }
+
+    /// Small deterministic pseudo-random generator, so that failures can be reproduced.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, max: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            ((self.0 >> 33) as usize) % max
+        }
+    }
+
+    #[test]
+    fn decodes_one_line_per_frame() {
+        let mut stream = frame(1, b"hello\n");
+        stream.extend(frame(2, b"oops\n"));
+        stream.extend(frame(1, b"world\n"));
+
+        assert_eq!(
+            decode_all(&[&stream]),
+            vec![
+                TtyLine::StdOut("hello".to_string()),
+                TtyLine::StdErr("oops".to_string()),
+                TtyLine::StdOut("world".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_frames_containing_several_lines() {
+        let stream = frame(1, b"one\ntwo\r\nthree\n");
+
+        assert_eq!(
+            decode_all(&[&stream]),
+            vec![
+                TtyLine::StdOut("one".to_string()),
+                TtyLine::StdOut("two".to_string()),
+                TtyLine::StdOut("three".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_lines_split_across_frames() {
+        let mut stream = frame(1, b"hel");
+        stream.extend(frame(2, b"err"));
+        stream.extend(frame(1, b"lo\n"));
+        stream.extend(frame(2, b"or\n"));
+
+        assert_eq!(
+            decode_all(&[&stream]),
+            vec![
+                TtyLine::StdOut("hello".to_string()),
+                TtyLine::StdErr("error".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn buffers_partial_headers() {
+        let stream = frame(2, b"partial\n");
+        let mut tty = InterlacedTty::new();
+
+        assert!(tty.feed(&stream[..3]).is_empty());
+        assert!(tty.feed(&stream[3..7]).is_empty());
+        assert!(tty.feed(&stream[7..10]).is_empty());
+        assert_eq!(
+            tty.feed(&stream[10..]),
+            vec![TtyLine::StdErr("partial".to_string())]
+        );
+    }
+
+    #[test]
+    fn skips_stdin_and_unknown_frames() {
+        let mut stream = frame(0, b"typed\n");
+        stream.extend(frame(7, b"garbage\n"));
+        stream.extend(frame(1, b"still there\n"));
+
+        assert_eq!(
+            decode_all(&[&stream]),
+            vec![TtyLine::StdOut("still there".to_string())]
+        );
+    }
+
+    #[test]
+    fn handles_empty_frames() {
+        let mut stream = frame(1, b"");
+        stream.extend(frame(1, b"after\n"));
+
+        assert_eq!(
+            decode_all(&[&stream]),
+            vec![TtyLine::StdOut("after".to_string())]
+        );
+    }
+
+    #[test]
+    fn finish_flushes_unterminated_lines() {
+        let mut stream = frame(1, b"no newline");
+        stream.extend(frame(2, b"nor here"));
+
+        assert_eq!(
+            decode_all(&[&stream]),
+            vec![
+                TtyLine::StdOut("no newline".to_string()),
+                TtyLine::StdErr("nor here".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn finish_flushes_unterminated_lines_in_arrival_order() {
+        let mut stream = frame(2, b"first");
+        stream.extend(frame(1, b"done\nsecond"));
+        stream.extend(frame(2, b" still"));
+
+        assert_eq!(
+            decode_all(&[&stream]),
+            vec![
+                TtyLine::StdOut("done".to_string()),
+                TtyLine::StdErr("first still".to_string()),
+                TtyLine::StdOut("second".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_multibyte_characters_split_across_frames() {
+        let text = "→ arrow\n".as_bytes();
+        let mut stream = frame(1, &text[..1]);
+        stream.extend(frame(1, &text[1..]));
+
+        assert_eq!(
+            decode_all(&[&stream]),
+            vec![TtyLine::StdOut("→ arrow".to_string())]
+        );
+    }
+
+    #[test]
+    fn raw_streams_are_split_into_lines() {
+        let mut tty = InterlacedTty::raw();
+        let mut lines = tty.feed(b"\x01\x00\x00\x00 looks like a header\r\npar");
+        lines.extend(tty.feed(b"tial\r\n"));
+        lines.extend(tty.feed(b"$ "));
+        lines.extend(tty.finish());
+
+        assert_eq!(
+            lines,
+            vec![
+                TtyLine::StdOut("\u{1}\u{0}\u{0}\u{0} looks like a header".to_string()),
+                TtyLine::StdOut("partial".to_string()),
+                TtyLine::StdOut("$ ".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn fuzzed_chunk_boundaries() {
+        let mut rng = Lcg(42);
+        for _ in 0..200 {
+            // Generate some lines, randomly spread across frames of random sizes
+            let mut expected = vec![];
+            let mut stdout = vec![];
+            let mut stderr = vec![];
+            let mut stream = vec![];
+            for i in 0..rng.next(30) {
+                let line = format!("line {} {}", i, "x".repeat(rng.next(50)));
+                if rng.next(2) == 0 {
+                    stdout.extend_from_slice(line.as_bytes());
+                    stdout.push(b'\n');
+                    expected.push(TtyLine::StdOut(line));
+                } else {
+                    stderr.extend_from_slice(line.as_bytes());
+                    stderr.push(b'\n');
+                    expected.push(TtyLine::StdErr(line));
+                }
+                // Flush pending output in frames of random sizes, interleaving only at line
+                // boundaries so that the expected order is well defined.
+                for (stream_type, pending) in [(1, &mut stdout), (2, &mut stderr)].iter_mut() {
+                    while !pending.is_empty() {
+                        let size = (rng.next(20) + 1).min(pending.len());
+                        let payload: Vec<u8> = pending.drain(..size).collect();
+                        stream.extend(frame(*stream_type, &payload));
+                    }
+                }
+            }
+
+            // Feed the stream in chunks of random sizes
+            let mut tty = InterlacedTty::new();
+            let mut lines = vec![];
+            let mut rest = &stream[..];
+            while !rest.is_empty() {
+                let size = (rng.next(16) + 1).min(rest.len());
+                lines.extend(tty.feed(&rest[..size]));
+                rest = &rest[size..];
+            }
+            lines.extend(tty.finish());
+
+            assert_eq!(lines, expected);
+        }
     }
 }
@@ -5,6 +5,7 @@ use std::sync::Arc;
 use log::LevelFilter;
 use termion::event::{Event, Key};
 use tui::{
+    backend::Backend,
     layout::Rect,
     style::{Color, Style},
     widgets::Widget,
@@ -13,9 +14,10 @@ use tui::{
 use tui_logger::{Dispatcher, EventListener, TuiLoggerSmartWidget, TuiWidgetState};
 
 use crate::app::AppCommand;
-use crate::docker::DockerExecutor;
-use crate::views::View;
-use crate::Backend;
+use crate::docker::DockerBackend;
+use crate::keys::Action;
+use crate::theme;
+use crate::views::{Draw, View};
 
 pub struct AppLogsView {
     state: RefCell<TuiWidgetState>,
@@ -34,25 +36,46 @@ impl AppLogsView {
 }
 
 impl View for AppLogsView {
-    fn handle_input(&mut self, key: Key, _docker: Arc<DockerExecutor>) -> Option<AppCommand> {
+    fn name(&self) -> &'static str {
+        "app_logs"
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        docker: Arc<dyn DockerBackend>,
+    ) -> Option<AppCommand> {
+        // The widget has its own key bindings, which include the navigation keys
+        let key = match action {
+            Action::Up => Key::Up,
+            Action::Down => Key::Down,
+            Action::PageUp => Key::PageUp,
+            Action::PageDown => Key::PageDown,
+            _ => return None,
+        };
+        self.handle_input(key, docker)
+    }
+
+    fn handle_input(&mut self, key: Key, _docker: Arc<dyn DockerBackend>) -> Option<AppCommand> {
         if self.dispatcher.borrow_mut().dispatch(&Event::Key(key)) {
             Some(AppCommand::NoOp)
         } else {
             None
         }
     }
+}
 
-    fn refresh(&mut self, _docker: Arc<DockerExecutor>) {}
-
-    fn draw(&self, t: &mut Frame<Backend>, rect: Rect) {
+impl<B: Backend> Draw<B> for AppLogsView {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
         TuiLoggerSmartWidget::default()
             .state(&*self.state.borrow_mut())
             .dispatcher(self.dispatcher.clone())
-            .style_error(Style::default().fg(Color::Red))
-            .style_debug(Style::default().fg(Color::Green))
-            .style_warn(Style::default().fg(Color::Yellow))
+            .style_error(Style::default().fg(theme.error))
+            .style_debug(Style::default().fg(theme.running))
+            .style_warn(Style::default().fg(theme.warning))
             .style_trace(Style::default().fg(Color::Magenta))
-            .style_info(Style::default().fg(Color::Cyan))
+            .style_info(Style::default().fg(theme.secondary))
             .render(t, rect);
     }
 }
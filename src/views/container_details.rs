@@ -1,22 +1,57 @@
 use std::sync::Arc;
+use std::time::SystemTime;
 
-use shiplift::rep::ContainerDetails;
-use termion::event::Key;
+use failure::Error;
+use serde_json::Value;
 use tui::{
+    backend::Backend,
     layout::Rect,
+    style::{Modifier, Style},
     widgets::{Block, Borders, Paragraph, Text, Widget},
     Frame,
 };
 
 use crate::app::{AppCommand, ContainerId};
-use crate::docker::DockerExecutor;
-use crate::views::View;
-use crate::Backend;
+use crate::docker::DockerBackend;
+use crate::keys::Action;
+use crate::theme;
+use crate::views::{human_duration, parse_timestamp, Draw, View, ViewType};
+use crate::worker::{Pending, Worker};
+
+/// A section of the container details, i.e. a titled list of key/value pairs.
+struct Section {
+    title: &'static str,
+    fields: Vec<(String, String)>,
+}
+
+impl Section {
+    fn new(title: &'static str) -> Section {
+        Section {
+            title,
+            fields: vec![],
+        }
+    }
+
+    fn add<K: Into<String>>(&mut self, key: K, value: String) {
+        self.fields.push((key.into(), value));
+    }
+}
 
 pub struct ContainerInfo {
     name: String,
-    details: Option<ContainerDetails>,
+    /// The details of the container, as returned by the daemon
+    details: Option<Value>,
+    sections: Vec<Section>,
+    /// Whether each section is collapsed
+    collapsed: Vec<bool>,
+    /// Index of the selected section
+    selected: usize,
+    /// Whether to display the raw JSON rather than the sections
+    raw: bool,
+    /// Scroll offset in raw mode
     scroll: u16,
+    /// Details being fetched in the background
+    pending: Pending<Result<Value, Error>>,
 }
 
 impl ContainerInfo {
@@ -25,46 +60,360 @@ impl ContainerInfo {
         ContainerInfo {
             name: id,
             details: None,
+            sections: vec![],
+            collapsed: vec![],
+            selected: 0,
+            raw: false,
             scroll: 0,
+            pending: Pending::none(),
+        }
+    }
+
+    /// Builds the text displayed in structured mode, and returns it along with the index of the
+    /// line of the selected section's title.
+    fn structured_text(&self) -> (Vec<Text<'_>>, usize) {
+        let theme = theme::current();
+        let title_style = Style::default().modifier(Modifier::BOLD).fg(theme.title);
+        let selected_style = Style::default().modifier(Modifier::BOLD).fg(theme.selected);
+        let key_width = self
+            .sections
+            .iter()
+            .flat_map(|s| s.fields.iter())
+            .map(|(k, _)| k.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(30);
+
+        let mut text = vec![];
+        let mut selected_line = 0;
+        for (i, section) in self.sections.iter().enumerate() {
+            let collapsed = self.collapsed[i];
+            let marker = if collapsed { '▶' } else { '▼' };
+            let title = format!("{} {} ({})\n", marker, section.title, section.fields.len());
+            if i == self.selected {
+                selected_line = text.len();
+                text.push(Text::styled(title, selected_style));
+            } else {
+                text.push(Text::styled(title, title_style));
+            }
+            if !collapsed {
+                if section.fields.is_empty() {
+                    text.push(Text::raw("    <none>\n"));
+                }
+                for (key, value) in &section.fields {
+                    text.push(Text::raw(format!(
+                        "    {:width$} {}\n",
+                        key,
+                        value,
+                        width = key_width
+                    )));
+                }
+            }
         }
+        (text, selected_line)
     }
 }
 
 impl View for ContainerInfo {
-    fn handle_input(&mut self, key: Key, _docker: Arc<DockerExecutor>) -> Option<AppCommand> {
-        match key {
-            Key::Up | Key::Char('k') => {
-                if self.scroll > 0 {
-                    self.scroll -= 1;
+    fn name(&self) -> &'static str {
+        "container_details"
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        _docker: Arc<dyn DockerBackend>,
+    ) -> Option<AppCommand> {
+        match action {
+            Action::Up => {
+                if self.raw {
+                    self.scroll = self.scroll.saturating_sub(1);
+                } else {
+                    self.selected = self.selected.saturating_sub(1);
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::Down | Key::Char('j') => {
-                self.scroll += 1;
+            Action::Down => {
+                if self.raw {
+                    self.scroll += 1;
+                } else {
+                    self.selected = (self.selected + 1).min(self.sections.len().saturating_sub(1));
+                }
                 Some(AppCommand::NoOp)
             }
+            Action::ToggleSection if !self.raw => {
+                if let Some(collapsed) = self.collapsed.get_mut(self.selected) {
+                    *collapsed = !*collapsed;
+                }
+                Some(AppCommand::NoOp)
+            }
+            Action::CollapseAll if !self.raw => {
+                self.collapsed.iter_mut().for_each(|c| *c = true);
+                Some(AppCommand::NoOp)
+            }
+            Action::ExpandAll if !self.raw => {
+                self.collapsed.iter_mut().for_each(|c| *c = false);
+                Some(AppCommand::NoOp)
+            }
+            Action::ToggleRaw => {
+                self.raw = !self.raw;
+                self.scroll = 0;
+                Some(AppCommand::NoOp)
+            }
+            Action::Graphs => {
+                let id = ContainerId(self.name.clone());
+                Some(AppCommand::SwitchToView(ViewType::ContainerGraphs(id)))
+            }
             _ => None,
         }
     }
 
-    fn refresh(&mut self, docker: Arc<DockerExecutor>) {
-        // self.details = docker.containers().get(&self.name).inspect().ok();
-        self.details = docker.container(&self.name).ok();
+    fn refresh(&mut self, worker: &Worker) {
+        let name = self.name.clone();
+        self.pending = worker.run(move |docker| docker.container_json(&name));
     }
 
-    fn draw(&self, t: &mut Frame<Backend>, rect: Rect) {
-        let data = if let Some(ref info) = self.details {
-            Text::raw(format!("{:#?}", info))
-        } else {
-            Text::raw("Could not retrieve container details.")
+    fn update(&mut self) {
+        match self.pending.take() {
+            Some(Ok(details)) => self.details = Some(details),
+            Some(Err(err)) => {
+                // Keep the last known details, e.g. if the daemon is unreachable
+                error!("Failed to inspect container {}: {}", self.name, err);
+                return;
+            }
+            None => return,
+        }
+        self.sections = self.details.as_ref().map(sections).unwrap_or_default();
+        // Keep the collapsed state of the sections across refreshes
+        self.collapsed.resize(self.sections.len(), false);
+        self.selected = self.selected.min(self.sections.len().saturating_sub(1));
+    }
+}
+
+impl<B: Backend> Draw<B> for ContainerInfo {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let height = rect.height.saturating_sub(2); // 2 for border
+
+        let (text, scroll) = match self.details {
+            None => (vec![Text::raw("Could not retrieve container details.")], 0),
+            Some(ref details) if self.raw => (
+                vec![Text::raw(
+                    serde_json::to_string_pretty(details).unwrap_or_default(),
+                )],
+                self.scroll,
+            ),
+            Some(_) => {
+                let (text, selected_line) = self.structured_text();
+                // Scroll just enough for the title of the selected section to be visible
+                let scroll = (selected_line as u16).saturating_sub(height.saturating_sub(1));
+                (text, scroll)
+            }
         };
-        let text = vec![data];
 
+        let title = format!(
+            "Container {}{}",
+            self.name,
+            if self.raw { " [raw]" } else { "" }
+        );
         Paragraph::new(text.iter())
-            .block(Block::default().borders(Borders::ALL))
+            .block(Block::default().borders(Borders::ALL).title(&title))
             .wrap(true)
-            .scroll(self.scroll)
+            .scroll(scroll)
             .raw(true)
             .render(t, rect);
     }
 }
+
+/// Formats a JSON value for display.
+fn show(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) if s.is_empty() => "-".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) if values.is_empty() => "-".to_string(),
+        Value::Array(values) => values.iter().map(show).collect::<Vec<_>>().join(" "),
+        v => v.to_string(),
+    }
+}
+
+/// Formats a timestamp, along with how long ago it was.
+fn show_timestamp(value: &Value) -> String {
+    let s = value.as_str().unwrap_or_default();
+    match parse_timestamp(s) {
+        Some(time) => {
+            let ago = SystemTime::now()
+                .duration_since(time)
+                .map(|d| format!(" ({} ago)", human_duration(&d)))
+                .unwrap_or_default();
+            format!("{}{}", s, ago)
+        }
+        None => "never".to_string(),
+    }
+}
+
+/// Formats a size in bytes.
+fn show_size(value: &Value) -> String {
+    match value.as_u64() {
+        Some(size) => bytesize::to_string(size, true),
+        None => "-".to_string(),
+    }
+}
+
+/// Formats a key/value map (e.g. labels), sorted by key.
+fn sorted_pairs(value: &Value) -> Vec<(String, String)> {
+    let mut pairs: Vec<_> = value
+        .as_object()
+        .map(|map| map.iter().map(|(k, v)| (k.clone(), show(v))).collect())
+        .unwrap_or_default();
+    pairs.sort();
+    pairs
+}
+
+/// Splits the details of a container into sections.
+fn sections(details: &Value) -> Vec<Section> {
+    let state = &details["State"];
+    let config = &details["Config"];
+    let host_config = &details["HostConfig"];
+    let network_settings = &details["NetworkSettings"];
+
+    let mut s = Section::new("State");
+    s.add("Status", show(&state["Status"]));
+    s.add("Running", show(&state["Running"]));
+    s.add("Paused", show(&state["Paused"]));
+    s.add("Restarting", show(&state["Restarting"]));
+    s.add("OOM killed", show(&state["OOMKilled"]));
+    s.add("Pid", show(&state["Pid"]));
+    s.add("Exit code", show(&state["ExitCode"]));
+    s.add("Error", show(&state["Error"]));
+    s.add("Started at", show_timestamp(&state["StartedAt"]));
+    s.add("Finished at", show_timestamp(&state["FinishedAt"]));
+    let state_section = s;
+
+    let mut s = Section::new("Config");
+    s.add("Id", show(&details["Id"]));
+    s.add(
+        "Name",
+        show(&details["Name"]).trim_start_matches('/').to_string(),
+    );
+    s.add("Created", show_timestamp(&details["Created"]));
+    s.add("Image", show(&config["Image"]));
+    s.add("Image id", show(&details["Image"]));
+    s.add("Entrypoint", show(&config["Entrypoint"]));
+    s.add("Command", show(&config["Cmd"]));
+    s.add("Working dir", show(&config["WorkingDir"]));
+    s.add("User", show(&config["User"]));
+    s.add("Hostname", show(&config["Hostname"]));
+    s.add("TTY", show(&config["Tty"]));
+    s.add("Privileged", show(&host_config["Privileged"]));
+    s.add(
+        "Memory limit",
+        match host_config["Memory"].as_u64() {
+            Some(0) | None => "unlimited".to_string(),
+            Some(size) => bytesize::to_string(size, true),
+        },
+    );
+    s.add("CPU shares", show(&host_config["CpuShares"]));
+    s.add("Size (rw)", show_size(&details["SizeRw"]));
+    s.add("Size (root fs)", show_size(&details["SizeRootFs"]));
+    let config_section = s;
+
+    let mut s = Section::new("Env");
+    for var in config["Env"].as_array().into_iter().flatten() {
+        let var = var.as_str().unwrap_or_default();
+        let mut parts = var.splitn(2, '=');
+        let key = parts.next().unwrap_or_default();
+        s.add(key, parts.next().unwrap_or_default().to_string());
+    }
+    let env_section = s;
+
+    let mut s = Section::new("Mounts");
+    for mount in details["Mounts"].as_array().into_iter().flatten() {
+        let mode = if mount["RW"].as_bool().unwrap_or(false) {
+            "rw"
+        } else {
+            "ro"
+        };
+        s.add(
+            show(&mount["Destination"]),
+            format!(
+                "{} ({}, {})",
+                show(&mount["Source"]),
+                show(&mount["Type"]),
+                mode
+            ),
+        );
+    }
+    let mounts_section = s;
+
+    let mut s = Section::new("Network settings");
+    s.add("Network mode", show(&host_config["NetworkMode"]));
+    if let Some(networks) = network_settings["Networks"].as_object() {
+        for (name, network) in networks {
+            s.add(
+                name.clone(),
+                format!(
+                    "{}/{} (gateway {}, mac {})",
+                    show(&network["IPAddress"]),
+                    show(&network["IPPrefixLen"]),
+                    show(&network["Gateway"]),
+                    show(&network["MacAddress"])
+                ),
+            );
+        }
+    }
+    if let Some(ports) = network_settings["Ports"].as_object() {
+        for (port, bindings) in ports {
+            let bindings = bindings
+                .as_array()
+                .map(|bindings| {
+                    bindings
+                        .iter()
+                        .map(|b| format!("{}:{}", show(&b["HostIp"]), show(&b["HostPort"])))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_else(|| "not published".to_string());
+            s.add(format!("Port {}", port), bindings);
+        }
+    }
+    let network_section = s;
+
+    let mut labels_section = Section::new("Labels");
+    labels_section.fields = sorted_pairs(&config["Labels"]);
+
+    let restart_policy = &host_config["RestartPolicy"];
+    let mut s = Section::new("Restart policy");
+    s.add("Policy", show(&restart_policy["Name"]));
+    s.add("Max retries", show(&restart_policy["MaximumRetryCount"]));
+    s.add("Restart count", show(&details["RestartCount"]));
+    let restart_section = s;
+
+    let health = &state["Health"];
+    let mut s = Section::new("Health");
+    if !health.is_null() {
+        s.add("Status", show(&health["Status"]));
+        s.add("Failing streak", show(&health["FailingStreak"]));
+        for check in health["Log"].as_array().into_iter().flatten() {
+            s.add(
+                show_timestamp(&check["Start"]),
+                format!(
+                    "exit code {}: {}",
+                    show(&check["ExitCode"]),
+                    show(&check["Output"]).trim()
+                ),
+            );
+        }
+    }
+    let health_section = s;
+
+    vec![
+        state_section,
+        config_section,
+        env_section,
+        mounts_section,
+        network_section,
+        labels_section,
+        restart_section,
+        health_section,
+    ]
+}
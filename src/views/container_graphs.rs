@@ -0,0 +1,152 @@
+use crossbeam_channel::{unbounded, Receiver};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    widgets::{Block, Borders, Paragraph, Sparkline, Text, Widget},
+    Frame,
+};
+
+use crate::app::ContainerId;
+use crate::events::DockerEvent;
+use crate::stats::{ContainerStats, Sample, Stats};
+use crate::theme;
+use crate::views::{Draw, View};
+use crate::worker::Worker;
+
+/// Graphs of the recent resource usage of a single container.
+pub struct ContainerGraphsView {
+    id: ContainerId,
+    stats: ContainerStats,
+    rx: Option<Receiver<(String, Option<Stats>)>>,
+}
+
+impl ContainerGraphsView {
+    pub fn new(id: ContainerId) -> ContainerGraphsView {
+        ContainerGraphsView {
+            id,
+            stats: ContainerStats::default(),
+            rx: None,
+        }
+    }
+
+    /// Returns the most recent values of `history` that fit in the given area.
+    fn visible(history: &[u64], rect: Rect) -> &[u64] {
+        let width = rect.width.saturating_sub(2) as usize; // 2 for border
+        &history[history.len().saturating_sub(width)..]
+    }
+}
+
+impl View for ContainerGraphsView {
+    fn name(&self) -> &'static str {
+        "container_graphs"
+    }
+
+    fn refresh(&mut self, worker: &Worker) {
+        // Keep the history we already have, the stream only needs to be started again once it
+        // has ended (i.e. the container has been restarted)
+        if self.rx.is_none() {
+            let (tx, rx) = unbounded();
+            let id = self.id.0.clone();
+            worker.spawn(move |docker| docker.container_stats(&id, tx));
+            self.rx = Some(rx);
+        }
+    }
+
+    fn tick(&mut self, _worker: &Worker) {
+        // The stats are streamed, there's nothing to refresh
+    }
+
+    fn handle_docker_event(&mut self, event: &DockerEvent) -> bool {
+        event.typ == "container" && event.action == "start"
+    }
+
+    fn update(&mut self) {
+        let mut ended = false;
+        if let Some(ref rx) = self.rx {
+            for (_, stats) in rx.try_iter() {
+                match stats {
+                    Some(stats) => self.stats.push(Sample::from(&stats)),
+                    None => ended = true,
+                }
+            }
+        }
+        if ended {
+            self.rx = None;
+        }
+    }
+}
+
+impl<B: Backend> Draw<B> for ContainerGraphsView {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Percentage(50),
+                Constraint::Min(0),
+                Constraint::Length(4),
+            ])
+            .margin(0)
+            .split(rect);
+        let current = &self.stats.current;
+
+        let cpu_history: Vec<u64> = self.stats.cpu_history.iter().cloned().collect();
+        let cpu_title = format!("{} - CPU: {:.2}%", self.id.0, self.stats.cpu_percent);
+        // Scale the graph to 100% of one CPU, unless the container uses more than that
+        let cpu_max = cpu_history
+            .iter()
+            .cloned()
+            .max()
+            .unwrap_or(0)
+            .max(100 * 100);
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(&cpu_title))
+            .data(Self::visible(&cpu_history, chunks[0]))
+            .max(cpu_max)
+            .style(Style::default().fg(theme.running))
+            .render(t, chunks[0]);
+
+        let mem_history: Vec<u64> = self.stats.mem_history.iter().cloned().collect();
+        let mem_title = format!(
+            "Memory: {} / {} ({:.2}%)",
+            bytesize::to_string(current.mem_usage, true),
+            bytesize::to_string(current.mem_limit, true),
+            current.mem_percent()
+        );
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(&mem_title))
+            .data(Self::visible(&mem_history, chunks[1]))
+            .max(current.mem_limit.max(1))
+            .style(Style::default().fg(theme.secondary))
+            .render(t, chunks[1]);
+
+        let text = [
+            Text::raw(format!(
+                "Received: {}/s ({} total)\n",
+                bytesize::to_string(self.stats.net_rx_rate as u64, false),
+                bytesize::to_string(current.net_rx, false)
+            )),
+            Text::raw(format!(
+                "Sent:     {}/s ({} total)\n",
+                bytesize::to_string(self.stats.net_tx_rate as u64, false),
+                bytesize::to_string(current.net_tx, false)
+            )),
+        ];
+        Paragraph::new(text.iter())
+            .block(Block::default().borders(Borders::ALL).title("Network"))
+            .render(t, chunks[2]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::views::snapshot::{assert_snapshot, render};
+
+    #[test]
+    fn snapshot_no_data() {
+        let view = ContainerGraphsView::new(ContainerId("web".to_string()));
+        assert_snapshot("container_graphs_empty", &render(&view, 60, 20));
+    }
+}
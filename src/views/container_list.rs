@@ -1,22 +1,223 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 
-use shiplift::{
-    rep::{Container, Port},
-    ContainerListOptions,
-};
+use failure::Error;
+use shiplift::rep::{Container, Port};
 use termion::event::Key;
 use tui::{
+    backend::Backend,
+    buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     widgets::{Block, Borders, List, Row, Table, Text, Widget},
     Frame,
 };
 
 use crate::app::{AppCommand, ContainerId};
-use crate::docker::DockerExecutor;
-use crate::views::{human_duration, View, ViewType};
-use crate::Backend;
+use crate::bulk::{ContainerOperation, Target};
+use crate::compose::{self, Group};
+use crate::docker::{DeleteOptions, DockerBackend, ListFilter, ListKind, ListOptions};
+use crate::events::DockerEvent;
+use crate::keys::Action;
+use crate::theme;
+use crate::views::{
+    container_name, human_duration, Column, Draw, FilterBar, FilterEditor, Highlights, RowMatch,
+    Searchable, SortOrder, View, ViewType,
+};
+use crate::worker::{Pending, Worker};
+
+/// The columns of the container list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContainerColumn {
+    Id,
+    Name,
+    Image,
+    Command,
+    Status,
+    State,
+    Created,
+    Ports,
+}
+
+impl Column for ContainerColumn {
+    type Row = Container;
+
+    const ALL: &'static [ContainerColumn] = &[
+        ContainerColumn::Id,
+        ContainerColumn::Name,
+        ContainerColumn::Image,
+        ContainerColumn::Command,
+        ContainerColumn::Status,
+        ContainerColumn::State,
+        ContainerColumn::Created,
+        ContainerColumn::Ports,
+    ];
+    const DEFAULT: &'static [ContainerColumn] = &[
+        ContainerColumn::Id,
+        ContainerColumn::Name,
+        ContainerColumn::Image,
+        ContainerColumn::Command,
+        ContainerColumn::Status,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ContainerColumn::Id => "id",
+            ContainerColumn::Name => "name",
+            ContainerColumn::Image => "image",
+            ContainerColumn::Command => "command",
+            ContainerColumn::Status => "status",
+            ContainerColumn::State => "state",
+            ContainerColumn::Created => "created",
+            ContainerColumn::Ports => "ports",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            ContainerColumn::Id => "Container ID",
+            ContainerColumn::Name => "Name",
+            ContainerColumn::Image => "Image",
+            ContainerColumn::Command => "Command",
+            ContainerColumn::Status => "Status",
+            ContainerColumn::State => "State",
+            ContainerColumn::Created => "Created",
+            ContainerColumn::Ports => "Ports",
+        }
+    }
+
+    fn width(self) -> u16 {
+        match self {
+            ContainerColumn::Id => 15,
+            ContainerColumn::Name => 20,
+            ContainerColumn::Image => 20,
+            ContainerColumn::Command => 30,
+            ContainerColumn::Status => 20,
+            ContainerColumn::State => 10,
+            ContainerColumn::Created => 15,
+            ContainerColumn::Ports => 30,
+        }
+    }
+
+    fn value(self, c: &Container) -> String {
+        match self {
+            ContainerColumn::Id => c.id.clone(),
+            ContainerColumn::Name => container_name(c).unwrap_or_default().to_string(),
+            ContainerColumn::Image => c.image.clone(),
+            ContainerColumn::Command => c.command.clone(),
+            ContainerColumn::Status => c.status.clone(),
+            ContainerColumn::State => state(&c.status).to_string(),
+            ContainerColumn::Created => {
+                let created_time = ::std::time::UNIX_EPOCH + Duration::from_secs(c.created);
+                let duration = created_time.elapsed().unwrap_or_default();
+                format!("{} ago", human_duration(&duration))
+            }
+            ContainerColumn::Ports => {
+                let mut ports = c.ports.clone();
+                ports.sort_by_key(|p| p.private_port);
+                ports
+                    .iter()
+                    .map(display_port)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        }
+    }
+
+    fn compare(self, a: &Container, b: &Container) -> Ordering {
+        match self {
+            // Running containers first, by uptime
+            ContainerColumn::Status => {
+                let key = |c: &Container| {
+                    let uptime = uptime(&c.status);
+                    (uptime.is_none(), uptime, state_rank(state(&c.status)))
+                };
+                key(a).cmp(&key(b))
+            }
+            ContainerColumn::State => {
+                state_rank(state(&a.status)).cmp(&state_rank(state(&b.status)))
+            }
+            ContainerColumn::Created => a.created.cmp(&b.created),
+            ContainerColumn::Ports => {
+                let lowest = |c: &Container| c.ports.iter().map(|p| p.private_port).min();
+                lowest(a).cmp(&lowest(b))
+            }
+            _ => self.value(a).cmp(&self.value(b)),
+        }
+    }
+}
+
+/// Returns the rank of a container state when sorting by state, from the most to the least
+/// alive.
+fn state_rank(state: &str) -> u8 {
+    match state {
+        "running" => 0,
+        "paused" => 1,
+        "restarting" => 2,
+        "created" => 3,
+        "removing" => 4,
+        "exited" => 5,
+        "dead" => 6,
+        _ => 7,
+    }
+}
+
+/// Returns for how long a container has been up, in seconds, from its status as reported by the
+/// daemon (e.g. `Up 5 minutes (Paused)`), or `None` if it isn't up.
+fn uptime(status: &str) -> Option<u64> {
+    let words: Vec<_> = status.strip_prefix("Up ")?.split_whitespace().collect();
+    let (count, unit) = match words.as_slice() {
+        ["Less", "than", "a", "second", ..] => return Some(0),
+        ["About", "a", unit, ..] | ["About", "an", unit, ..] => (1, *unit),
+        [count, unit, ..] => (count.parse().ok()?, *unit),
+        _ => return None,
+    };
+    let seconds = match unit.trim_end_matches('s') {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 3600,
+        "day" => 86400,
+        "week" => 7 * 86400,
+        "month" => 30 * 86400,
+        "year" => 365 * 86400,
+        _ => return None,
+    };
+    Some(count * seconds)
+}
+
+impl Searchable for ContainerColumn {
+    const SEARCHED: &'static [ContainerColumn] = &[
+        ContainerColumn::Id,
+        ContainerColumn::Name,
+        ContainerColumn::Image,
+        ContainerColumn::Command,
+        ContainerColumn::Status,
+    ];
+
+    fn labels(c: &Container) -> Vec<(&str, &str)> {
+        c.labels
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect()
+    }
+}
+
+/// A row of the container list.
+enum ListRow {
+    /// The header of a group, with the indices of its containers in `containers`
+    Group(Group, Vec<usize>),
+    /// A container matching the filter
+    Container(RowMatch<ContainerColumn>),
+}
+
+/// What is selected in the list, to keep it selected when the rows change.
+#[derive(Debug, Clone, PartialEq)]
+enum Selection {
+    Container(String),
+    Group(Group),
+}
 
 pub struct ContainerListView {
     /// List of containers to display
@@ -25,6 +226,30 @@ pub struct ContainerListView {
     pub selected: usize,
     /// Whether to only display currently running containers
     pub only_running: bool,
+    /// Filters applied by the daemon to the list of containers
+    pub filters: Vec<ListFilter>,
+    /// Edits the filters applied by the daemon
+    editor: FilterEditor,
+    /// Columns of the list
+    pub columns: Vec<ContainerColumn>,
+    /// IDs of the containers the operations apply to, instead of the selected one
+    marked: HashSet<String>,
+    /// Text the containers to mark must match, while it is being typed
+    mark_pattern: Option<String>,
+    /// Narrows the displayed containers
+    filter: FilterBar,
+    /// Order of the containers
+    sort: SortOrder<ContainerColumn>,
+    /// Whether the containers are grouped by Compose project and service
+    grouped: bool,
+    /// Groups whose containers are hidden
+    collapsed: HashSet<Group>,
+    /// Rows of the list, computed again when the containers, the filter or the groups change
+    rows: Vec<ListRow>,
+    /// Number of containers matching the filter
+    matching: usize,
+    /// Containers being fetched in the background
+    pending: Pending<Result<Vec<Container>, Error>>,
 }
 
 impl ContainerListView {
@@ -33,36 +258,215 @@ impl ContainerListView {
             containers: Vec::new(),
             selected: 0,
             only_running: false,
+            filters: Vec::new(),
+            editor: FilterEditor::new(ListKind::Containers),
+            columns: ContainerColumn::DEFAULT.to_vec(),
+            marked: HashSet::new(),
+            mark_pattern: None,
+            filter: FilterBar::new(),
+            sort: SortOrder::new(),
+            grouped: false,
+            collapsed: HashSet::new(),
+            rows: Vec::new(),
+            matching: 0,
+            pending: Pending::none(),
         }
     }
 
     pub fn get_selected_container(&self) -> Option<&Container> {
-        self.containers.get(self.selected)
+        match self.rows.get(self.selected)? {
+            ListRow::Container(m) => Some(&self.containers[m.index]),
+            ListRow::Group(..) => None,
+        }
+    }
+
+    /// Returns the containers matching the filter, which are the ones displayed.
+    fn visible(&self) -> Vec<RowMatch<ContainerColumn>> {
+        self.filter.apply(&self.containers)
+    }
+
+    /// Computes the rows of the list: the containers matching the filter, preceded by the headers
+    /// of their project and service in grouped mode.
+    fn update_rows(&mut self) {
+        let visible = self.visible();
+        self.matching = visible.len();
+        if !self.grouped {
+            self.rows = visible.into_iter().map(ListRow::Container).collect();
+            return;
+        }
+        let projects = compose::group(visible.iter().map(|m| &self.containers[m.index].labels));
+        let indices: Vec<_> = visible.iter().map(|m| m.index).collect();
+        let mut matches: Vec<_> = visible.into_iter().map(Some).collect();
+        let mut rows = Vec::new();
+        for project in projects {
+            let collapsed = self.collapsed.contains(&project.group);
+            let containers = project.containers.iter().map(|&p| indices[p]).collect();
+            rows.push(ListRow::Group(project.group, containers));
+            if collapsed {
+                continue;
+            }
+            if project.services.is_empty() {
+                let containers = project.containers.iter().filter_map(|&p| matches[p].take());
+                rows.extend(containers.map(ListRow::Container));
+            }
+            for (service, positions) in project.services {
+                let collapsed = self.collapsed.contains(&service);
+                let containers = positions.iter().map(|&p| indices[p]).collect();
+                rows.push(ListRow::Group(service, containers));
+                if !collapsed {
+                    let containers = positions.iter().filter_map(|&p| matches[p].take());
+                    rows.extend(containers.map(ListRow::Container));
+                }
+            }
+        }
+        self.rows = rows;
+    }
+
+    /// Returns what is selected.
+    fn selection(&self) -> Option<Selection> {
+        match self.rows.get(self.selected)? {
+            ListRow::Container(m) => {
+                Some(Selection::Container(self.containers[m.index].id.clone()))
+            }
+            ListRow::Group(group, _) => Some(Selection::Group(group.clone())),
+        }
+    }
+
+    /// Selects the given container or group if it is displayed, or keeps the selection within the
+    /// rows otherwise.
+    fn reselect(&mut self, selection: Option<Selection>) {
+        let rows = &self.rows;
+        let position = selection.and_then(|selection| {
+            rows.iter().position(|row| match (row, &selection) {
+                (ListRow::Container(m), Selection::Container(id)) => {
+                    self.containers[m.index].id == *id
+                }
+                (ListRow::Group(group, _), Selection::Group(selected)) => group == selected,
+                _ => false,
+            })
+        });
+        match position {
+            Some(position) => self.selected = position,
+            None => self.selected = self.selected.min(rows.len().saturating_sub(1)),
+        }
     }
 
-    fn draw_container_list(&self, t: &mut Frame<Backend>, rect: Rect) {
-        let selected_style = Style::default().fg(Color::Yellow).modifier(Modifier::BOLD);
-        let normal_style = Style::default().fg(Color::White);
-        let running_style = Style::default().fg(Color::Green);
-        let header = ["Container ID", "Name", "Image", "Command", "Status"];
-        let height = rect.height as usize - 4; // 2 for border + 2 for header
+    /// Returns the header of a group, with the number of its containers in each state, e.g.
+    /// `▼ shop: 2 running, 1 exited`.
+    fn group_header(&self, group: &Group, indices: &[usize]) -> String {
+        let mut states = BTreeMap::new();
+        for &i in indices {
+            let state = state(&self.containers[i].status);
+            *states.entry((state_rank(state), state)).or_insert(0) += 1;
+        }
+        let counts: Vec<_> = states
+            .iter()
+            .map(|((_, state), count)| format!("{} {}", count, state))
+            .collect();
+        let indent = match *group {
+            Group::Service(..) => "  ",
+            Group::Project(_) => "",
+        };
+        let arrow = if self.collapsed.contains(group) {
+            "▶"
+        } else {
+            "▼"
+        };
+        format!(
+            "{}{} {}: {}",
+            indent,
+            arrow,
+            group.title(),
+            counts.join(", ")
+        )
+    }
+
+    /// Returns the command applying the operation to the marked containers, or to the selected
+    /// container or group if none are marked.
+    fn operation(&self, operation: ContainerOperation) -> Option<AppCommand> {
+        let targets: Vec<_> = if self.marked.is_empty() {
+            match self.rows.get(self.selected) {
+                Some(ListRow::Container(m)) => vec![&self.containers[m.index]],
+                Some(ListRow::Group(_, indices)) => {
+                    indices.iter().map(|&i| &self.containers[i]).collect()
+                }
+                None => Vec::new(),
+            }
+        } else {
+            self.containers
+                .iter()
+                .filter(|c| self.marked.contains(&c.id))
+                .collect()
+        };
+        if targets.is_empty() {
+            return Some(AppCommand::NoOp);
+        }
+        let targets = targets
+            .into_iter()
+            .map(|c| Target {
+                id: ContainerId(c.id.clone()),
+                name: container_name(c).unwrap_or(c.id.as_str()).to_string(),
+                running: c.status.starts_with("Up "),
+            })
+            .collect();
+        Some(AppCommand::RunOperation(operation, targets))
+    }
+
+    fn draw_container_list<B: Backend>(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
+        let selected_style = Style::default().fg(theme.selected).modifier(Modifier::BOLD);
+        let normal_style = Style::default().fg(theme.foreground);
+        let running_style = Style::default().fg(theme.running);
+        // The first column shows which containers are marked
+        let header: Vec<_> = Some(String::new())
+            .into_iter()
+            .chain(self.columns.iter().map(|&c| self.sort.title(c)))
+            .collect();
+        let widths: Vec<_> = Some(1)
+            .into_iter()
+            .chain(self.columns.iter().map(|c| c.width()))
+            .collect();
+        let height = rect.height.saturating_sub(4) as usize; // 2 for border + 2 for header
         let offset = if self.selected >= height {
             self.selected - height + 1
         } else {
             0
         };
-        let rows: Vec<_> = self
-            .containers
+        let group_style = Style::default().fg(theme.accent).modifier(Modifier::BOLD);
+        let list_rows = &self.rows;
+        // The headers of the groups are drawn over empty rows
+        let headers: Vec<_> = list_rows
             .iter()
             .enumerate()
-            .map(|(i, c)| {
-                let data: Vec<String> = vec![
-                    c.id.clone(),
-                    Self::container_name(c).unwrap_or_default().to_string(),
-                    c.image.clone(),
-                    c.command.clone(),
-                    c.status.clone(),
-                ];
+            .filter_map(|(i, row)| match *row {
+                ListRow::Group(ref group, ref indices) => {
+                    let style = if i == self.selected {
+                        selected_style
+                    } else {
+                        group_style
+                    };
+                    Some((i, self.group_header(group, indices), style))
+                }
+                ListRow::Container(_) => None,
+            })
+            .collect();
+        let rows: Vec<_> = list_rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let m = match *row {
+                    ListRow::Container(ref m) => m,
+                    ListRow::Group(..) => {
+                        let empty = vec![String::new(); widths.len()];
+                        return Row::StyledData(empty.into_iter(), normal_style);
+                    }
+                };
+                let c = &self.containers[m.index];
+                let marker = if self.marked.contains(&c.id) { "*" } else { "" };
+                let data: Vec<String> = Some(marker.to_string())
+                    .into_iter()
+                    .chain(self.columns.iter().map(|col| col.value(c)))
+                    .collect();
                 if i == self.selected {
                     Row::StyledData(data.into_iter(), selected_style)
                 } else if c.status.starts_with("Up ") {
@@ -74,17 +478,55 @@ impl ContainerListView {
             .skip(offset)
             .collect();
 
+        let mut titles: Vec<_> = self.editor.title(&self.filters).into_iter().collect();
+        if let Some(filter) = self.filter.title() {
+            titles.push(format!(
+                "{} ({} of {})",
+                filter,
+                self.matching,
+                self.containers.len()
+            ));
+        }
+        titles.extend(self.sort.hidden_title(&self.columns));
+        match self.mark_pattern {
+            Some(ref pattern) => titles.push(format!("Mark the containers matching: {}_", pattern)),
+            None if !self.marked.is_empty() => titles.push(format!("{} marked", self.marked.len())),
+            None => {}
+        }
+        let title = titles.join(" | ");
         Table::new(header.iter(), rows.into_iter())
-            .block(Block::default().borders(Borders::ALL))
-            .widths(&[15, 20, 20, 30, 20]) // TODO be smarter with sizes here
+            .block(Block::default().borders(Borders::ALL).title(&title))
+            .widths(&widths) // TODO be smarter with sizes here
             .render(t, rect);
+        GroupHeaders {
+            headers: &headers,
+            offset,
+        }
+        .render(t, rect);
+        if self.filter.is_active() {
+            let matches: Vec<_> = list_rows
+                .iter()
+                .map(|row| match *row {
+                    ListRow::Container(ref m) => Some(m),
+                    ListRow::Group(..) => None,
+                })
+                .collect();
+            Highlights {
+                rows: &matches,
+                offset,
+                skip: 1,
+                columns: &self.columns,
+                widths: &widths,
+            }
+            .render(t, rect);
+        }
     }
 
-    fn draw_container_info(&self, t: &mut Frame<Backend>, rect: Rect) {
+    fn draw_container_info<B: Backend>(&self, t: &mut Frame<B>, rect: Rect) {
         let mut text = vec![];
         if let Some(c) = self.get_selected_container() {
             let created_time = ::std::time::UNIX_EPOCH + Duration::from_secs(c.created);
-            let duration = created_time.elapsed().unwrap();
+            let duration = created_time.elapsed().unwrap_or_default();
             let mut ports = c.ports.clone();
             let ports_slice: &mut [Port] = ports.as_mut();
             ports_slice.sort_by_key(|p: &Port| p.private_port);
@@ -105,7 +547,7 @@ impl ContainerListView {
             text.push(Text::raw(format!(
                 "{:>15}: {}",
                 "Name",
-                Self::container_name(c).unwrap_or_else(|| "")
+                container_name(c).unwrap_or_default()
             )));
             text.push(Text::raw(format!("{:>15}: {}", "Ports", ports_displayed)));
             text.push(Text::raw(format!("{:>15}: {}", "Status", c.status)));
@@ -121,42 +563,41 @@ impl ContainerListView {
             // .wrap(true)
             .render(t, rect);
     }
-
-    fn container_name(container: &Container) -> Option<&str> {
-        container.names.first().map(|name| {
-            if name.starts_with('/') {
-                &name[1..]
-            } else {
-                name.as_str()
-            }
-        })
-    }
 }
 
 impl View for ContainerListView {
-    fn handle_input(&mut self, key: Key, docker: Arc<DockerExecutor>) -> Option<AppCommand> {
-        let max_index = self.containers.len() - 1;
-        match key {
-            Key::Down | Key::Char('j') => {
-                if !self.containers.is_empty() {
+    fn name(&self) -> &'static str {
+        "containers"
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        _docker: Arc<dyn DockerBackend>,
+    ) -> Option<AppCommand> {
+        let count = self.rows.len();
+        let max_index = count.saturating_sub(1);
+        match action {
+            Action::Down => {
+                if count > 0 {
                     self.selected = (self.selected + 1).min(max_index);
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::Up | Key::Char('k') => {
-                if !self.containers.is_empty() && self.selected > 0 {
+            Action::Up => {
+                if count > 0 && self.selected > 0 {
                     self.selected -= 1;
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::PageDown | Key::Ctrl('d') => {
-                if !self.containers.is_empty() {
+            Action::PageDown => {
+                if count > 0 {
                     self.selected = (self.selected + 10).min(max_index);
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::PageUp | Key::Ctrl('u') => {
-                if !self.containers.is_empty() {
+            Action::PageUp => {
+                if count > 0 {
                     self.selected = if self.selected >= 10 {
                         self.selected - 10
                     } else {
@@ -165,121 +606,226 @@ impl View for ContainerListView {
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::End | Key::Char('G') => {
-                if !self.containers.is_empty() {
+            Action::Bottom => {
+                if count > 0 {
                     self.selected = max_index;
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::Home | Key::Char('g') => {
-                if !self.containers.is_empty() {
+            Action::Top => {
+                if count > 0 {
                     self.selected = 0;
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::Char('a') => {
+            Action::ToggleAll => {
                 self.only_running = !self.only_running;
                 Some(AppCommand::Refresh)
             }
-            Key::Char('\n') => {
-                let container = self.get_selected_container().unwrap();
+            Action::Details => match self.rows.get(self.selected)? {
+                ListRow::Container(m) => {
+                    let id = ContainerId(self.containers[m.index].id.clone());
+                    Some(AppCommand::SwitchToView(ViewType::ContainerDetails(id)))
+                }
+                ListRow::Group(group, _) => {
+                    let group = group.clone();
+                    if !self.collapsed.remove(&group) {
+                        self.collapsed.insert(group);
+                    }
+                    self.update_rows();
+                    Some(AppCommand::NoOp)
+                }
+            },
+            Action::Logs => {
+                let container = self.get_selected_container()?;
                 let id = ContainerId(container.id.clone());
-                Some(AppCommand::SwitchToView(ViewType::ContainerDetails(id)))
+                Some(AppCommand::SwitchToView(ViewType::ContainerLogs(id)))
             }
-            Key::Char('l') => {
-                let container = self.get_selected_container().unwrap();
+            Action::Shell => {
+                let container = self.get_selected_container()?;
                 let id = ContainerId(container.id.clone());
-                Some(AppCommand::SwitchToView(ViewType::ContainerLogs(id)))
+                Some(AppCommand::Exec(id))
             }
-            Key::Char('p') => {
-                let selected_container = self.get_selected_container().unwrap();
-                info!("Pausing container {}", selected_container.id);
-                match docker.container_pause(&selected_container.id) {
-                    Ok(_) => Some(AppCommand::Refresh),
-                    Err(err) => {
-                        error!("Failed to pause container: {}", err);
-                        Some(AppCommand::ErrorMsg(format!(
-                            "Failed to pause container: {}",
-                            err
-                        )))
+            Action::Pause => self.operation(ContainerOperation::Pause),
+            Action::Unpause => self.operation(ContainerOperation::Unpause),
+            Action::Stop => self.operation(ContainerOperation::Stop),
+            Action::Start => self.operation(ContainerOperation::Start),
+            Action::Restart => self.operation(ContainerOperation::Restart),
+            Action::Delete => self.operation(ContainerOperation::Delete(DeleteOptions::default())),
+            Action::ToggleMark => {
+                let ids: Vec<_> = match self.rows.get(self.selected) {
+                    Some(ListRow::Container(m)) => vec![self.containers[m.index].id.clone()],
+                    Some(ListRow::Group(_, indices)) => indices
+                        .iter()
+                        .map(|&i| self.containers[i].id.clone())
+                        .collect(),
+                    None => return Some(AppCommand::NoOp),
+                };
+                // A group is unmarked if all its containers are marked, and marked otherwise
+                if ids.iter().all(|id| self.marked.contains(id)) {
+                    for id in &ids {
+                        self.marked.remove(id);
                     }
+                } else {
+                    self.marked.extend(ids);
                 }
+                self.selected = (self.selected + 1).min(max_index);
+                Some(AppCommand::NoOp)
             }
-            Key::Char('P') => {
-                let selected_container = self.get_selected_container().unwrap();
-                info!("Un-pausing container {}", selected_container.id);
-                match docker.container_unpause(&selected_container.id) {
-                    Ok(_) => Some(AppCommand::Refresh),
-                    Err(err) => {
-                        error!("Failed to un-pause container: {}", err);
-                        Some(AppCommand::ErrorMsg(format!(
-                            "Failed to unpause container: {}",
-                            err
-                        )))
-                    }
-                }
+            Action::MarkAll => {
+                let containers = &self.containers;
+                self.marked = self
+                    .visible()
+                    .iter()
+                    .map(|m| containers[m.index].id.clone())
+                    .collect();
+                Some(AppCommand::NoOp)
             }
-            Key::Char('s') => {
-                let selected_container = self.get_selected_container().unwrap();
-                // TODO use a timeout?
-                info!("Stopping container {}", selected_container.id);
-                match docker.container_stop(&selected_container.id) {
-                    Ok(_) => Some(AppCommand::Refresh),
-                    Err(err) => {
-                        error!("Failed to stop container: {}", err);
-                        Some(AppCommand::ErrorMsg(format!(
-                            "Failed to stop container: {}",
-                            err
-                        )))
-                    }
-                }
+            Action::MarkMatching => {
+                self.mark_pattern = Some(String::new());
+                Some(AppCommand::NoOp)
             }
-            Key::Char('S') => {
-                let selected_container = self.get_selected_container().unwrap();
-                info!("Starting container {}", selected_container.id);
-                match docker.container_start(&selected_container.id) {
-                    Ok(_) => Some(AppCommand::Refresh),
-                    Err(err) => {
-                        error!("Failed to start container: {}", err);
-                        Some(AppCommand::ErrorMsg(format!(
-                            "Failed to start container: {}",
-                            err
-                        )))
-                    }
+            Action::UnmarkAll => {
+                self.marked.clear();
+                Some(AppCommand::NoOp)
+            }
+            Action::Filter => {
+                self.filter.edit();
+                Some(AppCommand::NoOp)
+            }
+            Action::EditFilters => {
+                self.editor.open(&self.filters);
+                Some(AppCommand::NoOp)
+            }
+            Action::GroupByProject => {
+                let selection = self.selection();
+                self.grouped = !self.grouped;
+                self.update_rows();
+                self.reselect(selection);
+                Some(AppCommand::NoOp)
+            }
+            Action::Sort | Action::ReverseSort => {
+                let selected = self.selection();
+                if action == Action::Sort {
+                    self.sort.next_column();
+                } else {
+                    self.sort.reverse();
                 }
+                if self.sort.column.is_none() {
+                    // Restore the order of the daemon
+                    return Some(AppCommand::Refresh);
+                }
+                self.sort.sort(&mut self.containers);
+                self.update_rows();
+                self.reselect(selected);
+                Some(AppCommand::NoOp)
             }
-            Key::Char('d') => {
-                // delete
-                let selected_container = self.get_selected_container().unwrap();
-                info!("Deleting container {}", selected_container.id);
-                match docker.container_delete(&selected_container.id) {
-                    Ok(_) => Some(AppCommand::Refresh),
-                    Err(err) => {
-                        error!("Failed to delete container: {}", err);
-                        Some(AppCommand::ErrorMsg(format!("{}", err)))
+            Action::NextMatch | Action::PreviousMatch if count > 0 => {
+                // Go to the next or previous container, skipping the headers of the groups
+                let step = if action == Action::NextMatch {
+                    1
+                } else {
+                    count - 1
+                };
+                let mut position = self.selected;
+                for _ in 0..count {
+                    position = (position + step) % count;
+                    if let ListRow::Container(_) = self.rows[position] {
+                        self.selected = position;
+                        break;
                     }
                 }
+                Some(AppCommand::NoOp)
             }
+            Action::NextMatch | Action::PreviousMatch => Some(AppCommand::NoOp),
             _ => None,
         }
     }
 
-    fn refresh(&mut self, docker: Arc<DockerExecutor>) {
-        let options = if self.only_running {
-            ContainerListOptions::builder().build()
-        } else {
-            ContainerListOptions::builder().all().build()
+    fn handle_input(&mut self, key: Key, _docker: Arc<dyn DockerBackend>) -> Option<AppCommand> {
+        if self.editor.is_open() {
+            return match self.editor.handle_input(key) {
+                Some(Ok(filters)) => {
+                    self.filters = filters;
+                    Some(AppCommand::Refresh)
+                }
+                Some(Err(err)) => Some(AppCommand::ErrorMsg(err.to_string())),
+                None => Some(AppCommand::NoOp),
+            };
+        }
+        if self.filter.is_editing() || (key == Key::Esc && self.filter.is_active()) {
+            // Keep the selected container selected while the rows change
+            let selected = self.selection();
+            if self.filter.is_editing() {
+                self.filter.handle_input(key);
+            } else {
+                self.filter.clear();
+            }
+            self.update_rows();
+            self.reselect(selected);
+            return Some(AppCommand::NoOp);
+        }
+        let pattern = self.mark_pattern.as_mut()?;
+        match key {
+            Key::Char('\n') => {
+                let pattern = pattern.to_lowercase();
+                let containers = &self.containers;
+                let visible = self.filter.apply::<ContainerColumn>(containers);
+                let matching = visible.iter().map(|m| &containers[m.index]).filter(|c| {
+                    container_name(c)
+                        .unwrap_or_default()
+                        .to_lowercase()
+                        .contains(&pattern)
+                        || c.image.to_lowercase().contains(&pattern)
+                });
+                self.marked.extend(matching.map(|c| c.id.clone()));
+                self.mark_pattern = None;
+            }
+            Key::Esc => self.mark_pattern = None,
+            Key::Backspace => {
+                pattern.pop();
+            }
+            Key::Char(c) => pattern.push(c),
+            _ => {}
+        }
+        Some(AppCommand::NoOp)
+    }
+
+    fn captures_input(&self) -> bool {
+        self.mark_pattern.is_some() || self.filter.is_editing() || self.editor.is_open()
+    }
+
+    fn handle_docker_event(&mut self, event: &DockerEvent) -> bool {
+        event.typ == "container" && event.changes_state()
+    }
+
+    fn refresh(&mut self, worker: &Worker) {
+        let options = ListOptions {
+            all: !self.only_running,
+            filters: self.filters.clone(),
         };
-        let containers = docker.containers(&options).unwrap();
-        self.containers = containers;
-        if self.containers.is_empty() {
-            self.selected = 0;
-        } else if self.selected >= self.containers.len() {
-            self.selected = self.containers.len() - 1;
+        self.pending = worker.run(move |docker| docker.containers(&options));
+    }
+
+    fn update(&mut self) {
+        let selected = self.selection();
+        match self.pending.take() {
+            Some(Ok(containers)) => self.containers = containers,
+            Some(Err(err)) => error!("Failed to list containers: {}", err),
+            None => return,
         }
+        self.sort.sort(&mut self.containers);
+        // Forget the marked containers that are gone
+        let containers = &self.containers;
+        self.marked
+            .retain(|id| containers.iter().any(|c| &c.id == id));
+        self.update_rows();
+        self.reselect(selected);
     }
+}
 
-    fn draw(&self, t: &mut Frame<Backend>, rect: Rect) {
+impl<B: Backend> Draw<B> for ContainerListView {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(vec![Constraint::Percentage(70), Constraint::Percentage(30)])
@@ -294,6 +840,33 @@ impl View for ContainerListView {
     }
 }
 
+/// Draws the headers of the groups over the rows of the table, across all the columns.
+struct GroupHeaders<'a> {
+    /// Position of each header in the rows, with its text and style
+    headers: &'a [(usize, String, Style)],
+    /// Index of the first displayed row
+    offset: usize,
+}
+
+impl<'a> Widget for GroupHeaders<'a> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        // Inside the borders, below the header and the line following it
+        let top = area.top() + 3;
+        let bottom = area.bottom().saturating_sub(1);
+        let width = area.width.saturating_sub(2) as usize;
+        for &(position, ref text, style) in self.headers {
+            if position < self.offset {
+                continue;
+            }
+            let y = top + (position - self.offset) as u16;
+            if y >= bottom {
+                break;
+            }
+            buf.set_stringn(area.left() + 1, y, text, width, style);
+        }
+    }
+}
+
 fn display_port(port: &Port) -> String {
     let mut s = String::new();
     if let Some(ref ip) = port.ip {
@@ -307,3 +880,474 @@ fn display_port(port: &Port) -> String {
 
     s
 }
+
+/// Returns the state of a container (e.g. `running` or `exited`) from its status as reported by
+/// the daemon, as the container list doesn't include the state itself.
+fn state(status: &str) -> &'static str {
+    match status.split_whitespace().next() {
+        Some("Up") if status.contains("(Paused)") => "paused",
+        Some("Up") => "running",
+        Some("Restarting") => "restarting",
+        Some("Created") => "created",
+        Some("Removal") => "removing",
+        Some("Exited") => "exited",
+        Some("Dead") => "dead",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::AppEvent;
+    use crate::docker::fake::FakeDocker;
+    use crate::views::snapshot::{assert_snapshot, render};
+    use crossbeam_channel::unbounded;
+
+    /// Returns a view listing the containers of `docker`.
+    fn refreshed_view(docker: Arc<dyn DockerBackend>) -> ContainerListView {
+        let (tx, rx) = unbounded();
+        let worker = Worker::new(docker, tx);
+        let mut view = ContainerListView::new();
+        view.refresh(&worker);
+        match rx.recv() {
+            Ok(AppEvent::Refreshed) => view.update(),
+            _ => panic!("The refresh didn't complete"),
+        }
+        view
+    }
+
+    #[test]
+    fn lists_all_containers() {
+        let docker = FakeDocker::new()
+            .with_container("0123456789ab", "web", "nginx", "running")
+            .with_container("ba9876543210", "db", "postgres", "exited");
+        let view = refreshed_view(Arc::new(docker));
+        let names: Vec<_> = view.containers.iter().filter_map(container_name).collect();
+        assert_eq!(names, vec!["web", "db"]);
+    }
+
+    fn target(id: &str, name: &str) -> Target {
+        Target {
+            id: ContainerId(id.to_string()),
+            name: name.to_string(),
+            running: true,
+        }
+    }
+
+    #[test]
+    fn pauses_the_selected_container() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_container("0123456789ab", "web", "nginx", "running")
+                .with_container("ba9876543210", "db", "postgres", "running"),
+        );
+        let mut view = refreshed_view(docker.clone());
+
+        view.handle_action(Action::Down, docker.clone());
+        let command = view.handle_action(Action::Pause, docker.clone());
+        assert_eq!(
+            command,
+            Some(AppCommand::RunOperation(
+                ContainerOperation::Pause,
+                vec![target("ba9876543210", "db")]
+            ))
+        );
+    }
+
+    #[test]
+    fn operates_on_the_marked_containers() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_container("0123456789ab", "web", "nginx", "running")
+                .with_container("ba9876543210", "db", "postgres", "running")
+                .with_container("456789abcdef", "cache", "redis", "running"),
+        );
+        let mut view = refreshed_view(docker.clone());
+
+        // Marking a container moves the selection to the next one
+        view.handle_action(Action::ToggleMark, docker.clone());
+        assert_eq!(view.selected, 1);
+        // Mark the containers matching some text
+        view.handle_action(Action::MarkMatching, docker.clone());
+        assert!(view.captures_input());
+        for c in "REDIS\n".chars() {
+            view.handle_input(Key::Char(c), docker.clone());
+        }
+        assert!(!view.captures_input());
+        let command = view.handle_action(Action::Stop, docker.clone());
+        assert_eq!(
+            command,
+            Some(AppCommand::RunOperation(
+                ContainerOperation::Stop,
+                vec![
+                    target("0123456789ab", "web"),
+                    target("456789abcdef", "cache")
+                ]
+            ))
+        );
+
+        view.handle_action(Action::UnmarkAll, docker.clone());
+        let command = view.handle_action(Action::Stop, docker.clone());
+        assert_eq!(
+            command,
+            Some(AppCommand::RunOperation(
+                ContainerOperation::Stop,
+                vec![target("ba9876543210", "db")]
+            ))
+        );
+
+        view.handle_action(Action::MarkAll, docker.clone());
+        assert_eq!(view.marked.len(), 3);
+        // Marks are dropped along with the containers that are gone
+        let force = DeleteOptions {
+            force: true,
+            volumes: false,
+        };
+        docker.container_delete("db", &force).unwrap();
+        let (tx, rx) = unbounded();
+        view.refresh(&Worker::new(docker, tx));
+        rx.recv().unwrap();
+        view.update();
+        assert_eq!(view.marked.len(), 2);
+    }
+
+    #[test]
+    fn navigating_an_empty_list() {
+        let docker = Arc::new(FakeDocker::new());
+        let mut view = refreshed_view(docker.clone());
+        view.handle_action(Action::Down, docker.clone());
+        view.handle_action(Action::Bottom, docker);
+        assert_eq!(view.selected, 0);
+    }
+
+    #[test]
+    fn state_from_status() {
+        assert_eq!(state("Up 5 minutes"), "running");
+        assert_eq!(state("Up 5 minutes (Paused)"), "paused");
+        assert_eq!(state("Restarting (1) 2 seconds ago"), "restarting");
+        assert_eq!(state("Exited (0) 5 minutes ago"), "exited");
+        assert_eq!(state("Removal In Progress"), "removing");
+        assert_eq!(state("Created"), "created");
+        assert_eq!(state("Dead"), "dead");
+    }
+
+    /// Types the given text in the view.
+    fn type_text(view: &mut ContainerListView, docker: &Arc<FakeDocker>, text: &str) {
+        for c in text.chars() {
+            view.handle_input(Key::Char(c), docker.clone());
+        }
+    }
+
+    fn selected_name(view: &ContainerListView) -> &str {
+        view.get_selected_container()
+            .and_then(container_name)
+            .unwrap()
+    }
+
+    #[test]
+    fn filters_the_containers() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_container("0123456789ab", "web", "nginx", "running")
+                .with_container("ba9876543210", "db", "postgres", "running")
+                .with_container("456789abcdef", "cache", "redis", "running")
+                .with_label("db", "com.docker.compose.project", "shop")
+                .with_label("cache", "com.docker.compose.project", "shop"),
+        );
+        let mut view = refreshed_view(docker.clone());
+        view.handle_action(Action::Down, docker.clone());
+
+        // The rows are narrowed as the text is typed, and the selection is kept
+        view.handle_action(Action::Filter, docker.clone());
+        assert!(view.captures_input());
+        type_text(&mut view, &docker, "e");
+        assert_eq!(view.visible().len(), 3);
+        view.handle_input(Key::Backspace, docker.clone());
+        type_text(&mut view, &docker, "re\n");
+        assert!(!view.captures_input());
+        assert_eq!(view.visible().len(), 2);
+        assert_eq!(selected_name(&view), "db");
+
+        // Jump between the matches
+        view.handle_action(Action::NextMatch, docker.clone());
+        assert_eq!(selected_name(&view), "cache");
+        view.handle_action(Action::NextMatch, docker.clone());
+        assert_eq!(selected_name(&view), "db");
+        view.handle_action(Action::PreviousMatch, docker.clone());
+        assert_eq!(selected_name(&view), "cache");
+        let command = view.handle_action(Action::Stop, docker.clone());
+        assert_eq!(
+            command,
+            Some(AppCommand::RunOperation(
+                ContainerOperation::Stop,
+                vec![target("456789abcdef", "cache")]
+            ))
+        );
+
+        // Esc removes the filter
+        view.handle_input(Key::Esc, docker.clone());
+        assert_eq!(view.visible().len(), 3);
+        assert_eq!(view.selected, 2);
+
+        view.handle_action(Action::Filter, docker.clone());
+        type_text(&mut view, &docker, "com.docker.compose.project=SHOP\n");
+        let names: Vec<_> = view
+            .visible()
+            .iter()
+            .filter_map(|m| container_name(&view.containers[m.index]))
+            .collect();
+        assert_eq!(names, vec!["db", "cache"]);
+        view.handle_action(Action::MarkAll, docker.clone());
+        assert_eq!(view.marked.len(), 2);
+    }
+
+    #[test]
+    fn nothing_to_act_on_when_nothing_matches() {
+        let docker =
+            Arc::new(FakeDocker::new().with_container("0123456789ab", "web", "nginx", "running"));
+        let mut view = refreshed_view(docker.clone());
+        view.handle_action(Action::Filter, docker.clone());
+        type_text(&mut view, &docker, "db\n");
+        assert!(view.visible().is_empty());
+        for &action in &[Action::Details, Action::Logs, Action::Shell] {
+            assert_eq!(view.handle_action(action, docker.clone()), None);
+        }
+    }
+
+    #[test]
+    fn daemon_filters() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_container("0123456789ab", "web", "nginx", "running")
+                .with_container("ba9876543210", "db", "postgres", "exited"),
+        );
+        let mut view = refreshed_view(docker.clone());
+        view.handle_action(Action::EditFilters, docker.clone());
+        assert!(view.captures_input());
+        type_text(&mut view, &docker, "status=sleeping");
+        match view.handle_input(Key::Char('\n'), docker.clone()) {
+            Some(AppCommand::ErrorMsg(msg)) => assert!(msg.starts_with("Invalid status sleeping")),
+            command => panic!("Unexpected command {:?}", command),
+        }
+        assert!(view.captures_input());
+        for _ in 0.."sleeping".len() {
+            view.handle_input(Key::Backspace, docker.clone());
+        }
+        type_text(&mut view, &docker, "exited");
+        let command = view.handle_input(Key::Char('\n'), docker.clone());
+        assert_eq!(command, Some(AppCommand::Refresh));
+        assert!(!view.captures_input());
+
+        let (tx, rx) = unbounded();
+        view.refresh(&Worker::new(docker, tx));
+        rx.recv().unwrap();
+        view.update();
+        let names: Vec<_> = view.containers.iter().filter_map(container_name).collect();
+        assert_eq!(names, vec!["db"]);
+    }
+
+    fn compose_stack() -> Arc<FakeDocker> {
+        Arc::new(
+            FakeDocker::new()
+                .with_container("0123456789ab", "shop_web_1", "nginx", "running")
+                .with_container("ba9876543210", "shop_db_1", "postgres", "exited")
+                .with_container("456789abcdef", "cache", "redis", "running")
+                .with_label("shop_web_1", compose::PROJECT_LABEL, "shop")
+                .with_label("shop_web_1", compose::SERVICE_LABEL, "web")
+                .with_label("shop_db_1", compose::PROJECT_LABEL, "shop")
+                .with_label("shop_db_1", compose::SERVICE_LABEL, "db"),
+        )
+    }
+
+    /// Returns the headers of the groups and the names of the containers, as displayed.
+    fn displayed_rows(view: &ContainerListView) -> Vec<String> {
+        view.rows
+            .iter()
+            .map(|row| match row {
+                ListRow::Group(group, indices) => view.group_header(group, indices),
+                ListRow::Container(m) => container_name(&view.containers[m.index])
+                    .unwrap()
+                    .to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn groups_by_compose_project() {
+        let docker = compose_stack();
+        let mut view = refreshed_view(docker.clone());
+        view.handle_action(Action::Bottom, docker.clone());
+        view.handle_action(Action::GroupByProject, docker.clone());
+        assert_eq!(
+            displayed_rows(&view),
+            vec![
+                "▼ shop: 1 running, 1 exited",
+                "  ▼ db: 1 exited",
+                "shop_db_1",
+                "  ▼ web: 1 running",
+                "shop_web_1",
+                "▼ Other containers: 1 running",
+                "cache",
+            ]
+        );
+        // The selected container stays selected
+        assert_eq!(selected_name(&view), "cache");
+
+        // Operations apply to all the containers of the selected group
+        view.handle_action(Action::Top, docker.clone());
+        assert!(view.get_selected_container().is_none());
+        let mut db = target("ba9876543210", "shop_db_1");
+        db.running = false;
+        assert_eq!(
+            view.handle_action(Action::Restart, docker.clone()),
+            Some(AppCommand::RunOperation(
+                ContainerOperation::Restart,
+                vec![target("0123456789ab", "shop_web_1"), db]
+            ))
+        );
+        view.handle_action(Action::Down, docker.clone());
+        view.handle_action(Action::ToggleMark, docker.clone());
+        assert_eq!(view.marked.len(), 1);
+
+        // Collapse the project
+        view.handle_action(Action::Top, docker.clone());
+        view.handle_action(Action::Details, docker.clone());
+        assert_eq!(
+            displayed_rows(&view),
+            vec![
+                "▶ shop: 1 running, 1 exited",
+                "▼ Other containers: 1 running",
+                "cache",
+            ]
+        );
+        view.handle_action(Action::NextMatch, docker.clone());
+        assert_eq!(selected_name(&view), "cache");
+        view.handle_action(Action::GroupByProject, docker);
+        assert_eq!(view.selected, 2);
+        assert_eq!(selected_name(&view), "cache");
+    }
+
+    #[test]
+    fn uptime_from_status() {
+        assert_eq!(uptime("Up Less than a second"), Some(0));
+        assert_eq!(uptime("Up 5 minutes (Paused)"), Some(300));
+        assert_eq!(uptime("Up About an hour (healthy)"), Some(3600));
+        assert_eq!(uptime("Up 2 days"), Some(2 * 86400));
+        assert_eq!(uptime("Exited (0) 5 minutes ago"), None);
+        assert_eq!(uptime("Created"), None);
+    }
+
+    #[test]
+    fn sorts_the_containers() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_container("0123456789ab", "web", "nginx", "running")
+                .with_container("ba9876543210", "db", "postgres", "exited")
+                .with_container("456789abcdef", "cache", "redis", "paused"),
+        );
+        let mut view = refreshed_view(docker.clone());
+        fn names(view: &ContainerListView) -> Vec<&str> {
+            view.containers.iter().filter_map(container_name).collect()
+        }
+        view.handle_action(Action::Bottom, docker.clone());
+
+        // The first column is the id
+        view.handle_action(Action::Sort, docker.clone());
+        assert_eq!(names(&view), vec!["web", "cache", "db"]);
+        view.handle_action(Action::Sort, docker.clone());
+        assert_eq!(view.sort.column, Some(ContainerColumn::Name));
+        assert_eq!(names(&view), vec!["cache", "db", "web"]);
+        assert_eq!(selected_name(&view), "cache");
+        view.handle_action(Action::ReverseSort, docker.clone());
+        assert_eq!(names(&view), vec!["web", "db", "cache"]);
+        assert_eq!(selected_name(&view), "cache");
+
+        // The order is kept across refreshes
+        docker.container_stop("web").unwrap();
+        let (tx, rx) = unbounded();
+        view.refresh(&Worker::new(docker.clone(), tx));
+        rx.recv().unwrap();
+        view.update();
+        assert_eq!(names(&view), vec!["web", "db", "cache"]);
+
+        // Running and paused containers first, then by state
+        view.sort.column = Some(ContainerColumn::Status);
+        view.sort.descending = false;
+        view.sort.sort(&mut view.containers);
+        assert_eq!(names(&view), vec!["cache", "web", "db"]);
+
+        // The columns that aren't displayed can be sorted by too
+        view.handle_action(Action::Sort, docker.clone());
+        assert_eq!(view.sort.column, Some(ContainerColumn::State));
+        assert!(!view.columns.contains(&ContainerColumn::State));
+        assert_eq!(
+            view.sort.hidden_title(&view.columns).unwrap(),
+            "Sorted by State ▲"
+        );
+
+        view.sort.column = ContainerColumn::ALL.last().cloned();
+        let command = view.handle_action(Action::Sort, docker);
+        assert_eq!(view.sort.column, None);
+        assert_eq!(command, Some(AppCommand::Refresh));
+    }
+
+    fn two_containers() -> ContainerListView {
+        let docker = FakeDocker::new()
+            .with_container("0123456789ab", "web", "nginx", "running")
+            .with_container("ba9876543210", "db", "postgres", "exited");
+        refreshed_view(Arc::new(docker))
+    }
+
+    #[test]
+    fn snapshot_containers() {
+        let mut view = two_containers();
+        view.selected = 1;
+        assert_snapshot("container_list", &render(&view, 110, 20));
+    }
+
+    #[test]
+    fn snapshot_marked_containers() {
+        let mut view = two_containers();
+        view.marked.insert("ba9876543210".to_string());
+        assert_snapshot("container_list_marked", &render(&view, 110, 20));
+        view.mark_pattern = Some("ngi".to_string());
+        assert_snapshot("container_list_mark_prompt", &render(&view, 110, 20));
+    }
+
+    #[test]
+    fn snapshot_filtered_containers() {
+        let docker = Arc::new(FakeDocker::new());
+        let mut view = two_containers();
+        view.handle_action(Action::Filter, docker.clone());
+        type_text(&mut view, &docker, "gre");
+        assert_snapshot("container_list_filter_prompt", &render(&view, 110, 20));
+        type_text(&mut view, &docker, "\n");
+        view.handle_action(Action::ToggleMark, docker);
+        assert_snapshot("container_list_filtered", &render(&view, 110, 20));
+    }
+
+    #[test]
+    fn snapshot_grouped_containers() {
+        let docker = compose_stack();
+        let mut view = refreshed_view(docker.clone());
+        view.handle_action(Action::GroupByProject, docker.clone());
+        view.handle_action(Action::Down, docker);
+        assert_snapshot("container_list_grouped", &render(&view, 110, 20));
+    }
+
+    #[test]
+    fn snapshot_empty_list() {
+        let view = refreshed_view(Arc::new(FakeDocker::new()));
+        assert_snapshot("container_list_empty", &render(&view, 110, 20));
+    }
+
+    #[test]
+    fn snapshot_small_heights() {
+        let view = two_containers();
+        for height in 0..6 {
+            let name = format!("container_list_height_{}", height);
+            assert_snapshot(&name, &render(&view, 110, height));
+        }
+    }
+}
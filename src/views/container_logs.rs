@@ -1,88 +1,228 @@
+use std::cell::Cell;
 use std::sync::Arc;
 
-use termion::event::Key;
+use crossbeam_channel::{unbounded, Receiver};
+use shiplift::LogsOptions;
 use tui::{
+    backend::Backend,
     layout::Rect,
-    style::{Color, Style},
+    style::Style,
     widgets::{Block, Borders, List, Text, Widget},
     Frame,
 };
 
-use app::{AppCommand, ContainerId};
-use docker::DockerExecutor;
-use tty::{InterlacedTty, TtyLine};
-use views::View;
-use Backend;
+use crate::app::{AppCommand, ContainerId};
+use crate::docker::DockerBackend;
+use crate::keys::Action;
+use crate::theme;
+use crate::tty::TtyLine;
+use crate::views::{Draw, View};
+use crate::worker::Worker;
+
+/// Maximum number of lines kept in memory. Older lines are discarded first.
+const MAX_LINES: usize = 10_000;
 
 pub struct ContainerLogsView {
     id: ContainerId,
-    scroll: u16,
-    logs: Option<InterlacedTty>,
+    /// Lines received so far
+    lines: Vec<TtyLine>,
+    /// Receiving end of the log stream, if it has been started
+    rx: Option<Receiver<TtyLine>>,
+    /// Index of the first displayed line when not following the logs
+    scroll: usize,
+    /// Whether to automatically scroll to the end when new lines arrive
+    follow: bool,
+    /// Number of lines that fit in the view, as of the last draw
+    height: Cell<usize>,
 }
 
 impl ContainerLogsView {
     pub fn new(id: ContainerId) -> ContainerLogsView {
         ContainerLogsView {
             id,
+            lines: Vec::new(),
+            rx: None,
             scroll: 0,
-            logs: None,
+            follow: true,
+            height: Cell::new(0),
+        }
+    }
+
+    /// Index of the first line that can be displayed while still filling the view.
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.height.get())
+    }
+
+    /// Index of the first displayed line.
+    fn offset(&self) -> usize {
+        if self.follow {
+            self.max_scroll()
+        } else {
+            self.scroll.min(self.max_scroll())
         }
     }
+
+    fn scroll_up(&mut self, n: usize) {
+        self.scroll = self.offset().saturating_sub(n);
+        self.follow = false;
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        self.scroll = (self.offset() + n).min(self.max_scroll());
+        // Reaching the bottom resumes following the logs
+        self.follow = self.scroll == self.max_scroll();
+    }
 }
 
 impl View for ContainerLogsView {
-    fn handle_input(&mut self, key: Key, _docker: Arc<DockerExecutor>) -> Option<AppCommand> {
-        match key {
-            Key::Up | Key::Char('k') => {
-                if self.scroll > 0 {
-                    self.scroll -= 1;
-                }
+    fn name(&self) -> &'static str {
+        "container_logs"
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        _docker: Arc<dyn DockerBackend>,
+    ) -> Option<AppCommand> {
+        match action {
+            Action::Up => {
+                self.scroll_up(1);
+                Some(AppCommand::NoOp)
+            }
+            Action::Down => {
+                self.scroll_down(1);
+                Some(AppCommand::NoOp)
+            }
+            Action::PageUp => {
+                self.scroll_up(10);
+                Some(AppCommand::NoOp)
+            }
+            Action::PageDown => {
+                self.scroll_down(10);
+                Some(AppCommand::NoOp)
+            }
+            Action::Top => {
+                self.scroll = 0;
+                self.follow = false;
+                Some(AppCommand::NoOp)
+            }
+            Action::Bottom => {
+                self.follow = true;
                 Some(AppCommand::NoOp)
             }
-            Key::Down | Key::Char('j') => {
-                self.scroll += 1;
+            Action::ToggleFollow => {
+                self.scroll = self.offset();
+                self.follow = !self.follow;
                 Some(AppCommand::NoOp)
             }
             _ => None,
         }
     }
 
-    fn refresh(&mut self, docker: Arc<DockerExecutor>) {
-        let logs_reader = docker.container_logs(
-            &self.id.0,
-            &LogsOptions::builder()
-                .follow(false)
+    fn refresh(&mut self, worker: &Worker) {
+        // (Re)start the stream from scratch. Dropping the previous receiver stops the previous
+        // stream.
+        let (tx, rx) = unbounded();
+        let id = self.id.0.clone();
+        worker.spawn(move |docker| {
+            // The logs of containers started with a TTY aren't multiplexed
+            let tty = match docker.container_json(&id) {
+                Ok(details) => details["Config"]["Tty"].as_bool().unwrap_or(false),
+                Err(err) => {
+                    warn!("Failed to inspect container {}: {}", id, err);
+                    false
+                }
+            };
+            let options = LogsOptions::builder()
+                .follow(true)
                 .tail("100")
                 .stdout(true)
                 .stderr(true)
-                .build(),
-        );
-        let tty = InterlacedTty::new(logs_reader);
-        self.logs = Some(tty);
+                .build();
+            docker.container_logs(&id, tty, &options, tx);
+        });
+        self.lines.clear();
+        self.rx = Some(rx);
     }
 
-    fn draw(&self, t: &mut Frame<Backend>, rect: Rect) {
-        let stdout_style = Style::default().bg(Color::Black).fg(Color::White);
-        let stderr_style = Style::default().bg(Color::Black).fg(Color::Red);
+    fn tick(&mut self, _worker: &Worker) {
+        // The logs are streamed, there's nothing to refresh
+    }
+
+    fn update(&mut self) {
+        if let Some(ref rx) = self.rx {
+            self.lines.extend(rx.try_iter());
+        }
+        if self.lines.len() > MAX_LINES {
+            let excess = self.lines.len() - MAX_LINES;
+            self.lines.drain(..excess);
+            self.scroll = self.scroll.saturating_sub(excess);
+        }
+    }
+}
+
+impl<B: Backend> Draw<B> for ContainerLogsView {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
+        let stdout_style = Style::default().bg(theme.background).fg(theme.foreground);
+        let stderr_style = Style::default().bg(theme.background).fg(theme.error);
 
         let style = |l: &TtyLine| match l {
             TtyLine::StdOut(_) => stdout_style,
             TtyLine::StdErr(_) => stderr_style,
         };
+
+        let height = rect.height.saturating_sub(2) as usize; // 2 for border
+        self.height.set(height);
         let formatted_lines = self
-            .logs
-            .as_ref()
-            .map(|t| {
-                t.lines
-                    .iter()
-                    .map(|l| Text::styled(format!("{}", l), style(l)))
-                    .collect()
-            })
-            .unwrap_or_else(|| vec![]);
+            .lines
+            .iter()
+            .skip(self.offset())
+            .take(height)
+            .map(|l| Text::styled(format!("{}", l), style(l)));
 
-        List::new(formatted_lines.into_iter())
-            .block(Block::default().borders(Borders::ALL))
-            .style(Style::default().bg(Color::Black).fg(Color::White))
+        let title = format!(
+            "Logs: {}{}",
+            self.id.0,
+            if self.follow { " [follow]" } else { "" }
+        );
+        List::new(formatted_lines)
+            .block(Block::default().borders(Borders::ALL).title(&title))
+            .style(Style::default().bg(theme.background).fg(theme.foreground))
             .render(t, rect);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::views::snapshot::{assert_snapshot, render};
+
+    fn logs_view() -> ContainerLogsView {
+        let mut view = ContainerLogsView::new(ContainerId("web".to_string()));
+        view.lines = (1..=20)
+            .map(|i| {
+                if i % 5 == 0 {
+                    TtyLine::StdErr(format!("error {}", i))
+                } else {
+                    TtyLine::StdOut(format!("line {}", i))
+                }
+            })
+            .collect();
+        view
+    }
+
+    #[test]
+    fn snapshot_follow() {
+        assert_snapshot("container_logs_follow", &render(&logs_view(), 40, 8));
+    }
+
+    #[test]
+    fn snapshot_scrolled() {
+        let mut view = logs_view();
+        // The view needs to have been drawn to know how many lines fit
+        render(&view, 40, 8);
+        view.scroll_up(3);
+        assert_snapshot("container_logs_scrolled", &render(&view, 40, 8));
+    }
+}
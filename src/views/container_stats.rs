@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use failure::Error;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Row, Table, Widget},
+    Frame,
+};
+
+use crate::app::{AppCommand, ContainerId};
+use crate::docker::{DockerBackend, ListOptions};
+use crate::events::DockerEvent;
+use crate::keys::Action;
+use crate::stats::{ContainerStats, Sample, Stats};
+use crate::theme;
+use crate::views::{container_name, Draw, View, ViewType};
+use crate::worker::{Pending, Worker};
+
+/// Live resource usage of all the running containers, similar to `docker stats`.
+pub struct ContainerStatsView {
+    /// The running containers, sorted by name
+    containers: Vec<RunningContainer>,
+    /// Index of the currently selected container
+    selected: usize,
+    /// Ids of the containers whose stats are being streamed
+    streaming: HashSet<String>,
+    tx: Sender<(String, Option<Stats>)>,
+    rx: Receiver<(String, Option<Stats>)>,
+    pending: Pending<Result<Refreshed, Error>>,
+}
+
+/// Ids and names of the running containers, along with the ids of the containers whose stats
+/// started being streamed during the refresh.
+type Refreshed = (Vec<(String, String)>, Vec<String>);
+
+/// A running container, and its stats.
+struct RunningContainer {
+    id: String,
+    name: String,
+    stats: ContainerStats,
+}
+
+impl ContainerStatsView {
+    pub fn new() -> ContainerStatsView {
+        let (tx, rx) = unbounded();
+        ContainerStatsView {
+            containers: Vec::new(),
+            selected: 0,
+            streaming: HashSet::new(),
+            tx,
+            rx,
+            pending: Pending::none(),
+        }
+    }
+}
+
+impl View for ContainerStatsView {
+    fn name(&self) -> &'static str {
+        "container_stats"
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        _docker: Arc<dyn DockerBackend>,
+    ) -> Option<AppCommand> {
+        let max_index = self.containers.len().saturating_sub(1);
+        match action {
+            Action::Down => {
+                self.selected = (self.selected + 1).min(max_index);
+                Some(AppCommand::NoOp)
+            }
+            Action::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                Some(AppCommand::NoOp)
+            }
+            Action::Graphs => self.containers.get(self.selected).map(|c| {
+                let id = ContainerId(c.name.clone());
+                AppCommand::SwitchToView(ViewType::ContainerGraphs(id))
+            }),
+            _ => None,
+        }
+    }
+
+    fn handle_docker_event(&mut self, event: &DockerEvent) -> bool {
+        event.typ == "container" && (event.action == "start" || event.action == "die")
+    }
+
+    fn refresh(&mut self, worker: &Worker) {
+        // Otherwise we could start streaming the stats of the new containers twice
+        if self.pending.is_waiting() {
+            return;
+        }
+
+        let streaming = self.streaming.clone();
+        let tx = self.tx.clone();
+        self.pending = worker.run(move |docker| {
+            let options = ListOptions::default();
+            let containers: Vec<(String, String)> = docker
+                .containers(&options)?
+                .iter()
+                .map(|c| (c.id.clone(), container_name(c).unwrap_or(&c.id).to_string()))
+                .collect();
+            // Start streaming the stats of the new containers, and of the ones whose stream has
+            // ended in the meantime (i.e. they have been restarted)
+            let started: Vec<String> = containers
+                .iter()
+                .map(|(id, _)| id)
+                .filter(|id| !streaming.contains(*id))
+                .cloned()
+                .collect();
+            for id in &started {
+                docker.container_stats(id, tx.clone());
+            }
+            Ok((containers, started))
+        });
+    }
+
+    fn update(&mut self) {
+        match self.pending.take() {
+            Some(Ok((containers, started))) => {
+                self.streaming.extend(started);
+                // Forget about the containers that are no longer running
+                let mut previous: HashMap<_, _> =
+                    self.containers.drain(..).map(|c| (c.id, c.stats)).collect();
+                self.containers = containers
+                    .into_iter()
+                    .map(|(id, name)| RunningContainer {
+                        stats: previous.remove(&id).unwrap_or_default(),
+                        id,
+                        name,
+                    })
+                    .collect();
+                self.containers.sort_by(|a, b| a.name.cmp(&b.name));
+                self.selected = self.selected.min(self.containers.len().saturating_sub(1));
+            }
+            Some(Err(err)) => error!("Failed to list containers: {}", err),
+            None => {}
+        }
+
+        for (id, stats) in self.rx.try_iter() {
+            match stats {
+                // Ignore late samples of containers that have been removed
+                Some(stats) => {
+                    if let Some(c) = self.containers.iter_mut().find(|c| c.id == id) {
+                        c.stats.push(Sample::from(&stats));
+                    }
+                }
+                None => {
+                    self.streaming.remove(&id);
+                }
+            }
+        }
+    }
+}
+
+impl<B: Backend> Draw<B> for ContainerStatsView {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
+        let selected_style = Style::default().fg(theme.selected).modifier(Modifier::BOLD);
+        let normal_style = Style::default().fg(theme.foreground);
+        let header = [
+            "Name",
+            "CPU %",
+            "Mem usage / limit",
+            "Mem %",
+            "Net I/O",
+            "Block I/O",
+        ];
+        let height = rect.height.saturating_sub(4) as usize; // 2 for border + 2 for header
+        let offset = if self.selected >= height {
+            self.selected - height + 1
+        } else {
+            0
+        };
+        let rows: Vec<_> = self
+            .containers
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let s = &c.stats.current;
+                let data: Vec<String> = vec![
+                    c.name.clone(),
+                    format!("{:.2}%", c.stats.cpu_percent),
+                    format!(
+                        "{} / {}",
+                        bytesize::to_string(s.mem_usage, true),
+                        bytesize::to_string(s.mem_limit, true)
+                    ),
+                    format!("{:.2}%", s.mem_percent()),
+                    format!(
+                        "{} / {}",
+                        bytesize::to_string(s.net_rx, false),
+                        bytesize::to_string(s.net_tx, false)
+                    ),
+                    format!(
+                        "{} / {}",
+                        bytesize::to_string(s.blk_read, false),
+                        bytesize::to_string(s.blk_write, false)
+                    ),
+                ];
+                if i == self.selected {
+                    Row::StyledData(data.into_iter(), selected_style)
+                } else {
+                    Row::StyledData(data.into_iter(), normal_style)
+                }
+            })
+            .skip(offset)
+            .collect();
+
+        Table::new(header.iter(), rows.into_iter())
+            .block(Block::default().borders(Borders::ALL).title("Stats"))
+            .widths(&[25, 10, 25, 10, 25, 25])
+            .render(t, rect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::AppEvent;
+    use crate::docker::fake::FakeDocker;
+    use crate::views::snapshot::{assert_snapshot, render};
+
+    fn running(id: &str, name: &str) -> RunningContainer {
+        RunningContainer {
+            id: id.to_string(),
+            name: name.to_string(),
+            stats: ContainerStats::default(),
+        }
+    }
+
+    /// Refreshes the view, and waits for the refresh to complete.
+    fn refresh(view: &mut ContainerStatsView, docker: &Arc<FakeDocker>) {
+        let (tx, rx) = unbounded();
+        view.refresh(&Worker::new(docker.clone(), tx));
+        match rx.recv() {
+            Ok(AppEvent::Refreshed) => view.update(),
+            _ => panic!("The refresh didn't complete"),
+        }
+    }
+
+    #[test]
+    fn restarts_the_streams_that_ended() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_container("0123456789ab", "web", "nginx", "running")
+                .with_container("ba9876543210", "db", "postgres", "running"),
+        );
+        let mut view = ContainerStatsView::new();
+        refresh(&mut view, &docker);
+        let names: Vec<_> = view.containers.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["db", "web"]);
+
+        // The streams are only started once
+        refresh(&mut view, &docker);
+        let streams = |docker: &FakeDocker| {
+            docker
+                .calls()
+                .into_iter()
+                .filter(|call| call.starts_with("container_stats"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            streams(&docker),
+            vec![
+                "container_stats 0123456789ab",
+                "container_stats ba9876543210"
+            ]
+        );
+
+        // The container has been restarted
+        view.tx.send(("0123456789ab".to_string(), None)).unwrap();
+        view.update();
+        refresh(&mut view, &docker);
+        assert_eq!(
+            streams(&docker).last().unwrap(),
+            "container_stats 0123456789ab"
+        );
+        assert_eq!(streams(&docker).len(), 3);
+    }
+
+    #[test]
+    fn snapshot_stats() {
+        let mut view = ContainerStatsView::new();
+        assert_snapshot("container_stats_empty", &render(&view, 120, 6));
+        view.containers = vec![
+            running("ba9876543210", "db"),
+            running("0123456789ab", "web"),
+        ];
+        assert_snapshot("container_stats", &render(&view, 120, 6));
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Row, Table, Widget},
+    Frame,
+};
+
+use crate::app::AppCommand;
+use crate::context::Context;
+use crate::docker::DockerBackend;
+use crate::keys::Action;
+use crate::theme;
+use crate::views::{Draw, View};
+
+/// Lists the available contexts, and lets the user switch to another one.
+pub struct ContextsView {
+    contexts: Vec<Context>,
+    /// Name of the active context
+    active: String,
+    selected: usize,
+}
+
+impl ContextsView {
+    pub fn new(contexts: Vec<Context>, active: String) -> ContextsView {
+        let selected = contexts.iter().position(|c| c.name == active).unwrap_or(0);
+        ContextsView {
+            contexts,
+            active,
+            selected,
+        }
+    }
+}
+
+impl View for ContextsView {
+    fn name(&self) -> &'static str {
+        "contexts"
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        _docker: Arc<dyn DockerBackend>,
+    ) -> Option<AppCommand> {
+        let max_index = self.contexts.len().saturating_sub(1);
+        match action {
+            Action::Down => {
+                self.selected = (self.selected + 1).min(max_index);
+                Some(AppCommand::NoOp)
+            }
+            Action::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                Some(AppCommand::NoOp)
+            }
+            Action::SwitchContext => self
+                .contexts
+                .get(self.selected)
+                .map(|c| AppCommand::SwitchContext(c.name.clone())),
+            _ => None,
+        }
+    }
+}
+
+impl<B: Backend> Draw<B> for ContextsView {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
+        let selected_style = Style::default().fg(theme.selected).modifier(Modifier::BOLD);
+        let active_style = Style::default().fg(theme.running);
+        let normal_style = Style::default().fg(theme.foreground);
+        let header = ["", "Name", "Description", "Docker endpoint"];
+        let height = rect.height.saturating_sub(4) as usize; // 2 for border + 2 for header
+        let offset = if self.selected >= height {
+            self.selected - height + 1
+        } else {
+            0
+        };
+        let rows: Vec<_> = self
+            .contexts
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let active = c.name == self.active;
+                let data = vec![
+                    if active { "*" } else { "" }.to_string(),
+                    c.name.clone(),
+                    c.description.clone(),
+                    c.config.host.clone(),
+                ];
+                if i == self.selected {
+                    Row::StyledData(data.into_iter(), selected_style)
+                } else if active {
+                    Row::StyledData(data.into_iter(), active_style)
+                } else {
+                    Row::StyledData(data.into_iter(), normal_style)
+                }
+            })
+            .skip(offset)
+            .collect();
+
+        Table::new(header.iter(), rows.into_iter())
+            .block(Block::default().borders(Borders::ALL).title("Contexts"))
+            .widths(&[1, 20, 40, 40])
+            .render(t, rect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::ConnectionConfig;
+    use crate::views::snapshot::{assert_snapshot, render};
+
+    fn contexts() -> Vec<Context> {
+        ["default", "ci", "staging"]
+            .iter()
+            .map(|name| Context {
+                name: name.to_string(),
+                description: format!("The {} daemon", name),
+                config: ConnectionConfig {
+                    host: format!("tcp://{}:2376", name),
+                    cert_path: None,
+                    tls_verify: false,
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn switches_to_the_selected_context() {
+        let docker: Arc<dyn DockerBackend> = Arc::new(crate::docker::fake::FakeDocker::new());
+        let mut view = ContextsView::new(contexts(), "ci".to_string());
+        assert_eq!(view.selected, 1);
+        view.handle_action(Action::Down, docker.clone());
+        assert_eq!(
+            view.handle_action(Action::SwitchContext, docker),
+            Some(AppCommand::SwitchContext("staging".to_string()))
+        );
+    }
+
+    #[test]
+    fn snapshot_contexts() {
+        let view = ContextsView::new(contexts(), "ci".to_string());
+        assert_snapshot("contexts", &render(&view, 110, 8));
+    }
+}
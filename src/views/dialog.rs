@@ -0,0 +1,246 @@
+use termion::event::Key;
+use tui::{
+    backend::Backend,
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Text, Widget},
+    Frame,
+};
+
+use crate::keys::{key_name, Action, Keymap};
+use crate::theme;
+use crate::views::Draw;
+
+/// Scope of the actions of the dialogs in the keymap
+const SCOPE: &str = "dialog";
+
+/// An option of a dialog, toggled with a key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DialogOption {
+    pub key: char,
+    pub label: &'static str,
+    pub checked: bool,
+}
+
+/// How the user dismissed a dialog.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Answer {
+    Confirmed,
+    Cancelled,
+}
+
+/// A modal dialog asking the user to confirm an operation, which lists what the operation affects
+/// and lets the user toggle some options. It is drawn above the current view, and captures all the
+/// key presses until it is dismissed.
+pub struct Dialog {
+    title: String,
+    /// What the operation affects, one per line
+    items: Vec<String>,
+    options: Vec<DialogOption>,
+    /// Reminder of the keys confirming and cancelling the operation
+    hint: String,
+}
+
+impl Dialog {
+    /// Creates a dialog, whose hint shows the keys confirming and cancelling the operation in the
+    /// given keymap.
+    pub fn new(title: String, items: Vec<String>, keymap: &Keymap) -> Dialog {
+        let first_key = |action| {
+            keymap
+                .keys(SCOPE, action)
+                .first()
+                .map_or_else(|| "(unbound)".to_string(), |&k| key_name(k))
+        };
+        Dialog {
+            title,
+            items,
+            options: Vec::new(),
+            hint: format!(
+                "{}: confirm  {}: cancel",
+                first_key(Action::Confirm),
+                first_key(Action::Cancel)
+            ),
+        }
+    }
+
+    /// Adds an option, toggled by pressing `key`.
+    pub fn with_option(mut self, key: char, label: &'static str, checked: bool) -> Dialog {
+        self.options.push(DialogOption {
+            key,
+            label,
+            checked,
+        });
+        self
+    }
+
+    /// Returns `true` if the option toggled by `key` is checked.
+    pub fn is_checked(&self, key: char) -> bool {
+        self.options.iter().any(|o| o.key == key && o.checked)
+    }
+
+    /// Handles a key press. Returns the answer of the user once the dialog has been dismissed.
+    pub fn handle_input(&mut self, key: Key, keymap: &Keymap) -> Option<Answer> {
+        // Only the actions of the dialog apply, the global ones are ignored
+        match keymap.action(SCOPE, key) {
+            Some(Action::Confirm) => return Some(Answer::Confirmed),
+            Some(Action::Cancel) => return Some(Answer::Cancelled),
+            _ => {}
+        }
+        if let Key::Char(c) = key {
+            if let Some(option) = self.options.iter_mut().find(|o| o.key == c) {
+                option.checked = !option.checked;
+            }
+        }
+        None
+    }
+
+    /// Returns the lines of the dialog, listing at most `max_items` items.
+    fn lines(&self, max_items: usize) -> Vec<(String, Style)> {
+        let theme = theme::current();
+        let normal = Style::default().fg(theme.foreground);
+        let mut lines = Vec::new();
+        if self.items.len() > max_items {
+            let shown = max_items.saturating_sub(1);
+            for item in &self.items[..shown] {
+                lines.push((item.clone(), normal));
+            }
+            lines.push((
+                format!("... and {} more", self.items.len() - shown),
+                Style::default().fg(theme.secondary),
+            ));
+        } else {
+            for item in &self.items {
+                lines.push((item.clone(), normal));
+            }
+        }
+        if !self.options.is_empty() {
+            lines.push((String::new(), normal));
+            for option in &self.options {
+                let check = if option.checked { "x" } else { " " };
+                let line = format!("[{}] {}: {}", check, option.key, option.label);
+                lines.push((line, normal));
+            }
+        }
+        lines.push((String::new(), normal));
+        lines.push((self.hint.clone(), Style::default().fg(theme.secondary)));
+        lines
+    }
+}
+
+impl<B: Backend> Draw<B> for Dialog {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
+        // Everything but the items, and the borders
+        let fixed = if self.options.is_empty() {
+            2
+        } else {
+            self.options.len() + 3
+        } + 2;
+        let max_items = (rect.height as usize).saturating_sub(fixed).max(1);
+        let lines = self.lines(max_items);
+
+        let content_width = lines
+            .iter()
+            .map(|(line, _)| line.chars().count())
+            .chain(Some(self.title.chars().count()))
+            .max()
+            .unwrap_or(0);
+        let width = (content_width as u16 + 4).max(40).min(rect.width);
+        let height = (lines.len() as u16 + 2).min(rect.height);
+        let area = Rect::new(
+            rect.x + (rect.width - width) / 2,
+            rect.y + (rect.height - height) / 2,
+            width,
+            height,
+        );
+
+        let text: Vec<_> = lines
+            .into_iter()
+            .map(|(line, style)| Text::styled(format!(" {}\n", line), style))
+            .collect();
+        let border = Style::default().fg(theme.warning);
+        Clear.render(t, area);
+        Paragraph::new(text.iter())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border)
+                    .title(&self.title)
+                    .title_style(border.modifier(Modifier::BOLD)),
+            )
+            .raw(true)
+            .render(t, area);
+    }
+}
+
+/// Erases what has already been drawn in an area.
+struct Clear;
+
+impl Widget for Clear {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                buf.get_mut(x, y).reset();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::find_binding;
+    use crate::views::snapshot::{assert_snapshot, render};
+
+    fn dialog(items: usize) -> Dialog {
+        let items = (0..items).map(|i| format!("container-{}", i)).collect();
+        Dialog::new(
+            "Really delete 2 containers?".to_string(),
+            items,
+            &Keymap::default(),
+        )
+        .with_option('f', "Force, kill the running containers first", false)
+        .with_option('v', "Remove the anonymous volumes", true)
+    }
+
+    #[test]
+    fn toggles_options_until_dismissed() {
+        let keymap = Keymap::default();
+        let mut dialog = dialog(2);
+        assert_eq!(dialog.handle_input(Key::Char('f'), &keymap), None);
+        assert_eq!(dialog.handle_input(Key::Char('v'), &keymap), None);
+        // Global actions are ignored
+        assert_eq!(dialog.handle_input(Key::Char('q'), &keymap), None);
+        assert!(dialog.is_checked('f'));
+        assert!(!dialog.is_checked('v'));
+        assert_eq!(
+            dialog.handle_input(Key::Char('\n'), &keymap),
+            Some(Answer::Confirmed)
+        );
+        assert_eq!(
+            dialog.handle_input(Key::Esc, &keymap),
+            Some(Answer::Cancelled)
+        );
+    }
+
+    #[test]
+    fn remapped_keys() {
+        let keymap =
+            Keymap::new(&[(find_binding("dialog", "confirm").unwrap(), vec![Key::F(2)])]).unwrap();
+        let mut dialog = Dialog::new("Really stop web?".to_string(), vec![], &keymap);
+        assert_eq!(dialog.hint, "f2: confirm  n: cancel");
+        assert_eq!(dialog.handle_input(Key::Char('y'), &keymap), None);
+        assert_eq!(
+            dialog.handle_input(Key::F(2), &keymap),
+            Some(Answer::Confirmed)
+        );
+    }
+
+    #[test]
+    fn snapshot_dialog() {
+        assert_snapshot("dialog", &render(&dialog(2), 60, 14));
+        // The items that don't fit are summarized
+        assert_snapshot("dialog_truncated", &render(&dialog(20), 60, 12));
+    }
+}
@@ -0,0 +1,260 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use termion::event::Key;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Borders, Row, Table, Widget},
+    Frame,
+};
+
+use crate::app::AppCommand;
+use crate::docker::DockerBackend;
+use crate::events::{DockerEvent, MAX_EVENTS};
+use crate::keys::Action;
+use crate::theme;
+use crate::views::{Draw, View};
+
+/// Timeline of the events reported by the Docker daemon, oldest first.
+pub struct EventsView {
+    events: VecDeque<DockerEvent>,
+    /// Only the events matching this text are displayed
+    filter: String,
+    /// Whether the filter is being typed
+    editing_filter: bool,
+    /// Index of the first displayed event (among the displayed ones) when not following
+    scroll: usize,
+    /// Whether to automatically scroll to the end when new events arrive
+    follow: bool,
+    /// Number of events that fit in the view, as of the last draw
+    height: Cell<usize>,
+}
+
+impl EventsView {
+    /// Creates a new view, initially showing the given events.
+    pub fn new(events: VecDeque<DockerEvent>) -> EventsView {
+        EventsView {
+            events,
+            filter: String::new(),
+            editing_filter: false,
+            scroll: 0,
+            follow: true,
+            height: Cell::new(0),
+        }
+    }
+
+    /// Returns `true` if the event should be displayed with the current filter.
+    fn matches(&self, event: &DockerEvent) -> bool {
+        let filter = self.filter.to_lowercase();
+        let image = event.attributes.get("image").map(String::as_str);
+        [
+            Some(event.typ.as_str()),
+            Some(event.action.as_str()),
+            Some(event.actor()),
+            Some(event.actor_id.as_str()),
+            image,
+        ]
+        .iter()
+        .filter_map(|field| *field)
+        .any(|field| field.to_lowercase().contains(&filter))
+    }
+
+    fn visible_events(&self) -> Vec<&DockerEvent> {
+        self.events.iter().filter(|e| self.matches(e)).collect()
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.visible_events()
+            .len()
+            .saturating_sub(self.height.get())
+    }
+
+    /// Index of the first displayed event.
+    fn offset(&self) -> usize {
+        if self.follow {
+            self.max_scroll()
+        } else {
+            self.scroll.min(self.max_scroll())
+        }
+    }
+
+    fn scroll_up(&mut self, n: usize) {
+        self.scroll = self.offset().saturating_sub(n);
+        self.follow = false;
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        self.scroll = (self.offset() + n).min(self.max_scroll());
+        // Reaching the bottom resumes following the events
+        self.follow = self.scroll == self.max_scroll();
+    }
+
+    fn handle_filter_input(&mut self, key: Key) -> Option<AppCommand> {
+        match key {
+            Key::Char('\n') => self.editing_filter = false,
+            Key::Esc => {
+                self.filter.clear();
+                self.editing_filter = false;
+            }
+            Key::Backspace => {
+                self.filter.pop();
+            }
+            Key::Char(c) => self.filter.push(c),
+            _ => {}
+        }
+        Some(AppCommand::NoOp)
+    }
+}
+
+impl View for EventsView {
+    fn name(&self) -> &'static str {
+        "events"
+    }
+
+    fn handle_input(&mut self, key: Key, _docker: Arc<dyn DockerBackend>) -> Option<AppCommand> {
+        if self.editing_filter {
+            self.handle_filter_input(key)
+        } else {
+            None
+        }
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        _docker: Arc<dyn DockerBackend>,
+    ) -> Option<AppCommand> {
+        match action {
+            Action::Up => {
+                self.scroll_up(1);
+                Some(AppCommand::NoOp)
+            }
+            Action::Down => {
+                self.scroll_down(1);
+                Some(AppCommand::NoOp)
+            }
+            Action::PageUp => {
+                self.scroll_up(10);
+                Some(AppCommand::NoOp)
+            }
+            Action::PageDown => {
+                self.scroll_down(10);
+                Some(AppCommand::NoOp)
+            }
+            Action::Top => {
+                self.scroll = 0;
+                self.follow = false;
+                Some(AppCommand::NoOp)
+            }
+            Action::Bottom => {
+                self.follow = true;
+                Some(AppCommand::NoOp)
+            }
+            Action::Filter => {
+                self.editing_filter = true;
+                Some(AppCommand::NoOp)
+            }
+            _ => None,
+        }
+    }
+
+    fn captures_input(&self) -> bool {
+        self.editing_filter
+    }
+
+    fn handle_docker_event(&mut self, event: &DockerEvent) -> bool {
+        self.events.push_back(event.clone());
+        if self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+            self.scroll = self.scroll.saturating_sub(1);
+        }
+        false
+    }
+}
+
+impl<B: Backend> Draw<B> for EventsView {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
+        let height = rect.height.saturating_sub(4) as usize; // 2 for border + 2 for header
+        self.height.set(height);
+        let header = ["Time", "Type", "Action", "Actor", "Image"];
+        let rows: Vec<_> = self
+            .visible_events()
+            .into_iter()
+            .skip(self.offset())
+            .take(height)
+            .map(|e| {
+                let data = vec![
+                    format_time(e.time),
+                    e.typ.clone(),
+                    e.action.clone(),
+                    e.actor().to_string(),
+                    e.attributes.get("image").cloned().unwrap_or_default(),
+                ];
+                let style = match e.action.as_str() {
+                    "start" | "unpause" | "pull" => Style::default().fg(theme.running),
+                    "die" | "kill" | "oom" | "destroy" | "delete" => {
+                        Style::default().fg(theme.error)
+                    }
+                    _ => Style::default().fg(theme.foreground),
+                };
+                Row::StyledData(data.into_iter(), style)
+            })
+            .collect();
+
+        let mut title = String::from("Events");
+        if self.editing_filter || !self.filter.is_empty() {
+            title.push_str(&format!(" - filter: {}", self.filter));
+            if self.editing_filter {
+                title.push('_');
+            }
+        }
+        if self.follow {
+            title.push_str(" [follow]");
+        }
+        Table::new(header.iter(), rows.into_iter())
+            .block(Block::default().borders(Borders::ALL).title(&title))
+            .widths(&[20, 10, 20, 30, 30])
+            .render(t, rect);
+    }
+}
+
+/// Formats the given time as `YYYY-MM-DD HH:MM:SS` in the local timezone.
+fn format_time(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0) as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return String::new();
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::views::snapshot::{assert_snapshot, render};
+
+    #[test]
+    fn snapshot_empty_timeline() {
+        let mut view = EventsView::new(VecDeque::new());
+        assert_snapshot("events_empty", &render(&view, 100, 6));
+        view.editing_filter = true;
+        view.filter = "nginx".to_string();
+        assert_snapshot("events_filter", &render(&view, 100, 6));
+    }
+}
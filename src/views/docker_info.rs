@@ -1,44 +1,63 @@
-use std::sync::Arc;
-
+use failure::Error;
 use shiplift::rep::Info;
-use termion::event::Key;
 use tui::{
+    backend::Backend,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     widgets::{Block, Borders, Paragraph, Text, Widget},
     Frame,
 };
 
-use crate::app::AppCommand;
-use crate::docker::DockerExecutor;
-use crate::views::View;
-use crate::Backend;
+use crate::events::DockerEvent;
+use crate::theme;
+use crate::views::{Draw, View};
+use crate::worker::{Pending, Worker};
 
 pub struct DockerInfo {
     info: Option<Info>,
+    pending: Pending<Result<Info, Error>>,
 }
 
 impl DockerInfo {
     pub fn new() -> DockerInfo {
-        DockerInfo { info: None }
+        DockerInfo {
+            info: None,
+            pending: Pending::none(),
+        }
     }
 }
 
 impl View for DockerInfo {
-    fn handle_input(&mut self, _key: Key, _docker: Arc<DockerExecutor>) -> Option<AppCommand> {
-        None
+    fn name(&self) -> &'static str {
+        "info"
+    }
+
+    fn handle_docker_event(&mut self, event: &DockerEvent) -> bool {
+        (event.typ == "container" || event.typ == "image") && event.changes_state()
     }
 
-    fn refresh(&mut self, docker: Arc<DockerExecutor>) {
-        self.info = docker.info().ok();
+    fn refresh(&mut self, worker: &Worker) {
+        self.pending = worker.run(|docker| docker.info());
     }
 
-    fn draw(&self, t: &mut Frame<Backend>, rect: Rect) {
+    fn update(&mut self) {
+        // Keep the last known info if the daemon is unreachable
+        match self.pending.take() {
+            Some(Ok(info)) => self.info = Some(info),
+            Some(Err(err)) => error!("Failed to get system info: {}", err),
+            None => {}
+        }
+    }
+}
+
+impl<B: Backend> Draw<B> for DockerInfo {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
         let text = if let Some(ref info) = self.info {
             vec![
                 Text::styled(
                     "Host details\n",
-                    Style::default().modifier(Modifier::BOLD).fg(Color::Blue),
+                    Style::default().modifier(Modifier::BOLD).fg(theme.title),
                 ),
                 Text::styled(format!("Hostname:       {}\n", info.name), Style::default()),
                 Text::styled(
@@ -60,7 +79,7 @@ impl View for DockerInfo {
                 Text::raw("\n"),
                 Text::styled(
                     "Engine details\n",
-                    Style::default().modifier(Modifier::BOLD).fg(Color::Blue),
+                    Style::default().modifier(Modifier::BOLD).fg(theme.title),
                 ),
                 Text::styled(
                     format!("Root Directory:       {}\n", info.docker_root_dir),
@@ -85,3 +104,23 @@ impl View for DockerInfo {
             .render(t, rect);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::{fake::FakeDocker, DockerBackend};
+    use crate::views::snapshot::{assert_snapshot, render};
+
+    #[test]
+    fn snapshot_info() {
+        let docker = FakeDocker::new().with_container("0123456789ab", "web", "nginx", "running");
+        let mut view = DockerInfo::new();
+        view.info = Some(docker.info().unwrap());
+        assert_snapshot("docker_info", &render(&view, 80, 20));
+    }
+
+    #[test]
+    fn snapshot_no_info() {
+        assert_snapshot("docker_info_empty", &render(&DockerInfo::new(), 80, 20));
+    }
+}
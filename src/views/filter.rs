@@ -0,0 +1,389 @@
+use termion::event::Key;
+use tui::{buffer::Buffer, layout::Rect, style::Modifier, widgets::Widget};
+
+use crate::theme;
+use crate::views::Column;
+
+/// The columns of a table whose rows can be filtered.
+pub trait Searchable: Column {
+    /// The columns matched against the filter, whether they are displayed or not
+    const SEARCHED: &'static [Self];
+
+    /// Returns the labels of the given row, which are matched as `key=value`.
+    fn labels(row: &Self::Row) -> Vec<(&str, &str)>;
+}
+
+/// How the text of the filter is matched against the values of the rows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterMode {
+    /// The value contains the text
+    Substring,
+    /// The value contains the characters of the text, in order
+    Fuzzy,
+}
+
+/// A row matching the filter.
+#[derive(Debug, PartialEq)]
+pub struct RowMatch<C> {
+    /// Index of the row in the table
+    pub index: usize,
+    /// Positions of the matched characters in the values of the columns
+    highlights: Vec<(C, Vec<usize>)>,
+}
+
+impl<C: Column> RowMatch<C> {
+    /// Returns the positions of the matched characters in the value of the given column.
+    pub fn highlights(&self, column: C) -> &[usize] {
+        self.highlights
+            .iter()
+            .find(|(c, _)| *c == column)
+            .map_or(&[], |(_, positions)| positions.as_slice())
+    }
+}
+
+/// The filter bar of a table, which narrows its rows as the user types. A text of the form
+/// `key=value` matches the rows with a label `key` whose value contains `value`.
+#[derive(Debug)]
+pub struct FilterBar {
+    text: String,
+    mode: FilterMode,
+    /// Whether the text is being typed
+    editing: bool,
+}
+
+impl FilterBar {
+    pub fn new() -> FilterBar {
+        FilterBar {
+            text: String::new(),
+            mode: FilterMode::Substring,
+            editing: false,
+        }
+    }
+
+    /// Returns `true` while the text is being typed.
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    /// Returns `true` if some rows may be hidden.
+    pub fn is_active(&self) -> bool {
+        !self.text.is_empty()
+    }
+
+    /// Opens the prompt, to edit the current text.
+    pub fn edit(&mut self) {
+        self.editing = true;
+    }
+
+    /// Removes the filter.
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.editing = false;
+    }
+
+    /// Handles a key press while the text is being typed: `Enter` closes the prompt, `Esc`
+    /// removes the filter, and `Tab` switches between substring and fuzzy matching.
+    pub fn handle_input(&mut self, key: Key) {
+        match key {
+            Key::Char('\n') => self.editing = false,
+            Key::Esc => self.clear(),
+            Key::Char('\t') => {
+                self.mode = match self.mode {
+                    FilterMode::Substring => FilterMode::Fuzzy,
+                    FilterMode::Fuzzy => FilterMode::Substring,
+                }
+            }
+            Key::Backspace => {
+                self.text.pop();
+            }
+            Key::Char(c) => self.text.push(c),
+            _ => {}
+        }
+    }
+
+    /// Returns the title of the table describing the filter, e.g. `Filter [fuzzy]: web`, or
+    /// `None` if there is no filter.
+    pub fn title(&self) -> Option<String> {
+        if !self.editing && self.text.is_empty() {
+            return None;
+        }
+        let mode = if self.label().is_some() {
+            "label"
+        } else {
+            match self.mode {
+                FilterMode::Substring => "substring",
+                FilterMode::Fuzzy => "fuzzy",
+            }
+        };
+        let cursor = if self.editing { "_" } else { "" };
+        Some(format!("Filter [{}]: {}{}", mode, self.text, cursor))
+    }
+
+    /// Returns the key and value of the label to match, if the text is of the form `key=value`.
+    fn label(&self) -> Option<(&str, &str)> {
+        let eq = self.text.find('=')?;
+        Some((self.text[..eq].trim(), &self.text[eq + 1..]))
+    }
+
+    /// Returns the rows matching the filter, in order.
+    pub fn apply<C: Searchable>(&self, rows: &[C::Row]) -> Vec<RowMatch<C>> {
+        rows.iter()
+            .enumerate()
+            .filter_map(|(index, row)| {
+                self.matches(row)
+                    .map(|highlights| RowMatch { index, highlights })
+            })
+            .collect()
+    }
+
+    /// Returns the positions of the matched characters in each searched column, or `None` if the
+    /// row doesn't match.
+    fn matches<C: Searchable>(&self, row: &C::Row) -> Option<Vec<(C, Vec<usize>)>> {
+        if self.text.is_empty() {
+            return Some(Vec::new());
+        }
+        if let Some((key, value)) = self.label() {
+            let value = value.to_lowercase();
+            let found = C::labels(row).into_iter().any(|(k, v)| {
+                (key.is_empty() || k.eq_ignore_ascii_case(key)) && v.to_lowercase().contains(&value)
+            });
+            return if found { Some(Vec::new()) } else { None };
+        }
+
+        let highlights: Vec<_> = C::SEARCHED
+            .iter()
+            .filter_map(|&column| self.find(&column.value(row)).map(|p| (column, p)))
+            .collect();
+        let in_labels = C::labels(row)
+            .into_iter()
+            .any(|(k, v)| self.find(&format!("{}={}", k, v)).is_some());
+        if highlights.is_empty() && !in_labels {
+            None
+        } else {
+            Some(highlights)
+        }
+    }
+
+    /// Returns the positions of the characters of `value` matching the text, if it matches.
+    fn find(&self, value: &str) -> Option<Vec<usize>> {
+        let same = |a: char, b: char| a.to_lowercase().eq(b.to_lowercase());
+        let value: Vec<char> = value.chars().collect();
+        let text: Vec<char> = self.text.chars().collect();
+        match self.mode {
+            FilterMode::Substring => {
+                if text.len() > value.len() {
+                    return None;
+                }
+                (0..=value.len() - text.len())
+                    .find(|&start| text.iter().zip(&value[start..]).all(|(&t, &v)| same(t, v)))
+                    .map(|start| (start..start + text.len()).collect())
+            }
+            FilterMode::Fuzzy => {
+                let mut positions = Vec::new();
+                let mut remaining = text.iter().peekable();
+                for (i, &v) in value.iter().enumerate() {
+                    match remaining.peek() {
+                        Some(&&t) if same(t, v) => {
+                            positions.push(i);
+                            remaining.next();
+                        }
+                        Some(_) => {}
+                        None => break,
+                    }
+                }
+                if remaining.peek().is_none() {
+                    Some(positions)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Highlights the matched characters of the rows displayed in a table, drawn in the same area
+/// with the given column widths. Rows that aren't filtered (e.g. headers between the rows) are
+/// `None`. `offset` is the index of the first displayed row, and `skip` the number of leading
+/// columns that aren't searched (e.g. the marks of the container list).
+pub struct Highlights<'a, C> {
+    pub rows: &'a [Option<&'a RowMatch<C>>],
+    pub offset: usize,
+    pub skip: usize,
+    pub columns: &'a [C],
+    pub widths: &'a [u16],
+}
+
+impl<'a, C: Column> Widget for Highlights<'a, C> {
+    fn draw(&mut self, area: Rect, buf: &mut Buffer) {
+        let accent = theme::current().accent;
+        // Inside the borders, below the header and the line following it
+        let top = area.top() + 3;
+        let bottom = area.bottom().saturating_sub(1);
+        let right = area.right().saturating_sub(1);
+        for (i, row) in self.rows.iter().skip(self.offset).enumerate() {
+            let y = top + i as u16;
+            if y >= bottom {
+                break;
+            }
+            let row = match row {
+                Some(row) => row,
+                None => continue,
+            };
+            let mut x = area.left() + 1;
+            for (n, &width) in self.widths.iter().enumerate() {
+                if let Some(&column) = n.checked_sub(self.skip).and_then(|n| self.columns.get(n)) {
+                    for &position in row.highlights(column) {
+                        let position = position as u16;
+                        if position < width && x + position < right {
+                            buf.get_mut(x + position, y)
+                                .set_fg(accent)
+                                .set_modifier(Modifier::UNDERLINED);
+                        }
+                    }
+                }
+                // Columns are separated by a space
+                x += width + 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Field {
+        Name,
+        Image,
+    }
+
+    impl Column for Field {
+        type Row = (&'static str, &'static str, &'static str);
+
+        const ALL: &'static [Field] = &[Field::Name, Field::Image];
+        const DEFAULT: &'static [Field] = Field::ALL;
+
+        fn name(self) -> &'static str {
+            match self {
+                Field::Name => "name",
+                Field::Image => "image",
+            }
+        }
+
+        fn title(self) -> &'static str {
+            self.name()
+        }
+
+        fn width(self) -> u16 {
+            10
+        }
+
+        fn value(self, row: &Self::Row) -> String {
+            match self {
+                Field::Name => row.0.to_string(),
+                Field::Image => row.1.to_string(),
+            }
+        }
+    }
+
+    impl Searchable for Field {
+        const SEARCHED: &'static [Field] = Field::ALL;
+
+        fn labels(row: &Self::Row) -> Vec<(&str, &str)> {
+            vec![("com.docker.compose.project", row.2)]
+        }
+    }
+
+    const ROWS: &[(&str, &str, &str)] = &[
+        ("web", "nginx:latest", "shop"),
+        ("db", "postgres:10", "shop"),
+        ("cache", "redis", "blog"),
+    ];
+
+    fn filter(text: &str, mode: FilterMode) -> Vec<(usize, Vec<usize>, Vec<usize>)> {
+        let mut filter = FilterBar::new();
+        filter.edit();
+        filter.mode = mode;
+        for c in text.chars() {
+            filter.handle_input(Key::Char(c));
+        }
+        filter
+            .apply::<Field>(ROWS)
+            .into_iter()
+            .map(|m| {
+                let name = m.highlights(Field::Name).to_vec();
+                let image = m.highlights(Field::Image).to_vec();
+                (m.index, name, image)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn substring() {
+        assert_eq!(filter("", FilterMode::Substring).len(), 3);
+        assert_eq!(
+            filter("E", FilterMode::Substring),
+            vec![
+                (0, vec![1], vec![9]),
+                (1, vec![], vec![6]),
+                (2, vec![4], vec![1])
+            ]
+        );
+        assert_eq!(
+            filter("ngin", FilterMode::Substring),
+            vec![(0, vec![], vec![0, 1, 2, 3])]
+        );
+        // Labels are matched too, but not highlighted
+        assert_eq!(
+            filter("blog", FilterMode::Substring),
+            vec![(2, vec![], vec![])]
+        );
+        assert!(filter("nginy", FilterMode::Substring).is_empty());
+    }
+
+    #[test]
+    fn fuzzy() {
+        assert_eq!(
+            filter("pg10", FilterMode::Fuzzy),
+            vec![(1, vec![], vec![0, 4, 9, 10])]
+        );
+        assert_eq!(
+            filter("cae", FilterMode::Fuzzy),
+            vec![(2, vec![0, 1, 4], vec![])]
+        );
+        assert!(filter("xyz", FilterMode::Fuzzy).is_empty());
+    }
+
+    #[test]
+    fn labels() {
+        let indices = |text| -> Vec<_> {
+            filter(text, FilterMode::Substring)
+                .into_iter()
+                .map(|(i, _, _)| i)
+                .collect()
+        };
+        assert_eq!(indices("com.docker.compose.project=shop"), vec![0, 1]);
+        assert_eq!(indices("COM.DOCKER.COMPOSE.PROJECT=B"), vec![2]);
+        assert_eq!(indices("=sho"), vec![0, 1]);
+        assert!(indices("project=shop").is_empty());
+    }
+
+    #[test]
+    fn prompt() {
+        let mut filter = FilterBar::new();
+        assert_eq!(filter.title(), None);
+        filter.edit();
+        filter.handle_input(Key::Char('w'));
+        filter.handle_input(Key::Char('\t'));
+        assert_eq!(filter.title().unwrap(), "Filter [fuzzy]: w_");
+        filter.handle_input(Key::Char('\n'));
+        assert!(!filter.is_editing());
+        assert!(filter.is_active());
+        assert_eq!(filter.title().unwrap(), "Filter [fuzzy]: w");
+        filter.edit();
+        filter.handle_input(Key::Esc);
+        assert!(!filter.is_active());
+        assert_eq!(filter.title(), None);
+    }
+}
@@ -0,0 +1,126 @@
+use failure::Error;
+use termion::event::Key;
+
+use crate::docker::{ListFilter, ListKind};
+
+/// Edits the filters applied by the daemon to a list, typed as `key=value` pairs separated by
+/// spaces, e.g. `status=exited label=com.docker.compose.project=shop`.
+#[derive(Debug)]
+pub struct FilterEditor {
+    kind: ListKind,
+    /// Text of the filters, while they are being edited
+    text: Option<String>,
+}
+
+impl FilterEditor {
+    pub fn new(kind: ListKind) -> FilterEditor {
+        FilterEditor { kind, text: None }
+    }
+
+    /// Returns `true` while the filters are being edited.
+    pub fn is_open(&self) -> bool {
+        self.text.is_some()
+    }
+
+    /// Opens the editor, starting from the given filters.
+    pub fn open(&mut self, filters: &[ListFilter]) {
+        let filters: Vec<_> = filters.iter().map(ListFilter::to_string).collect();
+        self.text = Some(filters.join(" "));
+    }
+
+    /// Handles a key press while the editor is open. Returns the new filters when `Enter` is
+    /// pressed, or the reason why they are invalid, in which case the editor stays open. `Esc`
+    /// closes the editor without changing the filters.
+    pub fn handle_input(&mut self, key: Key) -> Option<Result<Vec<ListFilter>, Error>> {
+        let text = self.text.as_mut()?;
+        match key {
+            Key::Char('\n') => {
+                let filters = ListFilter::parse_all(text, self.kind);
+                if filters.is_ok() {
+                    self.text = None;
+                }
+                return Some(filters);
+            }
+            Key::Esc => self.text = None,
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Char(c) => text.push(c),
+            _ => {}
+        }
+        None
+    }
+
+    /// Returns the title of the list describing the given filters, or the ones being edited, e.g.
+    /// `Daemon filters: status=exited`. Returns `None` if there are no filters.
+    pub fn title(&self, filters: &[ListFilter]) -> Option<String> {
+        match self.text {
+            Some(ref text) => Some(format!("Daemon filters: {}_", text)),
+            None if filters.is_empty() => None,
+            None => {
+                let filters: Vec<_> = filters.iter().map(ListFilter::to_string).collect();
+                Some(format!("Daemon filters: {}", filters.join(" ")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(editor: &mut FilterEditor, text: &str) {
+        for c in text.chars() {
+            assert!(editor.handle_input(Key::Char(c)).is_none());
+        }
+    }
+
+    #[test]
+    fn edits_the_filters() {
+        let mut editor = FilterEditor::new(ListKind::Containers);
+        let filters = vec![ListFilter::Status("exited".to_string())];
+        assert!(editor.handle_input(Key::Char('x')).is_none());
+        assert_eq!(
+            editor.title(&filters).unwrap(),
+            "Daemon filters: status=exited"
+        );
+
+        editor.open(&filters);
+        type_text(&mut editor, " name=we");
+        assert_eq!(
+            editor.title(&filters).unwrap(),
+            "Daemon filters: status=exited name=we_"
+        );
+        let filters = editor.handle_input(Key::Char('\n')).unwrap().unwrap();
+        assert!(!editor.is_open());
+        assert_eq!(
+            filters,
+            vec![
+                ListFilter::Status("exited".to_string()),
+                ListFilter::Name("we".to_string())
+            ]
+        );
+
+        // Clearing the text removes the filters
+        editor.open(&filters);
+        for _ in 0..30 {
+            editor.handle_input(Key::Backspace);
+        }
+        assert_eq!(
+            editor.handle_input(Key::Char('\n')).unwrap().unwrap(),
+            vec![]
+        );
+        assert_eq!(editor.title(&[]), None);
+    }
+
+    #[test]
+    fn invalid_filters_keep_the_editor_open() {
+        let mut editor = FilterEditor::new(ListKind::Images);
+        editor.open(&[]);
+        type_text(&mut editor, "status=exited");
+        assert!(editor.handle_input(Key::Char('\n')).unwrap().is_err());
+        assert!(editor.is_open());
+        editor.handle_input(Key::Esc);
+        assert!(!editor.is_open());
+    }
+}
@@ -1,65 +1,100 @@
 use std::sync::Arc;
 
-use termion::event::Key;
 use tui::{
+    backend::Backend,
     layout::Rect,
+    style::{Modifier, Style},
     widgets::{Block, Borders, Paragraph, Text, Widget},
     Frame,
 };
 
 use crate::app::AppCommand;
-use crate::docker::DockerExecutor;
-use crate::views::View;
-use crate::Backend;
+use crate::docker::DockerBackend;
+use crate::keys::{key_name, scope_title, Action, Keymap};
+use crate::theme;
+use crate::views::{Draw, View};
 
 pub struct HelpView {
+    /// The actions of each scope, with the keys they are bound to
+    sections: Vec<(&'static str, Vec<(String, &'static str)>)>,
     scroll: u16,
 }
 
 impl HelpView {
-    pub fn new() -> HelpView {
-        HelpView { scroll: 0 }
+    /// Creates the help screen for the given key bindings.
+    pub fn new(keymap: &Keymap) -> HelpView {
+        let mut sections: Vec<(&'static str, Vec<_>)> = Vec::new();
+        for (binding, keys) in keymap.bindings() {
+            let keys = if keys.is_empty() {
+                "(unbound)".to_string()
+            } else {
+                let names: Vec<_> = keys.iter().map(|&k| key_name(k)).collect();
+                names.join(", ")
+            };
+            let action = (keys, binding.description);
+            match sections.last_mut() {
+                Some((scope, actions)) if *scope == binding.scope => actions.push(action),
+                _ => sections.push((binding.scope, vec![action])),
+            }
+        }
+        HelpView {
+            sections,
+            scroll: 0,
+        }
     }
 }
 
 impl View for HelpView {
-    fn handle_input(&mut self, key: Key, _docker: Arc<DockerExecutor>) -> Option<AppCommand> {
-        match key {
-            Key::Up | Key::Char('k') => {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        _docker: Arc<dyn DockerBackend>,
+    ) -> Option<AppCommand> {
+        match action {
+            Action::Up => {
                 if self.scroll > 0 {
                     self.scroll -= 1;
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::Down | Key::Char('j') => {
+            Action::Down => {
                 self.scroll += 1;
                 Some(AppCommand::NoOp)
             }
             _ => None,
         }
     }
+}
 
-    fn refresh(&mut self, _docker: Arc<DockerExecutor>) {}
-
-    fn draw(&self, t: &mut Frame<Backend>, rect: Rect) {
-        let text = vec![
-            Text::raw("KEYS:\n"),
-            Text::raw("? - help\n"),
-            Text::raw("q - exit view\n"),
-            Text::raw("R - refresh view\n"),
-            Text::raw("i - switch to view: images list\n"),
-            Text::raw("v - switch to view: docker info\n"),
-            Text::raw("L - switch to view: application logs\n"),
-            Text::raw("k - up\n"),
-            Text::raw("j - down\n"),
-            Text::raw("s - stop container      in view: container list\n"),
-            Text::raw("S - start container     in view: container list\n"),
-            Text::raw("p - pause container     in view: container list\n"),
-            Text::raw("P - unpause container   in view: container list\n"),
-            Text::raw("d - delete container    in view: container list\n"),
-            Text::raw("l - container logs      in view: container list\n"),
-            Text::raw("\u{23CE} - container details   in view: container list\n"),
-        ];
+impl<B: Backend> Draw<B> for HelpView {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
+        let title = Style::default().fg(theme.title).modifier(Modifier::BOLD);
+        let key_width = self
+            .sections
+            .iter()
+            .flat_map(|(_, actions)| actions.iter().map(|(keys, _)| keys.len()))
+            .max()
+            .unwrap_or(0);
+        let mut text = Vec::new();
+        for (scope, actions) in &self.sections {
+            if !text.is_empty() {
+                text.push(Text::raw("\n"));
+            }
+            text.push(Text::styled(format!("{}\n", scope_title(scope)), title));
+            for (keys, description) in actions {
+                text.push(Text::raw(format!(
+                    "  {:width$}  {}\n",
+                    keys,
+                    description,
+                    width = key_width
+                )));
+            }
+        }
 
         Paragraph::new(text.iter())
             .block(Block::default().borders(Borders::ALL))
@@ -69,3 +104,50 @@ impl View for HelpView {
             .render(t, rect);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::views::snapshot::{assert_snapshot, render};
+
+    use crate::keys::find_binding;
+    use termion::event::Key;
+
+    #[test]
+    fn snapshot_help() {
+        assert_snapshot("help", &render(&HelpView::new(&Keymap::default()), 80, 60));
+    }
+
+    #[test]
+    fn remapped_keys() {
+        let keymap = Keymap::new(&[
+            (
+                find_binding("containers", "stop").unwrap(),
+                vec![Key::Char('x'), Key::F(9)],
+            ),
+            (find_binding("containers", "delete").unwrap(), vec![]),
+        ])
+        .unwrap();
+        let view = HelpView::new(&keymap);
+        let (scope, actions) = &view.sections[1];
+        assert_eq!(*scope, "containers");
+        assert!(actions.contains(&(
+            "x, f9".to_string(),
+            "Stop the marked containers, or the selected container or group"
+        )));
+        assert!(actions.contains(&(
+            "(unbound)".to_string(),
+            "Delete the marked containers, or the selected container or group"
+        )));
+        assert_snapshot("help_remapped", &render(&view, 80, 60));
+    }
+
+    #[test]
+    fn snapshot_scrolled_help() {
+        let mut view = HelpView::new(&Keymap::default());
+        view.scroll = 5;
+        assert_snapshot("help_scrolled", &render(&view, 60, 10));
+        view.scroll = 0;
+        assert_snapshot("help_height_2", &render(&view, 60, 2));
+    }
+}
@@ -1,25 +1,178 @@
+use std::cmp::Ordering;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
 use bytesize;
+use failure::Error;
 
-use shiplift::{rep::Image, ImageListOptions};
+use shiplift::rep::Image;
 use termion::event::Key;
 use tui::{
+    backend::Backend,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     widgets::{Block, Borders, Row, Table, Widget},
     Frame,
 };
 
 use crate::app::AppCommand;
-use crate::docker::DockerExecutor;
-use crate::views::{human_duration, View};
-use crate::Backend;
+use crate::docker::{DockerBackend, ImageDeleteOptions, ListFilter, ListKind, ListOptions};
+use crate::events::DockerEvent;
+use crate::images::{ImageOperation, ImageTarget};
+use crate::keys::Action;
+use crate::theme;
+use crate::views::{
+    human_duration, Column, Draw, FilterBar, FilterEditor, Highlights, RowMatch, Searchable,
+    SortOrder, View,
+};
+use crate::worker::{Pending, Worker};
+
+/// The columns of the image list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageColumn {
+    Id,
+    Parent,
+    Tag,
+    Created,
+    Size,
+}
+
+impl Column for ImageColumn {
+    type Row = Image;
+
+    const ALL: &'static [ImageColumn] = &[
+        ImageColumn::Id,
+        ImageColumn::Parent,
+        ImageColumn::Tag,
+        ImageColumn::Created,
+        ImageColumn::Size,
+    ];
+    const DEFAULT: &'static [ImageColumn] = ImageColumn::ALL;
+
+    fn name(self) -> &'static str {
+        match self {
+            ImageColumn::Id => "id",
+            ImageColumn::Parent => "parent",
+            ImageColumn::Tag => "tag",
+            ImageColumn::Created => "created",
+            ImageColumn::Size => "size",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            ImageColumn::Id => "Image ID",
+            ImageColumn::Parent => "Parent",
+            ImageColumn::Tag => "Tag",
+            ImageColumn::Created => "Created",
+            ImageColumn::Size => "Virtual Size",
+        }
+    }
+
+    fn width(self) -> u16 {
+        match self {
+            ImageColumn::Id => 10,
+            ImageColumn::Parent => 10,
+            ImageColumn::Tag => 45,
+            ImageColumn::Created => 15,
+            ImageColumn::Size => 20,
+        }
+    }
+
+    fn value(self, c: &Image) -> String {
+        match self {
+            ImageColumn::Id => short_id(&c.id),
+            ImageColumn::Parent => short_id(&c.parent_id),
+            // The other tags are only counted
+            ImageColumn::Tag => match c.repo_tags.as_deref() {
+                None | Some([]) => "<none>".to_string(),
+                Some([tag]) => tag.clone(),
+                Some(tags) => format!("{} (+{})", tags[0], tags.len() - 1),
+            },
+            ImageColumn::Created => {
+                let creation_timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(c.created);
+                let duration = creation_timestamp.elapsed().unwrap_or_default();
+                format!("{} ago", human_duration(&duration))
+            }
+            ImageColumn::Size => bytesize::to_string(c.virtual_size, false),
+        }
+    }
+
+    fn compare(self, a: &Image, b: &Image) -> Ordering {
+        match self {
+            ImageColumn::Created => a.created.cmp(&b.created),
+            ImageColumn::Size => a.virtual_size.cmp(&b.virtual_size),
+            _ => self.value(a).cmp(&self.value(b)),
+        }
+    }
+}
+
+impl Searchable for ImageColumn {
+    const SEARCHED: &'static [ImageColumn] = &[ImageColumn::Id, ImageColumn::Tag];
+
+    fn labels(c: &Image) -> Vec<(&str, &str)> {
+        c.labels
+            .iter()
+            .flatten()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect()
+    }
+}
+
+/// Shortens an image id like the docker CLI does.
+fn short_id(id: &str) -> String {
+    if id.starts_with("sha256:") {
+        id[7..17].to_string()
+    } else {
+        id.to_string()
+    }
+}
+
+/// Splits an image reference typed by the user, e.g. `registry:5000/web:v2`, into its repository
+/// and tag, which is `latest` if it isn't given. Returns `None` if the reference is invalid.
+fn parse_reference(reference: &str) -> Option<(String, String)> {
+    if reference.is_empty() || reference.contains(char::is_whitespace) {
+        return None;
+    }
+    let (repo, tag) = match reference.rfind(':') {
+        // A colon before a slash is the port of the registry
+        Some(colon) if !reference[colon..].contains('/') => {
+            (&reference[..colon], &reference[colon + 1..])
+        }
+        _ => (reference, "latest"),
+    };
+    if repo.is_empty() || tag.is_empty() {
+        return None;
+    }
+    Some((repo.to_string(), tag.to_string()))
+}
+
+/// A tag being typed, to add it to an image or remove it from it.
+struct TagPrompt {
+    /// The image selected when the prompt was opened, which the tag is applied to
+    image: ImageTarget,
+    /// Whether the tag is removed rather than added
+    untag: bool,
+    text: String,
+}
 
 pub struct ImagesListView {
     images: Vec<Image>,
     selected: usize,
+    /// Columns of the list
+    pub columns: Vec<ImageColumn>,
+    /// Filters applied by the daemon to the list of images
+    filters: Vec<ListFilter>,
+    /// Edits the filters applied by the daemon
+    editor: FilterEditor,
+    /// Narrows the displayed images
+    filter: FilterBar,
+    /// Order of the images
+    sort: SortOrder<ImageColumn>,
+    /// Tag being typed
+    prompt: Option<TagPrompt>,
+    /// Images being fetched in the background
+    pending: Pending<Result<Vec<Image>, Error>>,
 }
 
 impl ImagesListView {
@@ -27,34 +180,134 @@ impl ImagesListView {
         ImagesListView {
             images: Vec::new(),
             selected: 0,
+            columns: ImageColumn::DEFAULT.to_vec(),
+            filters: Vec::new(),
+            editor: FilterEditor::new(ListKind::Images),
+            filter: FilterBar::new(),
+            sort: SortOrder::new(),
+            prompt: None,
+            pending: Pending::none(),
+        }
+    }
+
+    /// Returns the images matching the filter, which are the ones displayed.
+    fn visible(&self) -> Vec<RowMatch<ImageColumn>> {
+        self.filter.apply(&self.images)
+    }
+
+    /// Returns the id of the selected image.
+    fn selected_id(&self) -> Option<String> {
+        self.visible()
+            .get(self.selected)
+            .map(|m| self.images[m.index].id.clone())
+    }
+
+    /// Returns the selected image, as the target of an operation.
+    fn target(&self) -> Option<ImageTarget> {
+        let image = &self.images[self.visible().get(self.selected)?.index];
+        let tags: Vec<_> = image
+            .repo_tags
+            .iter()
+            .flatten()
+            .filter(|t| *t != "<none>:<none>")
+            .cloned()
+            .collect();
+        Some(ImageTarget {
+            id: image.id.clone(),
+            name: tags.first().cloned().unwrap_or_else(|| short_id(&image.id)),
+            tags,
+        })
+    }
+
+    /// Handles a key press while a tag is being typed. `Enter` adds or removes the tag, `Tab`
+    /// goes to the next tag of the image when removing one, and `Esc` cancels.
+    fn handle_prompt(&mut self, key: Key) -> Option<AppCommand> {
+        let prompt = self.prompt.as_mut()?;
+        let image = &prompt.image;
+        match key {
+            Key::Char('\n') => {
+                let operation = if prompt.untag {
+                    if !image.tags.contains(&prompt.text) {
+                        let msg = format!("Image {} has no tag {}", image.name, prompt.text);
+                        return Some(AppCommand::ErrorMsg(msg));
+                    }
+                    ImageOperation::Untag(prompt.text.clone())
+                } else {
+                    match parse_reference(&prompt.text) {
+                        Some((repo, tag)) => ImageOperation::Tag(repo, tag),
+                        None => {
+                            let msg =
+                                format!("Invalid tag {}, expected repository[:tag]", prompt.text);
+                            return Some(AppCommand::ErrorMsg(msg));
+                        }
+                    }
+                };
+                let image = self.prompt.take()?.image;
+                return Some(AppCommand::RunImageOperation(operation, image));
+            }
+            Key::Esc => self.prompt = None,
+            Key::Char('\t') if prompt.untag => {
+                let next = image
+                    .tags
+                    .iter()
+                    .position(|t| *t == prompt.text)
+                    .map_or(0, |i| (i + 1) % image.tags.len());
+                prompt.text = image.tags[next].clone();
+            }
+            Key::Backspace => {
+                prompt.text.pop();
+            }
+            Key::Char(c) => prompt.text.push(c),
+            _ => {}
+        }
+        Some(AppCommand::NoOp)
+    }
+
+    /// Selects the image with the given id if it is displayed, or keeps the selection within the
+    /// displayed images otherwise.
+    fn reselect(&mut self, id: Option<String>) {
+        let visible = self.visible();
+        let images = &self.images;
+        match id.and_then(|id| visible.iter().position(|m| images[m.index].id == id)) {
+            Some(position) => self.selected = position,
+            None => self.selected = self.selected.min(visible.len().saturating_sub(1)),
         }
     }
 }
 
 impl View for ImagesListView {
-    fn handle_input(&mut self, key: Key, _docker: Arc<DockerExecutor>) -> Option<AppCommand> {
-        let max_index = self.images.len() - 1;
-        match key {
-            Key::Down | Key::Char('j') => {
-                if !self.images.is_empty() {
+    fn name(&self) -> &'static str {
+        "images"
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        _docker: Arc<dyn DockerBackend>,
+    ) -> Option<AppCommand> {
+        let count = self.visible().len();
+        let max_index = count.saturating_sub(1);
+        match action {
+            Action::Down => {
+                if count > 0 {
                     self.selected = (self.selected + 1).min(max_index);
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::Up | Key::Char('k') => {
-                if !self.images.is_empty() && self.selected > 0 {
+            Action::Up => {
+                if count > 0 && self.selected > 0 {
                     self.selected -= 1;
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::PageDown | Key::Ctrl('d') => {
-                if !self.images.is_empty() {
+            Action::PageDown => {
+                if count > 0 {
                     self.selected = (self.selected + 10).min(max_index);
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::PageUp | Key::Ctrl('u') => {
-                if !self.images.is_empty() {
+            Action::PageUp => {
+                if count > 0 {
                     self.selected = if self.selected >= 10 {
                         self.selected - 10
                     } else {
@@ -63,73 +316,167 @@ impl View for ImagesListView {
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::End | Key::Char('G') => {
-                if !self.images.is_empty() {
+            Action::Bottom => {
+                if count > 0 {
                     self.selected = max_index;
                 }
                 Some(AppCommand::NoOp)
             }
-            Key::Home | Key::Char('g') => {
-                if !self.images.is_empty() {
+            Action::Top => {
+                if count > 0 {
                     self.selected = 0;
                 }
                 Some(AppCommand::NoOp)
             }
+            Action::Filter => {
+                self.filter.edit();
+                Some(AppCommand::NoOp)
+            }
+            Action::EditFilters => {
+                self.editor.open(&self.filters);
+                Some(AppCommand::NoOp)
+            }
+            Action::Delete | Action::ForceDelete => {
+                let image = self.target()?;
+                let opts = ImageDeleteOptions {
+                    force: action == Action::ForceDelete,
+                    no_prune: false,
+                };
+                Some(AppCommand::RunImageOperation(
+                    ImageOperation::Delete(opts),
+                    image,
+                ))
+            }
+            Action::Tag => {
+                self.prompt = Some(TagPrompt {
+                    image: self.target()?,
+                    untag: false,
+                    text: String::new(),
+                });
+                Some(AppCommand::NoOp)
+            }
+            Action::Untag => {
+                let image = self.target()?;
+                if image.tags.len() < 2 {
+                    return Some(AppCommand::ErrorMsg(format!(
+                        "Image {} has no other tag, delete it instead",
+                        image.name
+                    )));
+                }
+                self.prompt = Some(TagPrompt {
+                    untag: true,
+                    text: image.tags[0].clone(),
+                    image,
+                });
+                Some(AppCommand::NoOp)
+            }
+            Action::Sort | Action::ReverseSort => {
+                let selected = self.selected_id();
+                if action == Action::Sort {
+                    self.sort.next_column();
+                } else {
+                    self.sort.reverse();
+                }
+                if self.sort.column.is_none() {
+                    // Restore the order of the daemon
+                    return Some(AppCommand::Refresh);
+                }
+                self.sort.sort(&mut self.images);
+                self.reselect(selected);
+                Some(AppCommand::NoOp)
+            }
+            Action::NextMatch => {
+                if count > 0 {
+                    self.selected = (self.selected + 1) % count;
+                }
+                Some(AppCommand::NoOp)
+            }
+            Action::PreviousMatch => {
+                if count > 0 {
+                    self.selected = (self.selected + count - 1) % count;
+                }
+                Some(AppCommand::NoOp)
+            }
             _ => None,
         }
     }
 
-    fn refresh(&mut self, docker: Arc<DockerExecutor>) {
-        let options = ImageListOptions::builder().all(true).build();
-        let images = docker.images(&options).unwrap();
-        self.images = images;
-        if self.images.is_empty() {
-            self.selected = 0;
-        } else if self.selected >= self.images.len() {
-            self.selected = self.images.len() - 1;
+    fn handle_input(&mut self, key: Key, _docker: Arc<dyn DockerBackend>) -> Option<AppCommand> {
+        if self.editor.is_open() {
+            return match self.editor.handle_input(key) {
+                Some(Ok(filters)) => {
+                    self.filters = filters;
+                    Some(AppCommand::Refresh)
+                }
+                Some(Err(err)) => Some(AppCommand::ErrorMsg(err.to_string())),
+                None => Some(AppCommand::NoOp),
+            };
+        }
+        if self.prompt.is_some() {
+            return self.handle_prompt(key);
+        }
+        if !(self.filter.is_editing() || (key == Key::Esc && self.filter.is_active())) {
+            return None;
+        }
+        // Keep the selected image selected while the rows change
+        let selected = self.selected_id();
+        if self.filter.is_editing() {
+            self.filter.handle_input(key);
+        } else {
+            self.filter.clear();
         }
+        self.reselect(selected);
+        Some(AppCommand::NoOp)
+    }
+
+    fn captures_input(&self) -> bool {
+        self.filter.is_editing() || self.editor.is_open() || self.prompt.is_some()
+    }
+
+    fn handle_docker_event(&mut self, event: &DockerEvent) -> bool {
+        event.typ == "image" && event.changes_state()
     }
 
-    fn draw(&self, t: &mut Frame<Backend>, rect: Rect) {
-        let selected_style = Style::default().fg(Color::Yellow).modifier(Modifier::BOLD);
-        let normal_style = Style::default().fg(Color::White);
-        let header = ["Image ID", "Parent", "Tag", "Created", "Virtual Size"];
-        let height = rect.height as usize - 4; // 2 for border + 2 for header
+    fn refresh(&mut self, worker: &Worker) {
+        let options = ListOptions {
+            all: true,
+            filters: self.filters.clone(),
+        };
+        self.pending = worker.run(move |docker| docker.images(&options));
+    }
+
+    fn update(&mut self) {
+        let selected = self.selected_id();
+        match self.pending.take() {
+            Some(Ok(images)) => self.images = images,
+            Some(Err(err)) => error!("Failed to list images: {}", err),
+            None => return,
+        }
+        self.sort.sort(&mut self.images);
+        self.reselect(selected);
+    }
+}
+
+impl<B: Backend> Draw<B> for ImagesListView {
+    fn draw(&self, t: &mut Frame<B>, rect: Rect) {
+        let theme = theme::current();
+        let selected_style = Style::default().fg(theme.selected).modifier(Modifier::BOLD);
+        let normal_style = Style::default().fg(theme.foreground);
+        let header: Vec<_> = self.columns.iter().map(|&c| self.sort.title(c)).collect();
+        let widths: Vec<_> = self.columns.iter().map(|c| c.width()).collect();
+        let height = rect.height.saturating_sub(4) as usize; // 2 for border + 2 for header
         let offset = if self.selected >= height {
             self.selected - height + 1
         } else {
             0
         };
-        let rows: Vec<_> = self
-            .images
+        let visible = self.visible();
+        let rows: Vec<_> = visible
             .iter()
             .enumerate()
-            .map(|(i, c)| {
-                let creation_timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(c.created);
-                let duration = creation_timestamp.elapsed().unwrap();
-                let mut duration_str = human_duration(&duration);
-                duration_str.push_str(" ago");
-                let id = if c.id.starts_with("sha256:") {
-                    (&c.id[7..17]).to_string()
-                } else {
-                    c.id.clone()
-                };
-                let parent = if c.parent_id.starts_with("sha256:") {
-                    (&c.parent_id[7..17]).to_string()
-                } else {
-                    c.parent_id.clone()
-                };
-                let data: Vec<String> = vec![
-                    id,
-                    parent,
-                    c.repo_tags
-                        .as_ref()
-                        .and_then(|tags| tags.first())
-                        .cloned()
-                        .unwrap_or_else(|| "<none>".to_string()),
-                    duration_str,
-                    bytesize::to_string(c.virtual_size, false),
-                ];
+            .map(|(i, m)| {
+                let c = &self.images[m.index];
+                let data: Vec<String> = self.columns.iter().map(|col| col.value(c)).collect();
                 if i == self.selected {
                     Row::StyledData(data.into_iter(), selected_style)
                 } else {
@@ -139,9 +486,258 @@ impl View for ImagesListView {
             .skip(offset)
             .collect();
 
+        let mut titles: Vec<_> = self.editor.title(&self.filters).into_iter().collect();
+        if let Some(filter) = self.filter.title() {
+            titles.push(format!(
+                "{} ({} of {})",
+                filter,
+                visible.len(),
+                self.images.len()
+            ));
+        }
+        titles.extend(self.sort.hidden_title(&self.columns));
+        if let Some(ref prompt) = self.prompt {
+            titles.push(if prompt.untag {
+                format!("Remove the tag (Tab: next tag): {}_", prompt.text)
+            } else {
+                format!("New tag (repository[:tag]): {}_", prompt.text)
+            });
+        }
+        let title = titles.join(" | ");
         Table::new(header.iter(), rows.into_iter())
-            .block(Block::default().borders(Borders::ALL))
-            .widths(&[10, 10, 45, 15, 20]) // TODO be smarter with sizes here
+            .block(Block::default().borders(Borders::ALL).title(&title))
+            .widths(&widths) // TODO be smarter with sizes here
             .render(t, rect);
+        if self.filter.is_active() {
+            let matches: Vec<_> = visible.iter().map(Some).collect();
+            Highlights {
+                rows: &matches,
+                offset,
+                skip: 0,
+                columns: &self.columns,
+                widths: &widths,
+            }
+            .render(t, rect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::fake::FakeDocker;
+    use crate::views::snapshot::{assert_snapshot, render};
+    use crossbeam_channel::unbounded;
+
+    const NGINX: &str = "sha256:06144b2878448774e55577ae7d7b0dd1c0de2bc2bdd24f3fbcd3bbee79b5a0f9";
+    const POSTGRES: &str =
+        "sha256:3e016ee7f4d6d5f95be1bd1b4f2e4f2d5b3e0a3aa95bf54b5c3b5d03b35c0b3e";
+
+    fn images_view(docker: &FakeDocker) -> ImagesListView {
+        let mut view = ImagesListView::new();
+        view.images = docker.images(&ListOptions::default()).unwrap();
+        view
+    }
+
+    #[test]
+    fn snapshot_images() {
+        let docker = FakeDocker::new()
+            .with_image(NGINX, "nginx:latest", 109_000_000)
+            .with_image(POSTGRES, "postgres:10", 228_000_000);
+        let view = images_view(&docker);
+        assert_snapshot("images_list", &render(&view, 110, 10));
+        assert_snapshot("images_list_height_3", &render(&view, 110, 3));
+    }
+
+    #[test]
+    fn sorts_by_size() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_image(NGINX, "nginx:latest", 109_000_000)
+                .with_image(POSTGRES, "postgres:10", 228_000_000),
+        );
+        let mut view = images_view(&docker);
+        while view.sort.column != Some(ImageColumn::Size) {
+            view.handle_action(Action::Sort, docker.clone());
+        }
+        assert_eq!(view.images[0].id, NGINX);
+        assert_eq!(view.selected_id().unwrap(), NGINX);
+        view.handle_action(Action::ReverseSort, docker.clone());
+        assert_eq!(view.images[0].id, POSTGRES);
+        // The selection follows the image
+        assert_eq!(view.selected_id().unwrap(), NGINX);
+        assert_eq!(view.sort.title(ImageColumn::Size), "Virtual Size ▼");
+    }
+
+    fn type_text(view: &mut ImagesListView, docker: &Arc<FakeDocker>, text: &str) {
+        for c in text.chars() {
+            view.handle_input(Key::Char(c), docker.clone());
+        }
+    }
+
+    #[test]
+    fn filters_the_images() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_image(NGINX, "nginx:latest", 109_000_000)
+                .with_image(POSTGRES, "postgres:10", 228_000_000),
+        );
+        let mut view = images_view(&docker);
+        view.handle_action(Action::Bottom, docker.clone());
+
+        view.handle_action(Action::Filter, docker.clone());
+        assert!(view.captures_input());
+        type_text(&mut view, &docker, "gres\n");
+        assert!(!view.captures_input());
+        assert_eq!(view.visible().len(), 1);
+        assert_eq!(view.selected_id().unwrap(), POSTGRES);
+
+        // Esc removes the filter, and the selection is kept
+        view.handle_input(Key::Esc, docker.clone());
+        assert_eq!(view.visible().len(), 2);
+        assert_eq!(view.selected_id().unwrap(), POSTGRES);
+
+        // n/N wrap around the matches
+        view.handle_action(Action::NextMatch, docker.clone());
+        assert_eq!(view.selected_id().unwrap(), NGINX);
+        view.handle_action(Action::PreviousMatch, docker.clone());
+        assert_eq!(view.selected_id().unwrap(), POSTGRES);
+
+        // Nothing to act on when nothing matches
+        view.handle_action(Action::Filter, docker.clone());
+        type_text(&mut view, &docker, "mysql\n");
+        assert!(view.visible().is_empty());
+        assert_eq!(view.handle_action(Action::Delete, docker.clone()), None);
+        assert_eq!(view.handle_action(Action::Tag, docker.clone()), None);
+        assert!(!view.captures_input());
+    }
+
+    #[test]
+    fn daemon_filters() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_image(NGINX, "nginx:latest", 109_000_000)
+                .with_image(POSTGRES, "postgres:10", 228_000_000),
+        );
+        let mut view = images_view(&docker);
+        view.handle_action(Action::EditFilters, docker.clone());
+        assert!(view.captures_input());
+        type_text(&mut view, &docker, "status=exited");
+        match view.handle_input(Key::Char('\n'), docker.clone()) {
+            Some(AppCommand::ErrorMsg(msg)) => {
+                assert!(msg.starts_with("Unsupported filter status"))
+            }
+            command => panic!("Unexpected command {:?}", command),
+        }
+        for _ in 0.."status=exited".len() {
+            view.handle_input(Key::Backspace, docker.clone());
+        }
+        type_text(&mut view, &docker, "reference=postgres");
+        let command = view.handle_input(Key::Char('\n'), docker.clone());
+        assert_eq!(command, Some(AppCommand::Refresh));
+        assert!(!view.captures_input());
+
+        // The filters are sent to the daemon
+        let (tx, rx) = unbounded();
+        view.refresh(&Worker::new(docker, tx));
+        rx.recv().unwrap();
+        view.update();
+        let ids: Vec<_> = view.images.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec![POSTGRES]);
+    }
+
+    #[test]
+    fn deletes_and_tags_the_selected_image() {
+        let docker = Arc::new(
+            FakeDocker::new()
+                .with_image(NGINX, "nginx:latest", 109_000_000)
+                .with_tag(NGINX, "nginx:1.17")
+                .with_image(POSTGRES, "postgres:10", 228_000_000),
+        );
+        let mut view = images_view(&docker);
+        assert_eq!(ImageColumn::Tag.value(&view.images[0]), "nginx:latest (+1)");
+        let nginx = ImageTarget {
+            id: NGINX.to_string(),
+            name: "nginx:latest".to_string(),
+            tags: vec!["nginx:latest".to_string(), "nginx:1.17".to_string()],
+        };
+        let force = ImageDeleteOptions {
+            force: true,
+            no_prune: false,
+        };
+        assert_eq!(
+            view.handle_action(Action::ForceDelete, docker.clone()),
+            Some(AppCommand::RunImageOperation(
+                ImageOperation::Delete(force),
+                nginx.clone()
+            ))
+        );
+
+        view.handle_action(Action::Tag, docker.clone());
+        assert!(view.captures_input());
+        type_text(&mut view, &docker, "web:");
+        assert!(match view.handle_input(Key::Char('\n'), docker.clone()) {
+            Some(AppCommand::ErrorMsg(msg)) => msg.starts_with("Invalid tag web:"),
+            _ => false,
+        });
+        type_text(&mut view, &docker, "v2");
+        // The tag goes to the image selected when the prompt was opened, even if a refresh has
+        // moved another one under the selection since
+        view.images.reverse();
+        assert_eq!(
+            view.handle_input(Key::Char('\n'), docker.clone()),
+            Some(AppCommand::RunImageOperation(
+                ImageOperation::Tag("web".to_string(), "v2".to_string()),
+                nginx.clone()
+            ))
+        );
+        assert!(!view.captures_input());
+        view.images.reverse();
+
+        // The tag to remove starts with the first one, and Tab goes to the next one
+        view.handle_action(Action::Untag, docker.clone());
+        view.handle_input(Key::Char('\t'), docker.clone());
+        assert_eq!(
+            view.handle_input(Key::Char('\n'), docker.clone()),
+            Some(AppCommand::RunImageOperation(
+                ImageOperation::Untag("nginx:1.17".to_string()),
+                nginx
+            ))
+        );
+
+        // The only tag of an image can't be removed
+        view.handle_action(Action::Down, docker.clone());
+        assert_eq!(
+            view.handle_action(Action::Untag, docker.clone()),
+            Some(AppCommand::ErrorMsg(
+                "Image postgres:10 has no other tag, delete it instead".to_string()
+            ))
+        );
+        assert!(!view.captures_input());
+    }
+
+    #[test]
+    fn image_references() {
+        let parse = |reference| {
+            parse_reference(reference)
+                .map(|(repo, tag)| format!("{} {}", repo, tag))
+                .unwrap_or_default()
+        };
+        assert_eq!(parse("web"), "web latest");
+        assert_eq!(parse("web:v2"), "web v2");
+        assert_eq!(parse("registry:5000/web"), "registry:5000/web latest");
+        assert_eq!(
+            parse("registry:5000/shop/web:v2"),
+            "registry:5000/shop/web v2"
+        );
+        assert_eq!(parse("web:"), "");
+        assert_eq!(parse(":v2"), "");
+        assert_eq!(parse("web v2"), "");
+    }
+
+    #[test]
+    fn snapshot_empty_list() {
+        let view = images_view(&FakeDocker::new());
+        assert_snapshot("images_list_empty", &render(&view, 110, 10));
     }
 }
@@ -1,45 +1,115 @@
 //! This module contains all the different views of the application.
+use std::cmp::Ordering;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use shiplift::rep::Container;
 use termion::event::Key;
-use tui::{layout::Rect, Frame};
+use tui::{backend::Backend, layout::Rect, Frame};
 
 use crate::app::{AppCommand, ContainerId};
-use crate::docker::DockerExecutor;
-use crate::Backend;
+use crate::docker::DockerBackend;
+use crate::events::DockerEvent;
+use crate::keys::Action;
+use crate::worker::Worker;
 
 mod app_logs;
 mod container_details;
+mod container_graphs;
 mod container_list;
-// mod container_logs;
+mod container_logs;
+mod container_stats;
+mod contexts;
+mod dialog;
+mod docker_events;
 mod docker_info;
+mod filter;
+mod filter_editor;
 mod help;
 mod images_list;
+#[cfg(test)]
+mod snapshot;
+mod sort;
 
 pub use self::app_logs::*;
 pub use self::container_details::*;
+pub use self::container_graphs::*;
 pub use self::container_list::*;
-// pub use self::container_logs::*;
+pub use self::container_logs::*;
+pub use self::container_stats::*;
+pub use self::contexts::*;
+pub use self::dialog::*;
+pub use self::docker_events::*;
 pub use self::docker_info::*;
+pub use self::filter::*;
+pub use self::filter_editor::*;
 pub use self::help::*;
 pub use self::images_list::*;
+pub use self::sort::*;
 
 /// This trait represents a view of the application i.e. a component that knows how to display
 /// itself, how to handle input, and how to refresh itself.
-pub trait View {
-    /// Handle input for this view.
+pub trait View: Draw<crate::Backend> {
+    /// Returns the name of the view, which is the scope of its actions in the configuration file.
+    fn name(&self) -> &'static str;
+
+    /// Handle an action triggered by the user (see `keys::BINDINGS`).
+    ///
+    /// If the view can handle this action, it should return an `AppCommand` (which can potentially
+    /// be NoOp). Otherwise, it should return `None`. The default implementation returns `None`.
+    fn handle_action(
+        &mut self,
+        _action: Action,
+        _docker: Arc<dyn DockerBackend>,
+    ) -> Option<AppCommand> {
+        None
+    }
+
+    /// Handle a key press that isn't bound to any action, or any key press while the view
+    /// captures input.
     ///
     /// If the view can handle this key, it should return an `AppCommand` (which can potentially be
-    /// NoOp). Otherwise, it should return `None`.
-    fn handle_input(&mut self, key: Key, docker: Arc<DockerExecutor>) -> Option<AppCommand>;
+    /// NoOp). Otherwise, it should return `None`. The default implementation returns `None`.
+    fn handle_input(&mut self, _key: Key, _docker: Arc<dyn DockerBackend>) -> Option<AppCommand> {
+        None
+    }
+
+    /// Returns `true` if the view currently needs all key presses, e.g. because some text is being
+    /// typed, in which case they are handled by `handle_input()` instead of being translated to
+    /// actions. The default implementation returns `false`.
+    fn captures_input(&self) -> bool {
+        false
+    }
+
+    /// Called when the Docker daemon reports an event. Returns `true` if the view is affected by
+    /// it and should be refreshed. The default implementation returns `false`.
+    fn handle_docker_event(&mut self, _event: &DockerEvent) -> bool {
+        false
+    }
 
-    /// Refresh the data displayed by this view (potentially using the provided handle to the
-    /// Docker API). The default implementation doesn't do anything.
-    fn refresh(&mut self, _docker: Arc<DockerExecutor>) {}
+    /// Refresh the data displayed by this view. Calls to the Docker API should be made in the
+    /// background using the provided worker, and their results picked up in `update()`. The
+    /// default implementation doesn't do anything.
+    fn refresh(&mut self, _worker: &Worker) {}
 
+    /// Called periodically to automatically refresh the view. The default implementation calls
+    /// `refresh()`, views displaying live data can override it to do nothing.
+    fn tick(&mut self, worker: &Worker) {
+        self.refresh(worker);
+    }
+
+    /// Called on every iteration of the main loop, before drawing. Views receiving data in the
+    /// background (e.g. streams) can use it to pick up what has arrived since the last call. The
+    /// default implementation doesn't do anything.
+    fn update(&mut self) {}
+}
+
+/// Drawing part of a view. This is separate from `View` so that views can be drawn with any
+/// backend, e.g. an in-memory one in tests, while the application only deals with
+/// `Box<dyn View>`.
+pub trait Draw<B: Backend> {
     /// Draws the view in the given area.
-    fn draw(&self, t: &mut Frame<Backend>, rect: Rect);
+    fn draw(&self, t: &mut Frame<B>, rect: Rect);
 }
 
 /// The different views that the application supports
@@ -49,11 +119,88 @@ pub enum ViewType {
     AppLogs,
     ContainerList,
     ContainerDetails(ContainerId),
+    ContainerGraphs(ContainerId),
     ContainerLogs(ContainerId),
+    ContainerStats,
+    Contexts,
     DockerInfo,
+    Events,
     ImagesList,
 }
 
+impl ViewType {
+    /// Returns the view with the given name, among the ones that can be opened on startup without
+    /// any argument.
+    pub fn from_name(name: &str) -> Option<ViewType> {
+        match name {
+            "containers" => Some(ViewType::ContainerList),
+            "images" => Some(ViewType::ImagesList),
+            "info" => Some(ViewType::DockerInfo),
+            "stats" => Some(ViewType::ContainerStats),
+            "events" => Some(ViewType::Events),
+            _ => None,
+        }
+    }
+}
+
+/// A column of a table, which can be chosen and ordered in the configuration file.
+pub trait Column: Copy + PartialEq + 'static {
+    /// The rows of the table
+    type Row;
+
+    /// All the columns of the table
+    const ALL: &'static [Self];
+    /// The columns displayed by default, in order
+    const DEFAULT: &'static [Self];
+
+    /// Returns the name of the column in the configuration file.
+    fn name(self) -> &'static str;
+
+    /// Returns the title of the column in the table header.
+    fn title(self) -> &'static str;
+
+    /// Returns the width of the column.
+    fn width(self) -> u16;
+
+    /// Returns the value of this column for the given row, as displayed in the table.
+    fn value(self, row: &Self::Row) -> String;
+
+    /// Compares two rows by the value of this column, to sort the table. The default
+    /// implementation compares the displayed values.
+    fn compare(self, a: &Self::Row, b: &Self::Row) -> Ordering {
+        self.value(a).cmp(&self.value(b))
+    }
+}
+
+/// Parses a list of column names.
+pub fn parse_columns<C: Column>(names: &[String]) -> Result<Vec<C>, String> {
+    names
+        .iter()
+        .map(|name| {
+            C::ALL
+                .iter()
+                .find(|c| c.name() == name)
+                .cloned()
+                .ok_or_else(|| {
+                    let all: Vec<_> = C::ALL.iter().map(|c| c.name()).collect();
+                    format!(
+                        "unknown column {}, expected one of {}",
+                        name,
+                        all.join(", ")
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Returns the name of the given container, without the leading `/`.
+pub fn container_name(container: &Container) -> Option<&str> {
+    container
+        .names
+        .first()
+        .map(|name| name.strip_prefix('/').unwrap_or(name))
+}
+
 pub fn human_duration(d: &Duration) -> String {
     let seconds = d.as_secs();
 
@@ -87,3 +234,34 @@ pub fn human_duration(d: &Duration) -> String {
 
     return format!("{} years", hours / 24 / 365);
 }
+
+/// Parses a timestamp as returned by the Docker API, e.g. `2018-08-28T01:02:03.456789Z`. Only UTC
+/// timestamps are supported, and the fractional part of the seconds is ignored. Returns `None`
+/// for invalid timestamps, or timestamps before the UNIX epoch (the API uses
+/// `0001-01-01T00:00:00Z` for events that never happened).
+pub fn parse_timestamp(s: &str) -> Option<SystemTime> {
+    if !s.ends_with('Z') || s.len() < 20 {
+        return None;
+    }
+    let num = |range: std::ops::Range<usize>| s.get(range).and_then(|n| n.parse::<u64>().ok());
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hours, minutes, seconds) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if year < 1970 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Number of days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let secs = days * 86400 + hours * 3600 + minutes * 60 + seconds;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
@@ -0,0 +1,62 @@
+//! Helpers to check the rendering of the views against golden snapshots, stored as text files in
+//! `src/views/snapshots`.
+//!
+//! A missing or different snapshot fails the test. Run the tests with `UPDATE_SNAPSHOTS=1` to
+//! write the snapshots from the current rendering after an intended change, and review them.
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use tui::{backend::TestBackend, layout::Rect, Terminal};
+
+use crate::views::Draw;
+
+/// Draws the view in an in-memory terminal of the given size, and returns what has been drawn as
+/// text, one line per row. Styles are ignored.
+pub fn render<V: Draw<TestBackend>>(view: &V, width: u16, height: u16) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    view.draw(&mut terminal.get_frame(), Rect::new(0, 0, width, height));
+
+    let buffer = terminal.current_buffer_mut();
+    let mut text = String::new();
+    for y in 0..height {
+        for x in 0..width {
+            text.push_str(&buffer.get(x, y).symbol);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Checks that `actual` matches the snapshot with the given name.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/views/snapshots")
+        .join(format!("{}.txt", name));
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some_and(|v| v == "1") {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = match fs::read_to_string(&path) {
+        Ok(expected) => expected,
+        Err(err) => panic!(
+            "Can't read snapshot {}: {} (run with UPDATE_SNAPSHOTS=1 to create it)\n\
+             actual:\n{}",
+            path.display(),
+            err,
+            actual
+        ),
+    };
+    if expected != actual {
+        panic!(
+            "Rendering doesn't match snapshot {} (run with UPDATE_SNAPSHOTS=1 to update it)\n\
+             expected:\n{}\nactual:\n{}",
+            path.display(),
+            expected,
+            actual
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::views::Column;
+
+/// The order of the rows of a table, which is kept across refreshes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortOrder<C> {
+    /// Column the rows are sorted by, or `None` to keep the order of the daemon
+    pub column: Option<C>,
+    pub descending: bool,
+}
+
+impl<C: Column> SortOrder<C> {
+    /// Keeps the order of the daemon.
+    pub fn new() -> SortOrder<C> {
+        SortOrder {
+            column: None,
+            descending: false,
+        }
+    }
+
+    /// Sorts by the column following the current one, in ascending order. All the columns can be
+    /// sorted by, including the ones that aren't displayed. After the last column, the order of
+    /// the daemon is restored.
+    pub fn next_column(&mut self) {
+        let next = match self.column {
+            None => 0,
+            Some(column) => C::ALL
+                .iter()
+                .position(|&c| c == column)
+                .map_or(0, |i| i + 1),
+        };
+        self.column = C::ALL.get(next).cloned();
+        self.descending = false;
+    }
+
+    /// Switches between ascending and descending order, if the rows are sorted.
+    pub fn reverse(&mut self) {
+        if self.column.is_some() {
+            self.descending = !self.descending;
+        }
+    }
+
+    /// Sorts the rows. Rows that compare equal keep the order of the daemon.
+    pub fn sort(&self, rows: &mut [C::Row]) {
+        if let Some(column) = self.column {
+            rows.sort_by(|a, b| {
+                let ordering = column.compare(a, b);
+                if self.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+    }
+
+    /// Returns a description of the order, e.g. `Sorted by State ▲`, if the rows are sorted by a
+    /// column that isn't among the displayed ones. The order is shown in the header otherwise.
+    pub fn hidden_title(&self, columns: &[C]) -> Option<String> {
+        match self.column {
+            Some(column) if !columns.contains(&column) => {
+                Some(format!("Sorted by {}", self.title(column)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the title of the given column in the header of the table, with an arrow if the
+    /// rows are sorted by it.
+    pub fn title(&self, column: C) -> String {
+        match self.column {
+            Some(c) if c == column => {
+                let arrow = if self.descending { "▼" } else { "▲" };
+                format!("{} {}", column.title(), arrow)
+            }
+            _ => column.title().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Field {
+        Name,
+        Size,
+    }
+
+    impl Column for Field {
+        type Row = (&'static str, u64);
+
+        const ALL: &'static [Field] = &[Field::Name, Field::Size];
+        const DEFAULT: &'static [Field] = Field::ALL;
+
+        fn name(self) -> &'static str {
+            match self {
+                Field::Name => "name",
+                Field::Size => "size",
+            }
+        }
+
+        fn title(self) -> &'static str {
+            match self {
+                Field::Name => "Name",
+                Field::Size => "Size",
+            }
+        }
+
+        fn width(self) -> u16 {
+            10
+        }
+
+        fn value(self, row: &Self::Row) -> String {
+            match self {
+                Field::Name => row.0.to_string(),
+                Field::Size => row.1.to_string(),
+            }
+        }
+
+        fn compare(self, a: &Self::Row, b: &Self::Row) -> std::cmp::Ordering {
+            match self {
+                Field::Name => a.0.cmp(b.0),
+                Field::Size => a.1.cmp(&b.1),
+            }
+        }
+    }
+
+    fn sorted(order: &SortOrder<Field>) -> Vec<&'static str> {
+        let mut rows = vec![("web", 100), ("db", 20), ("cache", 100), ("proxy", 3)];
+        order.sort(&mut rows);
+        rows.into_iter().map(|(name, _)| name).collect()
+    }
+
+    #[test]
+    fn cycles_through_the_columns() {
+        let mut order = SortOrder::new();
+        assert_eq!(sorted(&order), vec!["web", "db", "cache", "proxy"]);
+        order.reverse();
+        assert!(!order.descending);
+
+        order.next_column();
+        assert_eq!(order.column, Some(Field::Name));
+        assert_eq!(sorted(&order), vec!["cache", "db", "proxy", "web"]);
+        order.reverse();
+        assert_eq!(sorted(&order), vec!["web", "proxy", "db", "cache"]);
+
+        // Numbers are compared as numbers, and ties keep their order
+        order.next_column();
+        assert_eq!(order.column, Some(Field::Size));
+        assert!(!order.descending);
+        assert_eq!(sorted(&order), vec!["proxy", "db", "web", "cache"]);
+
+        order.next_column();
+        assert_eq!(order.column, None);
+    }
+
+    #[test]
+    fn header() {
+        let mut order = SortOrder::new();
+        assert_eq!(order.title(Field::Size), "Size");
+        order.column = Some(Field::Size);
+        assert_eq!(order.title(Field::Name), "Name");
+        assert_eq!(order.title(Field::Size), "Size ▲");
+        order.reverse();
+        assert_eq!(order.title(Field::Size), "Size ▼");
+        assert_eq!(order.hidden_title(Field::ALL), None);
+        assert_eq!(
+            order.hidden_title(&[Field::Name]),
+            Some("Sorted by Size ▼".to_string())
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Background worker performing the calls to the Docker daemon, so that the UI doesn't freeze when
+//! the daemon is slow to respond.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender, TryRecvError};
+
+use crate::app::AppEvent;
+use crate::docker::DockerBackend;
+
+type Job = Box<dyn FnOnce(&dyn DockerBackend) + Send>;
+
+/// Runs jobs on a background thread, one at a time. Once a job is done, an `AppEvent::Refreshed`
+/// is posted to the main event loop so that the UI gets redrawn.
+pub struct Worker {
+    jobs: Sender<Job>,
+    /// Number of jobs queued or running
+    in_flight: Arc<AtomicUsize>,
+    docker: Arc<dyn DockerBackend>,
+    events: Sender<AppEvent>,
+}
+
+impl Worker {
+    pub fn new(docker: Arc<dyn DockerBackend>, events: Sender<AppEvent>) -> Worker {
+        let (jobs, rx) = unbounded::<Job>();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let counter = in_flight.clone();
+        let (client, done) = (docker.clone(), events.clone());
+        thread::spawn(move || {
+            for job in rx {
+                job(client.as_ref());
+                counter.fetch_sub(1, Ordering::SeqCst);
+                if done.send(AppEvent::Refreshed).is_err() {
+                    // The main loop is gone
+                    break;
+                }
+            }
+        });
+        Worker {
+            jobs,
+            in_flight,
+            docker,
+            events,
+        }
+    }
+
+    /// Runs `f` in the background.
+    pub fn spawn<F>(&self, f: F)
+    where
+        F: FnOnce(&dyn DockerBackend) + Send + 'static,
+    {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        self.jobs.send(Box::new(f)).unwrap();
+    }
+
+    /// Runs `f` in the background. Its result can be retrieved from the returned `Pending` once it
+    /// is done.
+    pub fn run<F, T>(&self, f: F) -> Pending<T>
+    where
+        F: FnOnce(&dyn DockerBackend) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = bounded(1);
+        self.spawn(move |docker| {
+            // Nobody is waiting for the result anymore (e.g. the view has been closed)
+            let _ = tx.send(f(docker));
+        });
+        Pending(Some(rx))
+    }
+
+    /// Runs `f` on its own thread rather than after the queued jobs, so that a long job doesn't
+    /// hold up the refreshes of the views. Its result can be retrieved from the returned `Pending`
+    /// once it is done.
+    pub fn run_in_parallel<F, T>(&self, f: F) -> Pending<T>
+    where
+        F: FnOnce(Arc<dyn DockerBackend>) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = bounded(1);
+        let docker = self.docker.clone();
+        let events = self.events.clone();
+        let in_flight = self.in_flight.clone();
+        in_flight.fetch_add(1, Ordering::SeqCst);
+        thread::spawn(move || {
+            let _ = tx.send(f(docker));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            let _ = events.send(AppEvent::Refreshed);
+        });
+        Pending(Some(rx))
+    }
+
+    /// Returns `true` if some jobs haven't completed yet.
+    pub fn is_busy(&self) -> bool {
+        self.in_flight.load(Ordering::SeqCst) > 0
+    }
+}
+
+/// The result of a job started with `Worker::run()`, which may not be available yet.
+pub struct Pending<T>(Option<Receiver<T>>);
+
+impl<T> Pending<T> {
+    /// Returns a `Pending` that is not waiting for any result.
+    pub fn none() -> Pending<T> {
+        Pending(None)
+    }
+
+    /// Returns `true` if the result hasn't been retrieved yet.
+    pub fn is_waiting(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Returns the result if it is available. It can only be retrieved once.
+    pub fn take(&mut self) -> Option<T> {
+        let result = match self.0 {
+            Some(ref rx) => rx.try_recv(),
+            None => return None,
+        };
+        match result {
+            Ok(result) => {
+                self.0 = None;
+                Some(result)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                // The job didn't complete
+                self.0 = None;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::fake::FakeDocker;
+
+    #[test]
+    fn parallel_jobs_dont_wait_for_the_queue() {
+        let (tx, events) = unbounded();
+        let worker = Worker::new(Arc::new(FakeDocker::new()), tx);
+        let (release, blocked) = unbounded::<()>();
+        let mut queued = worker.run(move |_| blocked.recv().is_ok());
+        let mut parallel = worker.run_in_parallel(|_| 42);
+
+        // The queued job is still blocked, but the parallel one is done
+        events.recv().unwrap();
+        assert_eq!(parallel.take(), Some(42));
+        assert_eq!(queued.take(), None);
+        assert!(worker.is_busy());
+
+        release.send(()).unwrap();
+        events.recv().unwrap();
+        assert_eq!(queued.take(), Some(true));
+        assert!(!worker.is_busy());
+    }
+}